@@ -0,0 +1,17 @@
+//! Copies the workspace `Cargo.lock` into the build output so support
+//! bundles can list the exact dependency versions the backend was built with.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let lock = manifest_dir.join("../../Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+
+    // Building outside the workspace just leaves the list empty
+    let contents = fs::read_to_string(&lock).unwrap_or_default();
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("Cargo.lock");
+    fs::write(out, contents).unwrap();
+}
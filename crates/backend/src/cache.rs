@@ -0,0 +1,375 @@
+//! Cache for files the app can recreate, kept under a size quota.
+//!
+//! Entries are files in the cache directory (see [`crate::paths`]), named
+//! by a hash of their key. The cache keeps an index of their sizes and of
+//! the order they were last used in, and evicts the least recently used
+//! entries whenever the total goes over the quota set by
+//! `AppConfig::cache_quota_mb`. The order survives restarts through the
+//! files' modification times, which are updated when an entry is read.
+
+use crate::fsutil::write_atomic;
+use crate::paths;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::SystemTime;
+
+const MIB: u64 = 1024 * 1024;
+
+/// How much the cache holds and may hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheUsage {
+    pub bytes: u64,
+    pub entries: usize,
+    pub quota_bytes: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    bytes: u64,
+    /// Higher was used more recently.
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    /// Keyed by file name.
+    entries: HashMap<String, Entry>,
+    bytes: u64,
+    quota_bytes: u64,
+    next_use: u64,
+}
+
+impl Index {
+    fn insert(&mut self, name: String, bytes: u64) {
+        let used = self.next_use;
+        self.next_use += 1;
+        self.bytes += bytes;
+        if let Some(old) = self.entries.insert(name, Entry { bytes, used }) {
+            self.bytes -= old.bytes;
+        }
+    }
+
+    fn remove(&mut self, name: &str) -> Option<Entry> {
+        let entry = self.entries.remove(name)?;
+        self.bytes -= entry.bytes;
+        Some(entry)
+    }
+}
+
+/// A size-limited cache in one directory.
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    index: Mutex<Index>,
+}
+
+impl Cache {
+    /// Opens the cache in `dir`, indexing the entries already there and
+    /// evicting down to `quota_bytes`.
+    pub fn open(dir: &Path, quota_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut found = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let metadata = entry.metadata()?;
+            // Skips temporary files of writes in progress
+            if name.starts_with('.') || !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            found.push((modified, name, metadata.len()));
+        }
+        found.sort();
+
+        let mut index = Index {
+            quota_bytes,
+            ..Index::default()
+        };
+        for (_, name, bytes) in found {
+            index.insert(name, bytes);
+        }
+        let cache = Self {
+            dir: dir.to_path_buf(),
+            index: Mutex::new(index),
+        };
+        cache.evict(&mut cache.lock())?;
+        Ok(cache)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Returns the cached contents for `key`, marking it as used.
+    pub fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let name = file_name(key);
+        let mut index = self.lock();
+        let Some(bytes) = index.entries.get(&name).map(|entry| entry.bytes) else {
+            return Ok(None);
+        };
+        let path = self.file(&name);
+        match fs::read(&path) {
+            Ok(contents) => {
+                index.insert(name, bytes);
+                // Keeps the order for the next start; not worth failing over
+                let _ = File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()));
+                Ok(Some(contents))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                index.remove(&name);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stores `contents` for `key`, evicting older entries to stay under the
+    /// quota. Contents larger than the whole quota aren't stored, and any
+    /// older entry for `key` is removed so it isn't returned in their place.
+    pub fn put(&self, key: &str, contents: &[u8]) -> io::Result<()> {
+        let mut index = self.lock();
+        let bytes = contents.len() as u64;
+        let name = file_name(key);
+        if bytes > index.quota_bytes {
+            tracing::debug!(key, bytes, "not caching entry larger than the quota");
+            if index.entries.contains_key(&name) {
+                remove_if_exists(&self.file(&name))?;
+                index.remove(&name);
+            }
+            return Ok(());
+        }
+        write_atomic(&self.file(&name), contents)?;
+        index.insert(name, bytes);
+        self.evict(&mut index)
+    }
+
+    /// Removes the entry for `key`; returns whether there was one.
+    pub fn remove(&self, key: &str) -> io::Result<bool> {
+        let name = file_name(key);
+        let mut index = self.lock();
+        if index.remove(&name).is_none() {
+            return Ok(false);
+        }
+        remove_if_exists(&self.file(&name))?;
+        Ok(true)
+    }
+
+    /// Returns how much the cache holds.
+    pub fn usage(&self) -> CacheUsage {
+        let index = self.lock();
+        CacheUsage {
+            bytes: index.bytes,
+            entries: index.entries.len(),
+            quota_bytes: index.quota_bytes,
+        }
+    }
+
+    /// Changes the quota, evicting entries if the cache is now over it.
+    pub fn set_quota(&self, quota_bytes: u64) -> io::Result<()> {
+        let mut index = self.lock();
+        index.quota_bytes = quota_bytes;
+        self.evict(&mut index)
+    }
+
+    /// Removes every entry and returns the number of bytes freed.
+    ///
+    /// Entries whose file can't be removed stay in the index, and the first
+    /// such error is returned once the others are gone.
+    pub fn clear(&self) -> io::Result<u64> {
+        let mut index = self.lock();
+        let names: Vec<String> = index.entries.keys().cloned().collect();
+        let mut freed = 0;
+        let mut failed = None;
+        for name in names {
+            match remove_if_exists(&self.file(&name)) {
+                Ok(()) => freed += index.remove(&name).map_or(0, |entry| entry.bytes),
+                Err(e) => {
+                    failed.get_or_insert(e);
+                }
+            }
+        }
+        tracing::info!(freed, "cleared cache");
+        match failed {
+            Some(e) => Err(e),
+            None => Ok(freed),
+        }
+    }
+
+    /// Removes the least recently used entries until the cache is within
+    /// its quota.
+    fn evict(&self, index: &mut Index) -> io::Result<()> {
+        if index.bytes <= index.quota_bytes {
+            return Ok(());
+        }
+        let mut by_use: Vec<(u64, String)> = index
+            .entries
+            .iter()
+            .map(|(name, entry)| (entry.used, name.clone()))
+            .collect();
+        by_use.sort();
+        for (_, name) in by_use {
+            if index.bytes <= index.quota_bytes {
+                break;
+            }
+            remove_if_exists(&self.file(&name))?;
+            index.remove(&name);
+            tracing::debug!(name, "evicted cache entry");
+        }
+        Ok(())
+    }
+}
+
+/// Returns the file name of the entry for `key`.
+fn file_name(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the app's cache, opened in its cache directory on first use with
+/// the configured quota.
+pub fn global() -> Option<&'static Cache> {
+    static CACHE: OnceLock<Option<Cache>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| {
+            let dir = &paths::current()?.cache;
+            let quota = u64::from(crate::configcache::current().cache_quota_mb) * MIB;
+            Cache::open(dir, quota)
+                .inspect_err(|e| tracing::error!(error = %e, "could not open cache"))
+                .ok()
+        })
+        .as_ref()
+}
+
+/// Applies a changed quota to the app's cache.
+pub fn set_quota_mb(quota_mb: u32) {
+    if let Some(cache) = global()
+        && let Err(e) = cache.set_quota(u64::from(quota_mb) * MIB)
+    {
+        tracing::warn!(error = %e, "could not evict cache entries");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_get_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::open(dir.path(), 1024).unwrap();
+        assert_eq!(cache.get("a").unwrap(), None);
+
+        cache.put("a", b"first").unwrap();
+        cache.put("a", b"second").unwrap();
+        assert_eq!(cache.get("a").unwrap().unwrap(), b"second");
+        assert_eq!(
+            cache.usage(),
+            CacheUsage {
+                bytes: 6,
+                entries: 1,
+                quota_bytes: 1024
+            }
+        );
+
+        assert!(cache.remove("a").unwrap());
+        assert!(!cache.remove("a").unwrap());
+        assert_eq!(cache.usage().bytes, 0);
+
+        // Contents too large to cache replace the old entry with nothing
+        cache.put("a", b"small").unwrap();
+        cache.put("a", &[0; 2048]).unwrap();
+        assert_eq!(cache.get("a").unwrap(), None);
+        assert_eq!(cache.usage().entries, 0);
+    }
+
+    #[test]
+    fn test_clear_keeps_index_in_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::open(dir.path(), 1024).unwrap();
+        cache.put("a", &[0; 10]).unwrap();
+        cache.put("b", &[0; 20]).unwrap();
+        // A file that can't be removed
+        let stuck = dir.path().join(file_name("b"));
+        fs::remove_file(&stuck).unwrap();
+        fs::create_dir(&stuck).unwrap();
+        fs::write(stuck.join("x"), b"x").unwrap();
+
+        assert!(cache.clear().is_err());
+        assert_eq!(
+            cache.usage(),
+            CacheUsage {
+                bytes: 20,
+                entries: 1,
+                quota_bytes: 1024
+            }
+        );
+        assert_eq!(cache.get("a").unwrap(), None);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::open(dir.path(), 30).unwrap();
+        cache.put("a", &[0; 10]).unwrap();
+        cache.put("b", &[0; 10]).unwrap();
+        cache.put("c", &[0; 10]).unwrap();
+        // Reading "a" makes "b" the least recently used
+        cache.get("a").unwrap();
+        cache.put("d", &[0; 10]).unwrap();
+
+        assert!(cache.get("b").unwrap().is_none());
+        for key in ["a", "c", "d"] {
+            assert!(cache.get(key).unwrap().is_some(), "{} was evicted", key);
+        }
+        assert_eq!(cache.usage().bytes, 30);
+
+        // Too large to ever fit
+        cache.put("huge", &[0; 31]).unwrap();
+        assert!(cache.get("huge").unwrap().is_none());
+        assert_eq!(cache.usage().entries, 3);
+
+        cache.set_quota(10).unwrap();
+        assert_eq!(cache.usage().entries, 1);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_reopen_indexes_existing_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let cache = Cache::open(dir.path(), 1024).unwrap();
+            cache.put("a", b"kept").unwrap();
+        }
+        fs::write(dir.path().join(".partial.tmp"), b"ignored").unwrap();
+
+        let cache = Cache::open(dir.path(), 1024).unwrap();
+        assert_eq!(cache.usage().entries, 1);
+        assert_eq!(cache.get("a").unwrap().unwrap(), b"kept");
+
+        assert_eq!(cache.clear().unwrap(), 4);
+        assert_eq!(cache.usage().entries, 0);
+        assert!(cache.get("a").unwrap().is_none());
+    }
+}
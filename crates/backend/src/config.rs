@@ -1,22 +1,64 @@
 //! Application configuration with file persistence.
 //!
-//! Config is stored in the platform-appropriate config directory:
+//! The file is read and written through a [`ConfigStore`]. By default it is
+//! stored in the platform-appropriate config directory, unless
+//! [`paths`] moved it under another root:
 //! - Linux: ~/.config/{{project-name}}/config.json
 //! - macOS: ~/Library/Application Support/com.example.{{project-name}}/config.json
 //! - Windows: %APPDATA%\example\{{project-name}}\config\config.json
+//!
+//! The file may also be `config.toml` or `config.yaml` (or `.yml`); see
+//! [`configformat`](crate::configformat) for which is used and how each is
+//! written.
+//!
+//! Every load returns the file's revision, a hash of the settings it holds.
+//! Saving with the revision that was loaded fails with
+//! [`ConfigError::Conflict`] if another window or process saved in between,
+//! instead of silently overwriting its changes. Saves hold the store's
+//! lock, an advisory lock on `config.lock` for directories, so the check
+//! and the write happen as one step.
+//!
+//! Single settings are changed with [`AppConfig::update`], which applies a
+//! JSON Merge Patch or sets a JSON Pointer against the stored config under
+//! the same lock, so settings the caller didn't touch keep their stored
+//! values.
 
-use directories::ProjectDirs;
+use crate::configformat::Format;
+use crate::configstore::{self, ConfigStore};
+use crate::patch;
+use crate::paths;
+use schemars::{JsonSchema, generate::SchemaSettings};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// Error type for configuration operations.
 #[derive(Debug)]
 pub enum ConfigError {
     Io(io::Error),
     Json(serde_json::Error),
+    /// A TOML or YAML file couldn't be read or written.
+    Format {
+        format: Format,
+        message: String,
+    },
     NoConfigDir,
+    /// A dotted key that doesn't name a setting.
+    UnknownKey(String),
+    /// A setting has a value it can't take.
+    Invalid(String),
+    /// No saved profile has this name.
+    NoProfile(String),
+    /// The file changed since it was loaded; holds what it contains now.
+    Conflict {
+        revision: String,
+        current: Box<AppConfig>,
+    },
 }
 
 impl std::fmt::Display for ConfigError {
@@ -24,7 +66,16 @@ impl std::fmt::Display for ConfigError {
         match self {
             ConfigError::Io(e) => write!(f, "IO error: {}", e),
             ConfigError::Json(e) => write!(f, "JSON error: {}", e),
+            ConfigError::Format { format, message } => write!(f, "{} error: {}", format, message),
             ConfigError::NoConfigDir => write!(f, "Could not determine config directory"),
+            ConfigError::UnknownKey(key) => write!(f, "Unknown config key: {}", key),
+            ConfigError::Invalid(message) => write!(f, "Invalid config: {}", message),
+            ConfigError::NoProfile(name) => write!(f, "No profile named {:?}", name),
+            ConfigError::Conflict { revision, .. } => write!(
+                f,
+                "Config was changed elsewhere since it was loaded (now at revision {})",
+                revision
+            ),
         }
     }
 }
@@ -43,23 +94,170 @@ impl From<serde_json::Error> for ConfigError {
     }
 }
 
+/// Revision of a config file that doesn't exist.
+pub const NO_REVISION: &str = "";
+
+/// Returns the revision of config file contents that aren't a valid config.
+///
+/// Valid files get the revision of the config they hold; see
+/// [`AppConfig::revision`].
+pub fn revision_of(contents: &[u8]) -> String {
+    Sha256::digest(contents)[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// A config and the revision of the file it was loaded from.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Versioned {
+    pub config: AppConfig,
+    pub revision: String,
+}
+
+impl Versioned {
+    /// Pairs a config with its revision.
+    pub fn new(config: AppConfig) -> Result<Self, ConfigError> {
+        Ok(Self {
+            revision: config.revision()?,
+            config,
+        })
+    }
+}
+
+/// Result of a save as reported to the renderer.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SaveOutcome {
+    Saved { revision: String, config: AppConfig },
+    Conflict { revision: String, config: AppConfig },
+    Failed { error: String },
+}
+
+impl From<Result<Versioned, ConfigError>> for SaveOutcome {
+    fn from(result: Result<Versioned, ConfigError>) -> Self {
+        match result {
+            Ok(saved) => SaveOutcome::Saved {
+                revision: saved.revision,
+                config: saved.config,
+            },
+            Err(ConfigError::Conflict { revision, current }) => SaveOutcome::Conflict {
+                revision,
+                config: *current,
+            },
+            Err(e) => SaveOutcome::Failed {
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
 /// Application configuration that persists to disk.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "Settings")]
 pub struct AppConfig {
+    /// Your name, shown in the app and removed from logs and support bundles.
     #[serde(default)]
+    #[schemars(title = "Username")]
     pub username: String,
 
+    /// Use the dark color scheme.
     #[serde(default)]
+    #[schemars(title = "Dark mode")]
     pub dark_mode: bool,
 
+    /// Show desktop notifications.
     #[serde(default = "default_true")]
+    #[schemars(title = "Enable notifications")]
     pub notifications_enabled: bool,
 
+    /// Seconds between automatic saves.
     #[serde(default = "default_auto_save_interval")]
+    #[schemars(title = "Auto-save interval (seconds)", range(min = 1, max = 86400))]
     pub auto_save_interval: u32,
 
+    /// Color theme, or "system" to follow the operating system.
     #[serde(default = "default_theme")]
+    #[schemars(title = "Theme", extend("enum" = ["system", "light", "dark"]))]
     pub theme: String,
+
+    /// Warn when the app's total memory exceeds this many MiB (0 disables the warning).
+    #[serde(default)]
+    #[schemars(title = "Memory warning (MiB)", range(max = 1048576))]
+    pub memory_warning_mb: u32,
+
+    /// How long recorded metrics history is kept at each resolution.
+    #[serde(default)]
+    #[schemars(title = "Metrics history")]
+    pub metrics_retention: MetricsRetention,
+
+    /// Log levels and log file rotation.
+    #[serde(default)]
+    #[schemars(title = "Logging")]
+    pub logging: LoggingConfig,
+
+    /// Size the cache is kept under, in MiB; least recently used entries
+    /// are evicted beyond it.
+    #[serde(default = "default_cache_quota_mb")]
+    #[schemars(title = "Cache size limit (MiB)", range(max = 1048576))]
+    pub cache_quota_mb: u32,
+}
+
+/// Retention periods for the on-disk metrics history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MetricsRetention {
+    /// Hours of full-resolution samples to keep.
+    #[schemars(title = "Full-resolution history (hours)", range(min = 1, max = 8760))]
+    pub raw_hours: u32,
+    /// Days of one-minute roll-ups to keep.
+    #[schemars(title = "Per-minute history (days)", range(min = 1, max = 366))]
+    pub minute_days: u32,
+    /// Days of one-hour roll-ups to keep.
+    #[schemars(title = "Hourly history (days)", range(min = 1, max = 3660))]
+    pub hour_days: u32,
+}
+
+/// Log levels and rotation limits for the backend log file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Level for modules without their own entry.
+    #[schemars(
+        title = "Log level",
+        extend("enum" = ["trace", "debug", "info", "warn", "error", "off"])
+    )]
+    pub level: String,
+    /// Levels for individual modules, keyed by target such as "backend::config".
+    #[schemars(title = "Module log levels")]
+    pub modules: BTreeMap<String, String>,
+    /// Size at which the log file is rotated.
+    #[schemars(title = "Log file size (bytes)", range(min = 1))]
+    pub max_file_bytes: u64,
+    /// Number of rotated files kept besides the current one.
+    #[schemars(title = "Rotated log files", range(max = 100))]
+    pub max_files: u32,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            max_file_bytes: 5 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+impl Default for MetricsRetention {
+    fn default() -> Self {
+        Self {
+            raw_hours: 24,
+            minute_days: 7,
+            hour_days: 365,
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -74,6 +272,10 @@ fn default_theme() -> String {
     "system".to_string()
 }
 
+fn default_cache_quota_mb() -> u32 {
+    256
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
@@ -82,57 +284,445 @@ impl Default for AppConfig {
             notifications_enabled: true,
             auto_save_interval: 300,
             theme: "system".to_string(),
+            memory_warning_mb: 0,
+            metrics_retention: MetricsRetention::default(),
+            logging: LoggingConfig::default(),
+            cache_quota_mb: default_cache_quota_mb(),
         }
     }
 }
 
 impl AppConfig {
-    /// Returns the configuration directory path for this application.
+    /// Returns the configuration directory path for this application; see
+    /// [`paths`] for portable mode and other roots.
     pub fn config_dir() -> Option<PathBuf> {
-        ProjectDirs::from("com", "example", "{{project-name}}")
-            .map(|dirs| dirs.config_dir().to_path_buf())
+        paths::current().map(|paths| paths.config.clone())
+    }
+
+    /// Returns the data directory path for this application.
+    pub fn data_dir() -> Option<PathBuf> {
+        paths::current().map(|paths| paths.data.clone())
     }
 
-    /// Returns the full path to the config file.
+    /// Returns the full path to the config file, if the config is kept in one.
     pub fn config_path() -> Option<PathBuf> {
-        Self::config_dir().map(|dir| dir.join("config.json"))
+        configstore::default_store().path()
     }
 
-    /// Loads configuration from disk, returning defaults if file doesn't exist or is invalid.
+    /// Loads configuration, returning defaults if file doesn't exist or is invalid.
     pub fn load() -> Result<Self, ConfigError> {
-        let path = Self::config_path().ok_or(ConfigError::NoConfigDir)?;
+        Self::load_versioned().map(|loaded| loaded.config)
+    }
+
+    /// Like [`AppConfig::load`], but also returns the file's revision.
+    pub fn load_versioned() -> Result<Versioned, ConfigError> {
+        Self::load_from(&*configstore::default_store())
+    }
+
+    /// Loads the config in `store`, falling back to defaults if there is
+    /// none or it is invalid.
+    pub fn load_from(store: &(impl ConfigStore + ?Sized)) -> Result<Versioned, ConfigError> {
+        let Some(contents) = store.read()? else {
+            return Ok(Versioned::default());
+        };
 
-        if !path.exists() {
-            return Ok(Self::default());
+        // If the file is invalid, return defaults rather than failing
+        match store.format().parse::<AppConfig>(&contents) {
+            Ok(config) => Versioned::new(config),
+            Err(e) => {
+                tracing::warn!(path = ?store.path(), error = %e, "config file is invalid, using defaults");
+                Ok(Versioned {
+                    config: Self::default(),
+                    revision: revision_of(&contents),
+                })
+            }
         }
+    }
 
-        let contents = fs::read_to_string(&path)?;
+    /// Loads the config and its revision, failing if the file is invalid
+    /// instead of falling back to defaults.
+    pub fn load_strict() -> Result<Versioned, ConfigError> {
+        Self::load_strict_from(&*configstore::default_store())
+    }
 
-        // If JSON is invalid, return defaults rather than failing
-        match serde_json::from_str(&contents) {
-            Ok(config) => Ok(config),
-            Err(_) => Ok(Self::default()),
+    /// Like [`AppConfig::load_strict`] for the config in `store`.
+    pub fn load_strict_from(store: &(impl ConfigStore + ?Sized)) -> Result<Versioned, ConfigError> {
+        match store.read()? {
+            Some(contents) => Versioned::new(store.format().parse(&contents)?),
+            None => Ok(Versioned::default()),
         }
     }
 
-    /// Saves configuration to disk, creating the config directory if needed.
-    pub fn save(&self) -> Result<(), ConfigError> {
-        let dir = Self::config_dir().ok_or(ConfigError::NoConfigDir)?;
-        let path = dir.join("config.json");
+    /// Saves configuration, creating the config directory if needed, and
+    /// returns the new revision.
+    ///
+    /// With `expected` set, the save fails with [`ConfigError::Conflict`]
+    /// unless the file is still at that revision. A file that isn't valid
+    /// config is kept as the backup before it is replaced.
+    pub fn save(&self, expected: Option<&str>) -> Result<String, ConfigError> {
+        self.save_in(&*configstore::default_store(), expected)
+    }
+
+    /// Saves configuration in `store`; see [`AppConfig::save`].
+    pub fn save_in(
+        &self,
+        store: &(impl ConfigStore + ?Sized),
+        expected: Option<&str>,
+    ) -> Result<String, ConfigError> {
+        let _lock = store.lock()?;
+        check_revision(store, expected)?;
+        self.write_to(store)
+    }
+
+    /// Changes the stored config with `change` and saves the result, all
+    /// while holding the lock, and returns what was saved.
+    ///
+    /// The result must pass [`AppConfig::check`]. With `expected` set, fails
+    /// with [`ConfigError::Conflict`] unless the file is at that revision.
+    pub fn update(
+        expected: Option<&str>,
+        change: impl FnOnce(AppConfig) -> Result<AppConfig, ConfigError>,
+    ) -> Result<Versioned, ConfigError> {
+        Self::update_in(&*configstore::default_store(), expected, change)
+    }
+
+    /// Like [`AppConfig::update`] for the config in `store`.
+    pub fn update_in(
+        store: &(impl ConfigStore + ?Sized),
+        expected: Option<&str>,
+        change: impl FnOnce(AppConfig) -> Result<AppConfig, ConfigError>,
+    ) -> Result<Versioned, ConfigError> {
+        let _lock = store.lock()?;
+        let current = check_revision(store, expected)?;
 
-        // Create config directory if it doesn't exist
-        fs::create_dir_all(&dir)?;
+        let config = change(current.config)?;
+        config.check()?;
+        let revision = config.write_to(store)?;
+        Ok(Versioned { config, revision })
+    }
 
+    /// Copies the config file in `store` to its backup, e.g. before it is
+    /// reset. Returns false if there is no config file.
+    pub fn backup_in(store: &(impl ConfigStore + ?Sized)) -> Result<bool, ConfigError> {
+        let _lock = store.lock()?;
+        let Some(contents) = store.read()? else {
+            return Ok(false);
+        };
+        store.write_backup(&contents)?;
+        Ok(true)
+    }
+
+    /// Rewrites the config file in `store` in the format `to`, keeping the
+    /// old file as its backup; see [`Format::convert`]. Without a config
+    /// file, the defaults are written in `to`.
+    ///
+    /// Returns false if the file is already in `to`. Fails without changing
+    /// anything if the file isn't a valid config.
+    pub fn convert_in(
+        store: &(impl ConfigStore + ?Sized),
+        to: Format,
+    ) -> Result<bool, ConfigError> {
+        let _lock = store.lock()?;
+        let from = store.format();
+        if from == to {
+            return Ok(false);
+        }
+        let converted = match store.read()? {
+            Some(contents) => {
+                from.parse::<AppConfig>(&contents)?;
+                from.convert(&contents, to)?
+            }
+            None => to.render(&serde_json::to_value(Self::default())?, None)?,
+        };
+        store.replace(to, &converted)?;
+        tracing::info!(%from, %to, path = ?store.path(), "converted config file");
+        Ok(true)
+    }
+
+    /// Returns the revision of a file holding this config.
+    ///
+    /// It is a hash of the config rather than of the file, so it doesn't
+    /// depend on the format or on comments, and a pending save knows its
+    /// revision before the file is written.
+    pub fn revision(&self) -> Result<String, ConfigError> {
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(&path, json)?;
+        Ok(revision_of(json.as_bytes()))
+    }
+
+    /// Writes the config to `store`; the caller holds the lock.
+    fn write_to(&self, store: &(impl ConfigStore + ?Sized)) -> Result<String, ConfigError> {
+        let format = store.format();
+        let previous = store.read()?;
+        // Lenient loads use the defaults in place of a broken file, so keep
+        // it before saving over it in case it was edited by hand
+        if let Some(previous) = &previous
+            && format.parse::<AppConfig>(previous).is_err()
+        {
+            tracing::warn!(path = ?store.path(), "backing up invalid config file before replacing it");
+            store.write_backup(previous)?;
+        }
+
+        let contents = format.render(&serde_json::to_value(self)?, previous.as_deref())?;
+        store.write(&contents)?;
+        self.revision()
+    }
+
+    /// Reads configuration from `path`, failing if the file is missing or
+    /// not a valid config. The format is taken from the extension, and is
+    /// JSON for files without a known one.
+    pub fn read_from(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read(path)?;
+        Format::from_path(path).unwrap_or_default().parse(&contents)
+    }
+
+    /// Returns the problems with values that parse but can't be used.
+    pub fn validate(&self) -> Vec<String> {
+        // The default level is one of the schema's choices, checked below
+        let modules = LoggingConfig {
+            level: "off".to_string(),
+            ..self.logging.clone()
+        };
+        let mut problems: Vec<String> = crate::logging::filter(&modules)
+            .1
+            .into_iter()
+            .map(|entry| format!("unknown log level: logging.{}", entry))
+            .collect();
+        if let Ok(value) = serde_json::to_value(self) {
+            schema_problems(cached_schema(), &value, "", &mut problems);
+        }
+        problems
+    }
+
+    /// Returns the JSON Schema of the config, with subschemas inlined.
+    ///
+    /// Each setting has a title, its doc comment as the description, and the
+    /// range or the choices of values it takes, from the `schemars`
+    /// attributes above. The Settings form is built from it, and
+    /// [`AppConfig::validate`] checks the ranges and choices.
+    pub fn schema() -> Value {
+        cached_schema().clone()
+    }
+
+    /// Fails with [`ConfigError::Invalid`] listing the problems, if there are any.
+    pub fn check(&self) -> Result<(), ConfigError> {
+        let problems = self.validate();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems.join("; ")))
+        }
+    }
 
+    /// Returns a copy with a JSON Merge Patch (RFC 7396) applied.
+    ///
+    /// Fails with [`ConfigError::UnknownKey`] if the patch sets anything the
+    /// config doesn't have, and [`ConfigError::Invalid`] if a value has the
+    /// wrong type. Removing a setting with `null` resets it to its default.
+    pub fn patched(&self, merge_patch: &Value) -> Result<AppConfig, ConfigError> {
+        let mut value = serde_json::to_value(self)?;
+        patch::merge_patch(&mut value, merge_patch);
+        let config: AppConfig =
+            serde_json::from_value(value).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        if let Some(unknown) = patch::unknown_member(merge_patch, &serde_json::to_value(&config)?) {
+            return Err(ConfigError::UnknownKey(unknown));
+        }
+        Ok(config)
+    }
+
+    /// Returns the value at a JSON Pointer such as `/logging/level`.
+    pub fn pointer(&self, pointer: &str) -> Result<Value, ConfigError> {
+        serde_json::to_value(self)?
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| ConfigError::UnknownKey(pointer.to_string()))
+    }
+
+    /// Sets the value at a JSON Pointer, which must name a setting or an
+    /// entry of a map such as `/logging/modules`.
+    pub fn set_pointer(&mut self, pointer: &str, new: Value) -> Result<(), ConfigError> {
+        let tokens = patch::pointer_tokens(pointer)
+            .ok_or_else(|| ConfigError::UnknownKey(pointer.to_string()))?;
+        let mut value = serde_json::to_value(&*self)?;
+        let (parent, field) = parent_of(&mut value, &tokens, pointer)?;
+        parent.insert(field.to_string(), new);
+        self.replace_with(value, pointer)
+    }
+
+    /// Returns the value of a dotted key such as `logging.level`.
+    pub fn get(&self, key: &str) -> Result<Value, ConfigError> {
+        let value = serde_json::to_value(self)?;
+        key.split('.')
+            .try_fold(&value, |value, part| value.get(part))
+            .cloned()
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))
+    }
+
+    /// Sets a dotted key, parsing `raw` as JSON unless the setting is a string.
+    ///
+    /// Keys that don't survive a round trip through [`AppConfig`] are
+    /// unknown, which lets new entries be added to maps such as
+    /// `logging.modules` but nowhere else.
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<(), ConfigError> {
+        let tokens = dotted_tokens(key);
+        let mut value = serde_json::to_value(&*self)?;
+        let (parent, field) = parent_of(&mut value, &tokens, key)?;
+        let new = match parent.get(field) {
+            Some(Value::String(_)) => Value::String(raw.to_string()),
+            _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+        };
+        parent.insert(field.to_string(), new);
+        self.replace_with(value, key)
+    }
+
+    /// Resets a dotted key to its default, or removes it from a map.
+    pub fn unset(&mut self, key: &str) -> Result<(), ConfigError> {
+        let tokens = dotted_tokens(key);
+        let mut value = serde_json::to_value(&*self)?;
+        let (parent, field) = parent_of(&mut value, &tokens, key)?;
+        if parent.remove(field).is_none() {
+            return Err(ConfigError::UnknownKey(key.to_string()));
+        }
+        // Removed fields fall back to their serde defaults
+        *self = serde_json::from_value(value)?;
+        Ok(())
+    }
+
+    /// Returns every setting as a dotted key and its value, in key order.
+    pub fn entries(&self) -> Result<Vec<(String, Value)>, ConfigError> {
+        fn flatten(prefix: &str, value: Value, out: &mut Vec<(String, Value)>) {
+            match value {
+                Value::Object(map) if !map.is_empty() => {
+                    for (key, value) in map {
+                        let key = if prefix.is_empty() {
+                            key
+                        } else {
+                            format!("{}.{}", prefix, key)
+                        };
+                        flatten(&key, value, out);
+                    }
+                }
+                value => out.push((prefix.to_string(), value)),
+            }
+        }
+        let mut out = Vec::new();
+        flatten("", serde_json::to_value(self)?, &mut out);
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(out)
+    }
+
+    /// Replaces `self` with the config in `value`, as changed at `key`.
+    fn replace_with(&mut self, value: Value, key: &str) -> Result<(), ConfigError> {
+        let config: AppConfig = serde_json::from_value(value.clone())
+            .map_err(|e| ConfigError::Invalid(format!("{}: {}", key, e)))?;
+        if serde_json::to_value(&config)? != value {
+            return Err(ConfigError::UnknownKey(key.to_string()));
+        }
+        *self = config;
         Ok(())
     }
 }
 
+/// Returns [`AppConfig::schema`], generated on first use.
+fn cached_schema() -> &'static Value {
+    static SCHEMA: OnceLock<Value> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        SchemaSettings::draft2020_12()
+            .with(|settings| settings.inline_subschemas = true)
+            .into_generator()
+            .into_root_schema_for::<AppConfig>()
+            .to_value()
+    })
+}
+
+/// Adds a problem for each number in `value` outside the range `schema`
+/// gives it, and each string that isn't one of its choices. `key` is the
+/// dotted key of `value`.
+fn schema_problems(schema: &Value, value: &Value, key: &str, problems: &mut Vec<String>) {
+    if let (Some(properties), Some(values)) = (
+        schema.get("properties").and_then(Value::as_object),
+        value.as_object(),
+    ) {
+        for (name, property) in properties {
+            if let Some(value) = values.get(name) {
+                let key = match key {
+                    "" => name.clone(),
+                    parent => format!("{}.{}", parent, name),
+                };
+                schema_problems(property, value, &key, problems);
+            }
+        }
+        return;
+    }
+    if let Some(text) = value.as_str()
+        && let Some(choices) = schema.get("enum").and_then(Value::as_array)
+        && !choices.iter().any(|choice| choice.as_str() == Some(text))
+    {
+        let choices: Vec<&str> = choices.iter().filter_map(Value::as_str).collect();
+        problems.push(format!("{} must be one of {}", key, choices.join(", ")));
+    }
+    let Some(number) = value.as_f64() else {
+        return;
+    };
+    if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+        && number < min
+    {
+        problems.push(format!("{} must be at least {}", key, min));
+    }
+    if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+        && number > max
+    {
+        problems.push(format!("{} must be at most {}", key, max));
+    }
+}
+
+/// Loads the config in `store`, failing with [`ConfigError::Conflict`] if
+/// it isn't at the `expected` revision. The caller holds the lock.
+fn check_revision(
+    store: &(impl ConfigStore + ?Sized),
+    expected: Option<&str>,
+) -> Result<Versioned, ConfigError> {
+    let current = AppConfig::load_from(store)?;
+    if let Some(expected) = expected
+        && current.revision != expected
+    {
+        tracing::info!(expected, current = %current.revision, "config save conflict");
+        return Err(ConfigError::Conflict {
+            revision: current.revision,
+            current: Box::new(current.config),
+        });
+    }
+    Ok(current)
+}
+
+fn dotted_tokens(key: &str) -> Vec<String> {
+    key.split('.').map(str::to_string).collect()
+}
+
+/// Returns the object holding the last of `tokens`, and that token.
+///
+/// `key` is the key the tokens came from, for errors.
+fn parent_of<'a, 't>(
+    value: &'a mut Value,
+    tokens: &'t [String],
+    key: &str,
+) -> Result<(&'a mut Map<String, Value>, &'t str), ConfigError> {
+    let unknown = || ConfigError::UnknownKey(key.to_string());
+    let (field, path) = tokens.split_last().ok_or_else(unknown)?;
+    let parent = path
+        .iter()
+        .try_fold(value, |value, token| value.get_mut(token))
+        .and_then(Value::as_object_mut)
+        .ok_or_else(unknown)?;
+    if field.is_empty() {
+        return Err(unknown());
+    }
+    Ok((parent, field))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configstore::{DirStore, MemoryStore};
 
     #[test]
     fn test_default_config() {
@@ -142,6 +732,10 @@ mod tests {
         assert!(config.notifications_enabled);
         assert_eq!(config.auto_save_interval, 300);
         assert_eq!(config.theme, "system");
+        assert_eq!(config.memory_warning_mb, 0);
+        assert_eq!(config.metrics_retention.raw_hours, 24);
+        assert_eq!(config.logging.level, "info");
+        assert_eq!(config.cache_quota_mb, 256);
     }
 
     #[test]
@@ -152,6 +746,18 @@ mod tests {
             notifications_enabled: false,
             auto_save_interval: 60,
             theme: "dark".to_string(),
+            memory_warning_mb: 512,
+            metrics_retention: MetricsRetention {
+                raw_hours: 2,
+                minute_days: 3,
+                hour_days: 30,
+            },
+            logging: LoggingConfig {
+                level: "warn".to_string(),
+                modules: BTreeMap::from([("backend::config".to_string(), "debug".to_string())]),
+                ..LoggingConfig::default()
+            },
+            cache_quota_mb: 64,
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -162,5 +768,354 @@ mod tests {
         assert!(!parsed.notifications_enabled);
         assert_eq!(parsed.auto_save_interval, 60);
         assert_eq!(parsed.theme, "dark");
+        assert_eq!(parsed.memory_warning_mb, 512);
+        assert_eq!(parsed.metrics_retention.minute_days, 3);
+        assert_eq!(parsed.logging.modules["backend::config"], "debug");
+        assert_eq!(parsed.cache_quota_mb, 64);
+    }
+
+    #[test]
+    fn test_partial_retention_uses_defaults() {
+        let parsed: AppConfig =
+            serde_json::from_str(r#"{"metrics_retention": {"raw_hours": 6}}"#).unwrap();
+        assert_eq!(parsed.metrics_retention.raw_hours, 6);
+        assert_eq!(parsed.metrics_retention.hour_days, 365);
+    }
+
+    #[test]
+    fn test_get_set_unset_dotted_keys() {
+        let mut config = AppConfig::default();
+        assert_eq!(config.get("logging.level").unwrap(), "info");
+        assert!(matches!(
+            config.get("logging.nope"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+
+        config.set("username", "42").unwrap();
+        assert_eq!(config.username, "42");
+        config.set("memory_warning_mb", "512").unwrap();
+        assert_eq!(config.memory_warning_mb, 512);
+        config
+            .set("logging.modules.backend::config", "debug")
+            .unwrap();
+        assert_eq!(config.logging.modules["backend::config"], "debug");
+
+        assert!(matches!(
+            config.set("memory_warning_mb", "lots"),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            config.set("colour", "red"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            config.set("theme.name", "x"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert_eq!(config.memory_warning_mb, 512);
+
+        config.unset("memory_warning_mb").unwrap();
+        assert_eq!(config.memory_warning_mb, 0);
+        config.unset("logging.modules.backend::config").unwrap();
+        assert!(config.logging.modules.is_empty());
+        assert!(matches!(
+            config.unset("colour"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_entries_and_validate() {
+        let mut config = AppConfig::default();
+        let entries = config.entries().unwrap();
+        assert!(entries.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(entries.contains(&("metrics_retention.raw_hours".to_string(), Value::from(24))));
+        assert!(entries.contains(&("logging.modules".to_string(), Value::Object(Map::new()))));
+
+        assert!(config.validate().is_empty());
+        config.logging.level = "loud".to_string();
+        config.logging.max_file_bytes = 0;
+        assert_eq!(config.validate().len(), 2);
+    }
+
+    #[test]
+    fn test_schema_describes_settings() {
+        let schema = AppConfig::schema();
+        let properties = schema["properties"].as_object().unwrap();
+        // In declaration order, so the form lists settings the same way
+        assert_eq!(properties.keys().next().unwrap(), "username");
+        assert_eq!(properties["username"]["title"], "Username");
+        assert_eq!(properties["dark_mode"]["type"], "boolean");
+        assert_eq!(properties["theme"]["enum"][0], "system");
+        let raw_hours = &properties["metrics_retention"]["properties"]["raw_hours"];
+        assert_eq!(raw_hours["minimum"], 1);
+        assert_eq!(raw_hours["maximum"], 8760);
+        assert_eq!(raw_hours["default"], 24);
+        assert!(schema.get("$defs").is_none());
+
+        let mut config = AppConfig::default();
+        config.metrics_retention.raw_hours = 0;
+        config.cache_quota_mb = u32::MAX;
+        config.theme = "blue".to_string();
+        config.logging.level = "loud".to_string();
+        assert_eq!(
+            config.validate(),
+            [
+                "theme must be one of system, light, dark",
+                "metrics_retention.raw_hours must be at least 1",
+                "logging.level must be one of trace, debug, info, warn, error, off",
+                "cache_quota_mb must be at most 1048576"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_save_rejects_stale_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let store = DirStore::new(dir.path());
+        let loaded = AppConfig::load_from(&store).unwrap();
+        assert_eq!(loaded.revision, NO_REVISION);
+
+        // Two editors load the same revision; the first save wins
+        let first = AppConfig {
+            username: "first".to_string(),
+            ..AppConfig::default()
+        };
+        let revision = first.save_in(&store, Some(&loaded.revision)).unwrap();
+        assert_eq!(revision, AppConfig::load_from(&store).unwrap().revision);
+
+        let second = AppConfig {
+            username: "second".to_string(),
+            ..AppConfig::default()
+        };
+        match second.save_in(&store, Some(&loaded.revision)) {
+            Err(ConfigError::Conflict {
+                revision: current,
+                current: config,
+            }) => {
+                assert_eq!(current, revision);
+                assert_eq!(config.username, "first");
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+
+        // Saving against the current revision, or without one, overwrites
+        second.save_in(&store, Some(&revision)).unwrap();
+        first.save_in(&store, None).unwrap();
+        assert_eq!(AppConfig::read_from(&path).unwrap().username, "first");
+    }
+
+    #[test]
+    fn test_save_outcome_json() {
+        let conflict = SaveOutcome::from(Err(ConfigError::Conflict {
+            revision: "abc".to_string(),
+            current: Box::new(AppConfig::default()),
+        }));
+        let json = serde_json::to_value(conflict).unwrap();
+        assert_eq!(json["status"], "conflict");
+        assert_eq!(json["revision"], "abc");
+        assert_eq!(json["config"]["theme"], "system");
+
+        let saved = serde_json::to_value(SaveOutcome::from(Ok(Versioned {
+            config: AppConfig::default(),
+            revision: "def".to_string(),
+        })))
+        .unwrap();
+        assert_eq!(saved["status"], "saved");
+        assert_eq!(saved["revision"], "def");
+    }
+
+    #[test]
+    fn test_patched_and_pointer() {
+        let config = AppConfig {
+            theme: "dark".to_string(),
+            auto_save_interval: 60,
+            ..AppConfig::default()
+        };
+        let patched = config
+            .patched(&serde_json::json!({
+                "username": "me",
+                "logging": { "modules": { "backend::config": "debug" } },
+                "auto_save_interval": null
+            }))
+            .unwrap();
+        assert_eq!(patched.username, "me");
+        assert_eq!(patched.logging.modules["backend::config"], "debug");
+        // Untouched settings keep their values and removed ones reset
+        assert_eq!(patched.theme, "dark");
+        assert_eq!(patched.auto_save_interval, 300);
+
+        assert!(matches!(
+            config.patched(&serde_json::json!({ "logging": { "lvl": "warn" } })),
+            Err(ConfigError::UnknownKey(key)) if key == "/logging/lvl"
+        ));
+        assert!(matches!(
+            config.patched(&serde_json::json!({ "memory_warning_mb": "lots" })),
+            Err(ConfigError::Invalid(_))
+        ));
+
+        let mut config = config;
+        assert_eq!(config.pointer("/logging/level").unwrap(), "info");
+        config
+            .set_pointer("/logging/modules/a~1b", Value::from("warn"))
+            .unwrap();
+        assert_eq!(config.logging.modules["a/b"], "warn");
+        assert!(matches!(
+            config.set_pointer("/username", Value::from(1)),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            config.set_pointer("", Value::Null),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            config.pointer("/nope"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_keeps_concurrent_changes() {
+        let store = MemoryStore::new();
+        let stored = AppConfig {
+            theme: "dark".to_string(),
+            ..AppConfig::default()
+        };
+        let revision = stored.save_in(&store, None).unwrap();
+
+        let saved = AppConfig::update_in(&store, Some(&revision), |config| {
+            config.patched(&serde_json::json!({ "dark_mode": true }))
+        })
+        .unwrap();
+        assert!(saved.config.dark_mode);
+        assert_eq!(saved.config.theme, "dark");
+        assert_eq!(
+            AppConfig::load_from(&store).unwrap().revision,
+            saved.revision
+        );
+
+        // Invalid results and stale revisions leave the file alone
+        let invalid = AppConfig::update_in(&store, None, |config| {
+            config.patched(&serde_json::json!({ "logging": { "level": "loud" } }))
+        });
+        assert!(matches!(invalid, Err(ConfigError::Invalid(_))));
+        let stale = AppConfig::update_in(&store, Some(&revision), Ok);
+        assert!(matches!(stale, Err(ConfigError::Conflict { .. })));
+        assert_eq!(
+            AppConfig::load_from(&store).unwrap().revision,
+            saved.revision
+        );
+    }
+
+    #[test]
+    fn test_load_falls_back_only_when_lenient() {
+        let store = MemoryStore::new();
+        let missing = AppConfig::load_strict_from(&store).unwrap();
+        assert_eq!(missing.revision, NO_REVISION);
+
+        store
+            .write(br#"{"username": "me", "theme": "dark"}"#)
+            .unwrap();
+        let loaded = AppConfig::load_from(&store).unwrap();
+        assert_eq!(loaded.config.username, "me");
+        assert_eq!(loaded.config.auto_save_interval, 300);
+        assert_eq!(
+            loaded.revision,
+            AppConfig::load_strict_from(&store).unwrap().revision
+        );
+
+        store.write(b"{not json").unwrap();
+        let broken = AppConfig::load_from(&store).unwrap();
+        assert_eq!(broken.config.username, "");
+        assert_ne!(broken.revision, NO_REVISION);
+        assert!(matches!(
+            AppConfig::load_strict_from(&store),
+            Err(ConfigError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_saving_over_invalid_file_keeps_backup() {
+        let store = MemoryStore::with_contents("{\"username\": 1}");
+        let loaded = AppConfig::load_from(&store).unwrap();
+        loaded
+            .config
+            .save_in(&store, Some(&loaded.revision))
+            .unwrap();
+        assert_eq!(store.read_backup().unwrap().unwrap(), b"{\"username\": 1}");
+
+        // Valid files are only backed up on request
+        AppConfig {
+            username: "next".to_string(),
+            ..AppConfig::default()
+        }
+        .save_in(&store, None)
+        .unwrap();
+        assert_eq!(store.read_backup().unwrap().unwrap(), b"{\"username\": 1}");
+        assert!(AppConfig::backup_in(&store).unwrap());
+        let backup: AppConfig =
+            serde_json::from_slice(&store.read_backup().unwrap().unwrap()).unwrap();
+        assert_eq!(backup.username, "next");
+        assert!(!AppConfig::backup_in(&MemoryStore::new()).unwrap());
+    }
+
+    #[test]
+    fn test_toml_saves_and_conversion() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        let toml = dir.path().join("config.toml");
+        fs::write(&toml, "# Set by IT\ntheme = \"dark\"\n").unwrap();
+
+        let loaded = AppConfig::load_from(&store).unwrap();
+        assert_eq!(loaded.config.theme, "dark");
+        let saved = AppConfig::update_in(&store, Some(&loaded.revision), |config| {
+            config.patched(&serde_json::json!({ "dark_mode": true }))
+        })
+        .unwrap();
+        let contents = fs::read_to_string(&toml).unwrap();
+        assert!(contents.starts_with("# Set by IT\ntheme = \"dark\"\n"));
+        assert!(!dir.path().join("config.json").exists());
+        // Comments don't change the revision
+        fs::write(&toml, format!("# Reviewed\n{}", contents)).unwrap();
+        assert_eq!(
+            AppConfig::load_strict_from(&store).unwrap().revision,
+            saved.revision
+        );
+
+        assert!(AppConfig::convert_in(&store, Format::Yaml).unwrap());
+        assert!(!AppConfig::convert_in(&store, Format::Yaml).unwrap());
+        assert!(dir.path().join("config.toml.bak").exists());
+        let converted = AppConfig::load_strict_from(&store).unwrap();
+        assert_eq!(converted.revision, saved.revision);
+        assert!(converted.config.dark_mode);
+
+        // A broken file is left as it is
+        fs::write(dir.path().join("config.yaml"), "theme: [").unwrap();
+        assert!(matches!(
+            AppConfig::convert_in(&store, Format::Json),
+            Err(ConfigError::Format {
+                format: Format::Yaml,
+                ..
+            })
+        ));
+        assert!(!dir.path().join("config.json").exists());
+    }
+
+    #[test]
+    fn test_read_from_is_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        assert!(matches!(
+            AppConfig::read_from(&path),
+            Err(ConfigError::Io(_))
+        ));
+        fs::write(&path, "{not json").unwrap();
+        assert!(matches!(
+            AppConfig::read_from(&path),
+            Err(ConfigError::Json(_))
+        ));
+        fs::write(&path, r#"{"username": "a"}"#).unwrap();
+        assert_eq!(AppConfig::read_from(&path).unwrap().username, "a");
     }
 }
@@ -0,0 +1,560 @@
+//! Process-wide config held in memory, with write-behind saves.
+//!
+//! Reads are served from memory. Saves update the in-memory config right
+//! away and are written to disk by a background thread once no further
+//! change has come in for [`DEBOUNCE`], so a burst of changes becomes one
+//! atomic write. [`ConfigCache::flush`] writes pending changes immediately
+//! and is called when the app quits.
+//!
+//! Revisions stay meaningful while a write is pending: the revision of a
+//! save is that of the file it will produce. Pending changes are kept as the
+//! difference from the config last read from or written to disk, and are
+//! merged onto the file when flushed, so a change made in the meantime by
+//! another process (e.g. the CLI) to other settings is kept. If the other
+//! process changed one of the same settings, the write fails with
+//! [`ConfigError::Conflict`] and the changes stay pending until a save
+//! against the file's revision settles it.
+
+use crate::config::{AppConfig, ConfigError, Versioned};
+use crate::configstore::{self, ConfigStore};
+use crate::patch;
+use serde_json::Value;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait after the last change before writing.
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// A steady stream of changes is still written at least this often,
+/// counted in debounce periods.
+const MAX_DELAY_DEBOUNCES: u32 = 4;
+
+/// Longest wait before retrying a failed write.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct State {
+    /// The current config and the revision it will have on disk.
+    current: Versioned,
+    /// The config as last read from or written to disk.
+    on_disk: Versioned,
+    /// The file, when another process changed settings that are also
+    /// changed here; nothing is written until a save settles it.
+    conflict: Option<Versioned>,
+    /// When the oldest and newest unwritten changes were made.
+    pending: Option<(Instant, Instant)>,
+    /// Failed background writes in a row, and when to try again.
+    failed: Option<(u32, Instant)>,
+    writer_started: bool,
+}
+
+/// In-memory config for one store.
+#[derive(Debug)]
+pub struct ConfigCache {
+    store: Arc<dyn ConfigStore>,
+    debounce: Duration,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl ConfigCache {
+    /// Loads the config in `store`, falling back to defaults like
+    /// [`AppConfig::load`].
+    pub fn open(store: Arc<dyn ConfigStore>, debounce: Duration) -> Result<Arc<Self>, ConfigError> {
+        let loaded = AppConfig::load_from(&*store)?;
+        Ok(Arc::new(Self {
+            store,
+            debounce,
+            state: Mutex::new(State {
+                on_disk: loaded.clone(),
+                current: loaded,
+                conflict: None,
+                pending: None,
+                failed: None,
+                writer_started: false,
+            }),
+            changed: Condvar::new(),
+        }))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the current config and its revision.
+    pub fn get(&self) -> Versioned {
+        self.lock().current.clone()
+    }
+
+    /// Returns whether changes are waiting to be written.
+    pub fn is_dirty(&self) -> bool {
+        self.lock().pending.is_some()
+    }
+
+    /// Replaces the config; see [`AppConfig::save`] for `expected`.
+    pub fn save(
+        self: &Arc<Self>,
+        config: AppConfig,
+        expected: Option<&str>,
+    ) -> Result<Versioned, ConfigError> {
+        self.change(expected, |_| Ok(config), false)
+    }
+
+    /// Changes the config with `change`; see [`AppConfig::update`].
+    pub fn update(
+        self: &Arc<Self>,
+        expected: Option<&str>,
+        change: impl FnOnce(AppConfig) -> Result<AppConfig, ConfigError>,
+    ) -> Result<Versioned, ConfigError> {
+        self.change(expected, change, true)
+    }
+
+    fn change(
+        self: &Arc<Self>,
+        expected: Option<&str>,
+        change: impl FnOnce(AppConfig) -> Result<AppConfig, ConfigError>,
+        check: bool,
+    ) -> Result<Versioned, ConfigError> {
+        let mut state = self.lock();
+        match state.conflict.take() {
+            // A save against the file's revision settles a conflict: the
+            // caller has seen the file, so the changes it conflicted with
+            // are dropped
+            Some(file) if expected == Some(file.revision.as_str()) => {
+                state.on_disk = file.clone();
+                state.current = file;
+            }
+            // Saving without a revision overwrites, so those changes are
+            // made on top of the file
+            Some(file) if expected.is_none() => {
+                let changes = state.unwritten_changes()?;
+                let rebased = match &changes {
+                    Some(changes) => file.config.patched(changes)?,
+                    None => file.config.clone(),
+                };
+                state.on_disk = file;
+                state.current = Versioned::new(rebased)?;
+            }
+            Some(file) => {
+                let error = ConfigError::Conflict {
+                    revision: file.revision.clone(),
+                    current: Box::new(file.config.clone()),
+                };
+                state.conflict = Some(file);
+                return Err(error);
+            }
+            None => {
+                if let Some(expected) = expected
+                    && state.current.revision != expected
+                {
+                    return Err(ConfigError::Conflict {
+                        revision: state.current.revision.clone(),
+                        current: Box::new(state.current.config.clone()),
+                    });
+                }
+            }
+        }
+
+        let config = change(state.current.config.clone())?;
+        if check {
+            config.check()?;
+        }
+        state.current = Versioned::new(config)?;
+
+        let now = Instant::now();
+        let first = state.pending.map_or(now, |(first, _)| first);
+        state.pending = Some((first, now));
+        if !state.writer_started {
+            state.writer_started = self.start_writer();
+            if !state.writer_started {
+                self.flush_locked(&mut state)?;
+            }
+        }
+        self.changed.notify_all();
+        Ok(state.current.clone())
+    }
+
+    /// Writes pending changes now.
+    pub fn flush(&self) -> Result<(), ConfigError> {
+        let mut state = self.lock();
+        self.flush_locked(&mut state)
+    }
+
+    /// Returns the pending changes as a merge patch, if there are any.
+    pub fn pending_changes(&self) -> Result<Option<Value>, ConfigError> {
+        let state = self.lock();
+        match state.pending {
+            Some(_) => state.unwritten_changes(),
+            None => Ok(None),
+        }
+    }
+
+    fn flush_locked(&self, state: &mut State) -> Result<(), ConfigError> {
+        if state.pending.is_none() {
+            return Ok(());
+        }
+        let on_disk = serde_json::to_value(&state.on_disk.config)?;
+        let changes = state.unwritten_changes()?;
+
+        // Merged onto the file as it is now, which another process may
+        // have changed since it was last read, as long as it didn't change
+        // the same settings
+        let mut conflict = None;
+        let result = AppConfig::update_in(&*self.store, None, |disk| {
+            let revision = disk.revision()?;
+            if revision != state.on_disk.revision
+                && let Some(changes) = &changes
+                && patch::diff(&on_disk, &serde_json::to_value(&disk)?)
+                    .is_some_and(|theirs| patch::overlaps(&theirs, changes))
+            {
+                conflict = Some(Versioned {
+                    config: disk.clone(),
+                    revision: revision.clone(),
+                });
+                return Err(ConfigError::Conflict {
+                    revision,
+                    current: Box::new(disk),
+                });
+            }
+            match &changes {
+                Some(changes) => disk.patched(changes),
+                None => Ok(disk),
+            }
+        });
+        if conflict.is_some() {
+            tracing::warn!("config was changed elsewhere, not writing conflicting changes");
+            state.conflict = conflict;
+        }
+        let written = result?;
+        tracing::debug!(revision = %written.revision, "wrote pending config changes");
+
+        state.on_disk = written.clone();
+        state.current = written;
+        state.pending = None;
+        state.failed = None;
+        state.conflict = None;
+        Ok(())
+    }
+
+    /// Re-reads the config after the file was changed by another process.
+    ///
+    /// Pending changes are written first, on top of the new file. Returns
+    /// `None` if the file holds the config already in memory, as it does
+    /// after the cache's own writes. Fails without changing anything if the
+    /// file isn't valid config, e.g. when it is caught half-way through
+    /// being edited by hand.
+    pub fn reload(&self) -> Result<Option<Versioned>, ConfigError> {
+        let mut state = self.lock();
+        let mut loaded = AppConfig::load_strict_from(&*self.store)?;
+        if loaded.revision == state.current.revision {
+            return Ok(None);
+        }
+        if state.pending.is_some() {
+            self.flush_locked(&mut state)?;
+            loaded = AppConfig::load_strict_from(&*self.store)?;
+        }
+        state.on_disk = loaded.clone();
+        state.current = loaded;
+        Ok(Some(state.current.clone()))
+    }
+
+    /// Starts the thread that writes changes once they have settled. It
+    /// exits when the cache is dropped.
+    fn start_writer(self: &Arc<Self>) -> bool {
+        let cache = Arc::downgrade(self);
+        let spawned = thread::Builder::new()
+            .name("config-writer".to_string())
+            .spawn(move || while write_when_settled(&cache) {});
+        if let Err(e) = &spawned {
+            tracing::error!(error = %e, "could not start config writer, saving immediately");
+        }
+        spawned.is_ok()
+    }
+}
+
+impl State {
+    /// Returns the difference between the config in memory and on disk.
+    fn unwritten_changes(&self) -> Result<Option<Value>, ConfigError> {
+        Ok(patch::diff(
+            &serde_json::to_value(&self.on_disk.config)?,
+            &serde_json::to_value(&self.current.config)?,
+        ))
+    }
+}
+
+/// Waits for pending changes to settle and writes them. Returns false once
+/// the cache is gone.
+fn write_when_settled(cache: &Weak<ConfigCache>) -> bool {
+    let Some(cache) = cache.upgrade() else {
+        return false;
+    };
+    let max_delay = cache.debounce * MAX_DELAY_DEBOUNCES;
+    let mut state = cache.lock();
+    // A conflict waits for a save to settle it
+    let Some((first, last)) = state.pending.filter(|_| state.conflict.is_none()) else {
+        // Wake up now and then to notice the cache being dropped
+        let _ = cache.changed.wait_timeout(state, Duration::from_secs(1));
+        return true;
+    };
+
+    let mut due = (last + cache.debounce).min(first + max_delay);
+    if let Some((_, retry_at)) = state.failed {
+        due = due.max(retry_at);
+    }
+    let now = Instant::now();
+    if now < due {
+        let _ = cache.changed.wait_timeout(state, due - now);
+        return true;
+    }
+    if let Err(e) = cache.flush_locked(&mut state) {
+        // The changes stay pending, so a flush on quit still writes them
+        let failures = state.failed.map_or(1, |(failures, _)| failures + 1);
+        let delay = cache
+            .debounce
+            .saturating_mul(2u32.saturating_pow(failures))
+            .min(MAX_RETRY_DELAY);
+        tracing::error!(error = %e, retry_in = ?delay, "could not write config");
+        state.failed = Some((failures, now + delay));
+    }
+    true
+}
+
+/// Returns the process-wide cache for [`configstore::default_store`].
+pub fn global() -> Option<&'static Arc<ConfigCache>> {
+    static CACHE: OnceLock<Option<Arc<ConfigCache>>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| {
+            ConfigCache::open(configstore::default_store(), DEBOUNCE)
+                .inspect_err(|e| tracing::error!(error = %e, "could not load config"))
+                .ok()
+        })
+        .as_ref()
+}
+
+/// Returns the current config, from the cache where possible.
+pub fn current() -> AppConfig {
+    match global() {
+        Some(cache) => cache.get().config,
+        None => AppConfig::load().unwrap_or_default(),
+    }
+}
+
+/// Writes pending config changes, if there are any.
+pub fn flush() -> Result<(), ConfigError> {
+    global().map_or(Ok(()), |cache| cache.flush())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configformat::Format;
+    use crate::configstore::{DirStore, MemoryStore, StoreLock};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn with_username(username: &str) -> AppConfig {
+        AppConfig {
+            username: username.to_string(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_changes_are_coalesced_until_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(DirStore::new(dir.path()));
+        let cache = ConfigCache::open(store.clone(), Duration::from_secs(60)).unwrap();
+
+        let first = cache.save(with_username("a"), None).unwrap();
+        let second = cache
+            .save(with_username("b"), Some(&first.revision))
+            .unwrap();
+        assert_eq!(cache.get().config.username, "b");
+        assert!(cache.is_dirty());
+        assert!(!dir.path().join("config.json").exists());
+
+        cache.flush().unwrap();
+        assert!(!cache.is_dirty());
+        let on_disk = AppConfig::load_from(&*store).unwrap();
+        assert_eq!(on_disk.config.username, "b");
+        // The revision handed out before the write matches the file
+        assert_eq!(on_disk.revision, second.revision);
+
+        assert!(matches!(
+            cache.save(with_username("c"), Some(&first.revision)),
+            Err(ConfigError::Conflict { .. })
+        ));
+    }
+
+    #[test]
+    fn test_writer_saves_after_debounce() {
+        let store = Arc::new(MemoryStore::new());
+        let cache = ConfigCache::open(store.clone(), Duration::from_millis(20)).unwrap();
+        cache.save(with_username("later"), None).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while cache.is_dirty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            AppConfig::load_strict_from(&*store)
+                .unwrap()
+                .config
+                .username,
+            "later"
+        );
+    }
+
+    /// A store whose first write fails.
+    #[derive(Debug, Default)]
+    struct FailsOnce {
+        inner: MemoryStore,
+        failed: AtomicBool,
+    }
+
+    impl ConfigStore for FailsOnce {
+        fn path(&self) -> Option<PathBuf> {
+            None
+        }
+
+        fn format(&self) -> Format {
+            self.inner.format()
+        }
+
+        fn read(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+            self.inner.read()
+        }
+
+        fn write(&self, contents: &[u8]) -> Result<(), ConfigError> {
+            if !self.failed.swap(true, Ordering::SeqCst) {
+                return Err(std::io::Error::other("disk full").into());
+            }
+            self.inner.write(contents)
+        }
+
+        fn lock(&self) -> Result<StoreLock<'_>, ConfigError> {
+            self.inner.lock()
+        }
+
+        fn read_backup(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+            self.inner.read_backup()
+        }
+
+        fn write_backup(&self, contents: &[u8]) -> Result<(), ConfigError> {
+            self.inner.write_backup(contents)
+        }
+
+        fn replace(&self, format: Format, contents: &[u8]) -> Result<(), ConfigError> {
+            self.inner.replace(format, contents)
+        }
+    }
+
+    #[test]
+    fn test_failed_write_stays_pending() {
+        let store = Arc::new(FailsOnce::default());
+        let cache = ConfigCache::open(store.clone(), Duration::from_millis(10)).unwrap();
+        cache.save(with_username("kept"), None).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !store.failed.load(Ordering::SeqCst) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(store.failed.load(Ordering::SeqCst));
+
+        // As on quit
+        cache.flush().unwrap();
+        assert!(!cache.is_dirty());
+        assert_eq!(
+            AppConfig::load_strict_from(&*store)
+                .unwrap()
+                .config
+                .username,
+            "kept"
+        );
+    }
+
+    #[test]
+    fn test_flush_keeps_changes_made_by_other_processes() {
+        let store = Arc::new(MemoryStore::new());
+        let cache = ConfigCache::open(store.clone(), Duration::from_secs(60)).unwrap();
+
+        cache
+            .update(None, |config| {
+                config.patched(&serde_json::json!({ "dark_mode": true }))
+            })
+            .unwrap();
+        // Another process changes a different setting before the write
+        with_username("cli").save_in(&*store, None).unwrap();
+
+        let reloaded = cache.reload().unwrap().unwrap();
+        assert!(reloaded.config.dark_mode);
+        assert_eq!(reloaded.config.username, "cli");
+        let stored = AppConfig::load_strict_from(&*store).unwrap().config;
+        assert!(stored.dark_mode);
+        assert_eq!(stored.username, "cli");
+
+        // The cache's own write isn't reported as a change
+        cache.save(with_username("app"), None).unwrap();
+        cache.flush().unwrap();
+        assert!(cache.reload().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_flush_reports_conflicting_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(DirStore::new(dir.path()));
+        let cache = ConfigCache::open(store, Duration::from_secs(60)).unwrap();
+        cache.save(with_username("app"), None).unwrap();
+
+        // Another process changes the same setting before the write
+        let other = DirStore::new(dir.path());
+        with_username("cli").save_in(&other, None).unwrap();
+
+        let Err(ConfigError::Conflict { revision, current }) = cache.flush() else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(current.username, "cli");
+        assert!(cache.is_dirty());
+        assert_eq!(
+            cache.pending_changes().unwrap().unwrap(),
+            serde_json::json!({ "username": "app" })
+        );
+        assert_eq!(
+            AppConfig::load_strict_from(&other).unwrap().config.username,
+            "cli"
+        );
+        // Saves against the revision the app had are rejected too
+        let before = cache.get().revision;
+        assert!(matches!(
+            cache.save(with_username("x"), Some(&before)),
+            Err(ConfigError::Conflict { .. })
+        ));
+
+        // Overwriting against the file's revision settles it
+        cache
+            .update(Some(&revision), |config| {
+                config.patched(&serde_json::json!({ "username": "app" }))
+            })
+            .unwrap();
+        cache.flush().unwrap();
+        assert_eq!(
+            AppConfig::load_strict_from(&other).unwrap().config.username,
+            "app"
+        );
+    }
+
+    #[test]
+    fn test_reload_of_invalid_file_keeps_config() {
+        let store = Arc::new(MemoryStore::new());
+        with_username("me").save_in(&*store, None).unwrap();
+        let cache = ConfigCache::open(store.clone(), Duration::from_secs(60)).unwrap();
+        let before = cache.get();
+
+        store.write(b"{\"username\": ").unwrap();
+        assert!(cache.reload().is_err());
+        let after = cache.get();
+        assert_eq!(after.config.username, "me");
+        assert_eq!(after.revision, before.revision);
+    }
+}
@@ -0,0 +1,291 @@
+//! File formats the config can be kept in.
+//!
+//! The app writes JSON, but admins who edit the config by hand can switch it
+//! to TOML or YAML, which allow comments. A directory holding config files
+//! in several formats uses the first in [`Format::PRECEDENCE`].
+//!
+//! Saving to a TOML file writes the settings into the existing document, so
+//! its comments, key order and formatting are kept for the settings that
+//! are still there. JSON and YAML files are written out in full.
+
+use crate::config::ConfigError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// A config file format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    /// Formats in the order they are looked for. TOML and YAML come first
+    /// because a file in either was put there on purpose, while JSON is
+    /// what the app writes by default.
+    pub const PRECEDENCE: [Format; 3] = [Format::Toml, Format::Yaml, Format::Json];
+
+    /// Returns the file extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    /// Returns the extensions a file in the format may have, the one the
+    /// app writes first.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Format::Json => &["json"],
+            Format::Toml => &["toml"],
+            Format::Yaml => &["yaml", "yml"],
+        }
+    }
+
+    /// Returns the format of a file, going by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+
+    /// Parses file contents, e.g. into an `AppConfig` or a JSON value.
+    pub fn parse<T: DeserializeOwned>(self, contents: &[u8]) -> Result<T, ConfigError> {
+        match self {
+            Format::Json => Ok(serde_json::from_slice(contents)?),
+            Format::Toml => toml_edit::de::from_slice(contents).map_err(|e| self.error(e)),
+            Format::Yaml => serde_norway::from_slice(contents).map_err(|e| self.error(e)),
+        }
+    }
+
+    /// Renders `value` as file contents.
+    ///
+    /// For TOML, `previous` is the file being replaced: if it is a TOML
+    /// document, `value` is written into it, keeping its comments and the
+    /// order of its keys.
+    pub fn render(self, value: &Value, previous: Option<&[u8]>) -> Result<Vec<u8>, ConfigError> {
+        match self {
+            Format::Json => Ok(serde_json::to_string_pretty(value)?.into_bytes()),
+            Format::Toml => {
+                let rendered =
+                    toml_edit::ser::to_string_pretty(value).map_err(|e| self.error(e))?;
+                let document = previous
+                    .and_then(|previous| std::str::from_utf8(previous).ok())
+                    .and_then(|previous| previous.parse::<DocumentMut>().ok());
+                let Some(mut document) = document else {
+                    return Ok(rendered.into_bytes());
+                };
+                let fresh: DocumentMut = rendered.parse().map_err(|e| self.error(e))?;
+                merge_table(document.as_table_mut(), fresh.as_table());
+                Ok(document.to_string().into_bytes())
+            }
+            Format::Yaml => Ok(serde_norway::to_string(value)
+                .map_err(|e| self.error(e))?
+                .into_bytes()),
+        }
+    }
+
+    /// Rewrites file contents in the format `to`.
+    ///
+    /// Everything in the file is carried over, including keys the app
+    /// doesn't know; fails rather than drop a value `to` can't hold, such
+    /// as a null in TOML. Comments aren't carried over.
+    pub fn convert(self, contents: &[u8], to: Format) -> Result<Vec<u8>, ConfigError> {
+        let value: Value = self.parse(contents)?;
+        let converted = to.render(&value, None)?;
+        if to.parse::<Value>(&converted)? != value {
+            return Err(ConfigError::Invalid(format!(
+                "the config can't be converted to {} without changing it",
+                to
+            )));
+        }
+        Ok(converted)
+    }
+
+    fn error(self, error: impl fmt::Display) -> ConfigError {
+        ConfigError::Format {
+            format: self,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            Format::Yaml => "YAML",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            _ => Err(format!("unknown config format {:?}", s)),
+        }
+    }
+}
+
+/// Makes `old` hold the same keys and values as `new`, keeping the comments
+/// and position of every key that is in both.
+fn merge_table(old: &mut dyn TableLike, new: &dyn TableLike) {
+    let removed: Vec<String> = old
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in removed {
+        old.remove(&key);
+    }
+    for (key, new_item) in new.iter() {
+        match old.get_mut(key) {
+            Some(old_item) => merge_item(old_item, new_item),
+            None => {
+                old.insert(key, new_item.clone());
+            }
+        }
+    }
+}
+
+fn merge_item(old: &mut Item, new: &Item) {
+    if let (Some(old_table), Some(new_table)) = (old.as_table_like_mut(), new.as_table_like()) {
+        merge_table(old_table, new_table);
+        return;
+    }
+    match (old.as_value_mut(), new.as_value()) {
+        // Left alone so that e.g. a literal string keeps its quotes
+        (Some(old_value), Some(new_value)) if same_value(old_value, new_value) => {}
+        (Some(old_value), Some(new_value)) => {
+            let decor = old_value.decor().clone();
+            *old_value = new_value.clone();
+            *old_value.decor_mut() = decor;
+        }
+        _ => *old = new.clone(),
+    }
+}
+
+/// Returns whether two values are equal, however they are written.
+fn same_value(a: &toml_edit::Value, b: &toml_edit::Value) -> bool {
+    use toml_edit::Value;
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Float(a), Value::Float(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b))
+        }
+        (Value::InlineTable(a), Value::InlineTable(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same_value(a, b)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_names_and_extensions() {
+        assert_eq!(
+            Format::from_path(Path::new("/x/config.toml")),
+            Some(Format::Toml)
+        );
+        assert_eq!(Format::from_path(Path::new("a.YML")), Some(Format::Yaml));
+        assert_eq!(Format::from_path(Path::new("config")), None);
+        for format in Format::PRECEDENCE {
+            assert_eq!(format.extensions()[0], format.extension());
+            for extension in format.extensions() {
+                assert_eq!(extension.parse::<Format>().unwrap(), format);
+            }
+        }
+        assert!("ini".parse::<Format>().is_err());
+        assert_eq!(Format::default(), Format::Json);
+    }
+
+    #[test]
+    fn test_toml_render_keeps_comments_and_order() {
+        let previous = "\
+# Managed by IT
+username = 'alice' # literal quotes
+
+[logging]
+# Raise to debug when asked by support
+level = \"info\"
+max_files = 5
+";
+        let value = json!({
+            "username": "alice",
+            "dark_mode": true,
+            "logging": { "level": "debug" },
+        });
+        let rendered = Format::Toml
+            .render(&value, Some(previous.as_bytes()))
+            .unwrap();
+        let rendered = String::from_utf8(rendered).unwrap();
+        assert_eq!(
+            rendered,
+            "\
+# Managed by IT
+username = 'alice' # literal quotes
+dark_mode = true
+
+[logging]
+# Raise to debug when asked by support
+level = \"debug\"
+"
+        );
+        assert_eq!(
+            Format::Toml.parse::<Value>(rendered.as_bytes()).unwrap(),
+            value
+        );
+
+        // Anything that isn't a TOML document is replaced outright
+        let fresh = Format::Toml.render(&value, Some(b"{}")).unwrap();
+        assert_eq!(Format::Toml.parse::<Value>(&fresh).unwrap(), value);
+    }
+
+    #[test]
+    fn test_convert_keeps_everything_or_fails() {
+        let json =
+            br#"{"username": "a", "extra": {"kept": [1, 2.5]}, "logging": {"level": "warn"}}"#;
+        let original: Value = serde_json::from_slice(json).unwrap();
+        for to in [Format::Toml, Format::Yaml] {
+            let converted = Format::Json.convert(json, to).unwrap();
+            let back = to.convert(&converted, Format::Json).unwrap();
+            assert_eq!(serde_json::from_slice::<Value>(&back).unwrap(), original);
+        }
+
+        assert!(matches!(
+            Format::Json.convert(br#"{"gone": null}"#, Format::Toml),
+            Err(ConfigError::Format { .. } | ConfigError::Invalid(_))
+        ));
+        assert!(matches!(
+            Format::Toml.convert(b"not = [valid", Format::Json),
+            Err(ConfigError::Format {
+                format: Format::Toml,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,458 @@
+//! Where the config file is kept.
+//!
+//! [`AppConfig`](crate::config::AppConfig) reads and writes its file through
+//! a [`ConfigStore`], so the same load, save and update logic works against
+//! the platform config directory, a directory chosen at runtime, or memory
+//! in tests. The app uses [`default_store`], which is the platform
+//! directory unless [`set_default`] picked another store at startup.
+
+use crate::config::{AppConfig, ConfigError};
+use crate::configformat::Format;
+use crate::fsutil::write_atomic;
+use std::fmt::Debug;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Storage for the config file and its backup.
+pub trait ConfigStore: Debug + Send + Sync {
+    /// Returns the path of the config file, if it is kept in one.
+    fn path(&self) -> Option<PathBuf>;
+
+    /// Returns the format of the config file, which it is also saved in.
+    fn format(&self) -> Format;
+
+    /// Returns the contents of the config file, or `None` if there is none.
+    fn read(&self) -> Result<Option<Vec<u8>>, ConfigError>;
+
+    /// Replaces the config file. Readers see the old or the new contents,
+    /// never a mix.
+    fn write(&self, contents: &[u8]) -> Result<(), ConfigError>;
+
+    /// Takes the store's lock, which is held across processes where the
+    /// store allows it and released when the returned value is dropped.
+    fn lock(&self) -> Result<StoreLock<'_>, ConfigError>;
+
+    /// Returns the contents of the backup, or `None` if there is none.
+    fn read_backup(&self) -> Result<Option<Vec<u8>>, ConfigError>;
+
+    /// Replaces the backup.
+    fn write_backup(&self, contents: &[u8]) -> Result<(), ConfigError>;
+
+    /// Replaces the config file with `contents` in `format`. A file in
+    /// another format is kept as the backup for that format, so it is no
+    /// longer read.
+    fn replace(&self, format: Format, contents: &[u8]) -> Result<(), ConfigError>;
+}
+
+/// Keeps a store locked until it is dropped.
+pub struct StoreLock<'a> {
+    _held: Box<dyn Held + 'a>,
+}
+
+trait Held {}
+
+impl<T> Held for T {}
+
+impl<'a> StoreLock<'a> {
+    /// Wraps whatever holds the lock, such as a locked file or a mutex guard.
+    pub fn new<T: 'a>(held: T) -> Self {
+        Self {
+            _held: Box::new(held),
+        }
+    }
+}
+
+/// A config file and its backup in a directory, which is created on the
+/// first write.
+///
+/// The file is `config.toml`, `config.yaml` (or `config.yml`) or
+/// `config.json`, whichever is first in [`Format::PRECEDENCE`]; a new one is
+/// `config.json`. The backup is the file's name followed by `.bak`.
+#[derive(Debug, Clone)]
+pub struct DirStore {
+    dir: PathBuf,
+}
+
+impl DirStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn file_in(&self, format: Format) -> PathBuf {
+        self.dir.join(format!("config.{}", format.extension()))
+    }
+
+    /// Returns the config file that exists, and its format.
+    fn existing(&self) -> Option<(Format, PathBuf)> {
+        Format::PRECEDENCE.into_iter().find_map(|format| {
+            format
+                .extensions()
+                .iter()
+                .map(|extension| self.dir.join(format!("config.{extension}")))
+                .find(|path| path.is_file())
+                .map(|path| (format, path))
+        })
+    }
+
+    fn file(&self) -> PathBuf {
+        self.existing()
+            .map(|(_, path)| path)
+            .unwrap_or_else(|| self.file_in(Format::default()))
+    }
+
+    fn backup(&self) -> PathBuf {
+        backup_of(&self.file())
+    }
+}
+
+impl ConfigStore for DirStore {
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.file())
+    }
+
+    fn format(&self) -> Format {
+        self.existing()
+            .map(|(format, _)| format)
+            .unwrap_or_default()
+    }
+
+    fn read(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        Ok(read_if_exists(&self.file())?)
+    }
+
+    fn write(&self, contents: &[u8]) -> Result<(), ConfigError> {
+        fs::create_dir_all(&self.dir)?;
+        // Written atomically so the app never reads a half-written file
+        // saved by the CLI, and vice versa
+        write_atomic(&self.file(), contents)?;
+        tracing::debug!(path = %self.file().display(), "saved config");
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<StoreLock<'_>, ConfigError> {
+        Ok(StoreLock::new(lock_dir(&self.dir)?))
+    }
+
+    fn read_backup(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        Ok(read_if_exists(&self.backup())?)
+    }
+
+    fn write_backup(&self, contents: &[u8]) -> Result<(), ConfigError> {
+        fs::create_dir_all(&self.dir)?;
+        write_atomic(&self.backup(), contents)?;
+        Ok(())
+    }
+
+    fn replace(&self, format: Format, contents: &[u8]) -> Result<(), ConfigError> {
+        let old = self.existing().map(|(_, path)| path);
+        let new = self.file_in(format);
+        fs::create_dir_all(&self.dir)?;
+        write_atomic(&new, contents)?;
+        if let Some(old) = old
+            && old != new
+        {
+            fs::rename(&old, backup_of(&old))?;
+        }
+        tracing::debug!(path = %new.display(), "replaced config");
+        Ok(())
+    }
+}
+
+/// Returns the backup path for a config file: its name followed by `.bak`.
+fn backup_of(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// The platform config directory; see [`AppConfig::config_dir`].
+///
+/// Fails with [`ConfigError::NoConfigDir`] on platforms without one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlatformStore;
+
+impl PlatformStore {
+    fn dir_store(&self) -> Result<DirStore, ConfigError> {
+        AppConfig::config_dir()
+            .map(DirStore::new)
+            .ok_or(ConfigError::NoConfigDir)
+    }
+}
+
+impl ConfigStore for PlatformStore {
+    fn path(&self) -> Option<PathBuf> {
+        self.dir_store().ok()?.path()
+    }
+
+    fn format(&self) -> Format {
+        self.dir_store()
+            .map(|store| store.format())
+            .unwrap_or_default()
+    }
+
+    fn read(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        self.dir_store()?.read()
+    }
+
+    fn write(&self, contents: &[u8]) -> Result<(), ConfigError> {
+        self.dir_store()?.write(contents)
+    }
+
+    fn lock(&self) -> Result<StoreLock<'_>, ConfigError> {
+        Ok(StoreLock::new(lock_dir(self.dir_store()?.dir())?))
+    }
+
+    fn read_backup(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        self.dir_store()?.read_backup()
+    }
+
+    fn write_backup(&self, contents: &[u8]) -> Result<(), ConfigError> {
+        self.dir_store()?.write_backup(contents)
+    }
+
+    fn replace(&self, format: Format, contents: &[u8]) -> Result<(), ConfigError> {
+        self.dir_store()?.replace(format, contents)
+    }
+}
+
+/// A config file kept in memory, for tests. The lock only excludes other
+/// users of the same store.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    files: Mutex<MemoryFiles>,
+    lock: Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryFiles {
+    format: Format,
+    config: Option<Vec<u8>>,
+    backup: Option<Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a store whose config file holds `contents`.
+    pub fn with_contents(contents: impl Into<Vec<u8>>) -> Self {
+        let store = Self::new();
+        store.files().config = Some(contents.into());
+        store
+    }
+
+    fn files(&self) -> std::sync::MutexGuard<'_, MemoryFiles> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ConfigStore for MemoryStore {
+    fn path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn format(&self) -> Format {
+        self.files().format
+    }
+
+    fn read(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        Ok(self.files().config.clone())
+    }
+
+    fn write(&self, contents: &[u8]) -> Result<(), ConfigError> {
+        self.files().config = Some(contents.to_vec());
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<StoreLock<'_>, ConfigError> {
+        Ok(StoreLock::new(
+            self.lock.lock().unwrap_or_else(|e| e.into_inner()),
+        ))
+    }
+
+    fn read_backup(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        Ok(self.files().backup.clone())
+    }
+
+    fn write_backup(&self, contents: &[u8]) -> Result<(), ConfigError> {
+        self.files().backup = Some(contents.to_vec());
+        Ok(())
+    }
+
+    fn replace(&self, format: Format, contents: &[u8]) -> Result<(), ConfigError> {
+        let mut files = self.files();
+        if files.format != format {
+            files.backup = files.config.take();
+        }
+        files.format = format;
+        files.config = Some(contents.to_vec());
+        Ok(())
+    }
+}
+
+/// Copies the config file from `from` to `to`, unless `to` already has one.
+///
+/// Returns whether anything was copied. Used when the app is pointed at a
+/// new directory, so it starts out with the settings it had before.
+pub fn migrate(
+    from: &(impl ConfigStore + ?Sized),
+    to: &(impl ConfigStore + ?Sized),
+) -> Result<bool, ConfigError> {
+    let _lock = to.lock()?;
+    if to.read()?.is_some() {
+        return Ok(false);
+    }
+    let Some(contents) = from.read()? else {
+        return Ok(false);
+    };
+    to.write(&contents)?;
+    tracing::info!(from = ?from.path(), to = ?to.path(), "copied config to new location");
+    Ok(true)
+}
+
+fn default_cell() -> &'static OnceLock<Arc<dyn ConfigStore>> {
+    static DEFAULT: OnceLock<Arc<dyn ConfigStore>> = OnceLock::new();
+    &DEFAULT
+}
+
+/// Makes `store` the one used by [`AppConfig::load`], [`AppConfig::save`]
+/// and the app. Must be called before the config is first used; returns
+/// false if a store was already chosen.
+pub fn set_default(store: Arc<dyn ConfigStore>) -> bool {
+    default_cell().set(store).is_ok()
+}
+
+/// Returns the store chosen with [`set_default`], or the platform directory.
+pub fn default_store() -> Arc<dyn ConfigStore> {
+    default_cell()
+        .get_or_init(|| Arc::new(PlatformStore))
+        .clone()
+}
+
+/// Creates `dir` if needed and takes the lock on its config, which is held
+/// until the returned file is dropped.
+fn lock_dir(dir: &Path) -> io::Result<fs::File> {
+    fs::create_dir_all(dir)?;
+    // The config file itself is replaced on every save, so the lock is kept on a
+    // separate file
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("config.lock"))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+/// Reads a file, returning `None` if it doesn't exist.
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path().join("nested"));
+        assert_eq!(store.read().unwrap(), None);
+
+        store.write(b"{}").unwrap();
+        store.write_backup(b"old").unwrap();
+        assert_eq!(store.read().unwrap().unwrap(), b"{}");
+        assert_eq!(store.read_backup().unwrap().unwrap(), b"old");
+        assert_eq!(
+            store.path().unwrap(),
+            dir.path().join("nested").join("config.json")
+        );
+        assert!(dir.path().join("nested/config.json.bak").exists());
+    }
+
+    #[test]
+    fn test_dir_store_format_precedence_and_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        assert_eq!(store.format(), Format::Json);
+
+        fs::write(dir.path().join("config.json"), "{}").unwrap();
+        fs::write(dir.path().join("config.yaml"), "username: yaml").unwrap();
+        assert_eq!(store.format(), Format::Yaml);
+        assert_eq!(store.path().unwrap(), dir.path().join("config.yaml"));
+        fs::write(dir.path().join("config.toml"), "username = 'toml'").unwrap();
+        assert_eq!(store.format(), Format::Toml);
+        assert_eq!(
+            AppConfig::load_from(&store).unwrap().config.username,
+            "toml"
+        );
+
+        // Replacing moves the file that was read aside
+        store.replace(Format::Yaml, b"username: next").unwrap();
+        assert_eq!(store.format(), Format::Yaml);
+        assert!(!dir.path().join("config.toml").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.toml.bak")).unwrap(),
+            "username = 'toml'"
+        );
+        assert_eq!(
+            AppConfig::load_from(&store).unwrap().config.username,
+            "next"
+        );
+    }
+
+    #[test]
+    fn test_dir_store_reads_yml_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        fs::write(dir.path().join("config.json"), "{}").unwrap();
+        fs::write(dir.path().join("config.yml"), "username: yml").unwrap();
+        assert_eq!(store.format(), Format::Yaml);
+        assert_eq!(store.path().unwrap(), dir.path().join("config.yml"));
+        assert_eq!(AppConfig::load_from(&store).unwrap().config.username, "yml");
+
+        // Saves keep the file's name, and so does its backup
+        store.write(b"username: saved").unwrap();
+        store.write_backup(b"username: old").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.yml")).unwrap(),
+            "username: saved"
+        );
+        assert!(dir.path().join("config.yml.bak").exists());
+
+        // Replacing moves it aside as `config.yml.bak`
+        store.replace(Format::Toml, b"username = 'toml'").unwrap();
+        assert_eq!(store.format(), Format::Toml);
+        assert!(!dir.path().join("config.yml").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.yml.bak")).unwrap(),
+            "username: saved"
+        );
+    }
+
+    #[test]
+    fn test_migrate_copies_only_into_empty_store() {
+        let from = MemoryStore::with_contents(r#"{"username": "old"}"#);
+        let dir = tempfile::tempdir().unwrap();
+        let to = DirStore::new(dir.path());
+
+        assert!(migrate(&from, &to).unwrap());
+        assert_eq!(AppConfig::load_from(&to).unwrap().config.username, "old");
+
+        from.write(br#"{"username": "newer"}"#).unwrap();
+        assert!(!migrate(&from, &to).unwrap());
+        assert_eq!(AppConfig::load_from(&to).unwrap().config.username, "old");
+        assert!(!migrate(&MemoryStore::new(), &MemoryStore::new()).unwrap());
+    }
+}
@@ -4,13 +4,18 @@
 //! of the Neon bindings. The Neon functions in `lib.rs` are thin wrappers
 //! around these functions.
 
+use crate::provider::SystemProvider;
+use serde::Serialize;
+use std::path::Path;
+
 /// Returns a greeting message with system info that's inaccessible from the JS sandbox.
-pub fn hello() -> String {
-    let cores = std::thread::available_parallelism()
-        .map(|n| n.get().to_string())
-        .unwrap_or_else(|_| "unknown".to_string());
+pub fn hello(system: &dyn SystemProvider) -> String {
+    let cores = system
+        .available_parallelism()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
 
-    let kernel = get_kernel_version();
+    let kernel = get_kernel_version(system);
 
     format!(
         "Native Rust via Neon (not WASM) with full system access. \
@@ -19,16 +24,11 @@ pub fn hello() -> String {
 }
 
 /// Returns the kernel/OS version. Reads from /proc on Linux, falls back to OS name elsewhere.
-fn get_kernel_version() -> String {
-    #[cfg(target_os = "linux")]
-    {
-        std::fs::read_to_string("/proc/sys/kernel/osrelease")
-            .map(|s| s.trim().to_string())
-            .unwrap_or_else(|_| "unknown".to_string())
-    }
-    #[cfg(not(target_os = "linux"))]
-    {
-        std::env::consts::OS.to_string()
+fn get_kernel_version(system: &dyn SystemProvider) -> String {
+    match system.read_to_string(Path::new("/proc/sys/kernel/osrelease")) {
+        Ok(release) => release.trim().to_string(),
+        Err(_) if system.os() == "linux" => "unknown".to_string(),
+        Err(_) => system.os(),
     }
 }
 
@@ -38,7 +38,7 @@ pub fn add(a: f64, b: f64) -> f64 {
 }
 
 /// System information structure.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct SystemInfo {
     pub platform: String,
     pub arch: String,
@@ -46,23 +46,258 @@ pub struct SystemInfo {
 }
 
 /// Returns information about the current system.
-pub fn get_system_info() -> SystemInfo {
+pub fn get_system_info(system: &dyn SystemProvider) -> SystemInfo {
     SystemInfo {
-        platform: std::env::consts::OS.to_string(),
-        arch: std::env::consts::ARCH.to_string(),
-        family: std::env::consts::FAMILY.to_string(),
+        platform: system.os(),
+        arch: system.arch(),
+        family: system.family(),
+    }
+}
+
+/// Distribution identity parsed from `/etc/os-release`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct OsRelease {
+    pub name: Option<String>,
+    pub pretty_name: Option<String>,
+    pub version: Option<String>,
+    pub version_id: Option<String>,
+    pub id: Option<String>,
+}
+
+/// Graphical session type, as reported by the session manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionType {
+    Wayland,
+    X11,
+    Tty,
+    Unknown,
+}
+
+/// How the application was packaged and launched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Packaging {
+    Native,
+    Flatpak,
+    Snap,
+    AppImage,
+}
+
+/// OS identity and runtime environment of the running application.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EnvironmentInfo {
+    pub os: OsRelease,
+    pub kernel: String,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    pub session_type: SessionType,
+    pub desktop: Option<String>,
+    pub packaging: Packaging,
+    /// Container engine (e.g. "docker", "podman"), if running inside one.
+    pub container: Option<String>,
+}
+
+/// Parses the `KEY=value` lines of an os-release file, unquoting values.
+pub fn parse_os_release(contents: &str) -> OsRelease {
+    let mut release = OsRelease::default();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = unquote(value.trim());
+        let slot = match key.trim() {
+            "NAME" => &mut release.name,
+            "PRETTY_NAME" => &mut release.pretty_name,
+            "VERSION" => &mut release.version,
+            "VERSION_ID" => &mut release.version_id,
+            "ID" => &mut release.id,
+            _ => continue,
+        };
+        *slot = Some(value);
+    }
+
+    release
+}
+
+/// Strips matching single or double quotes and shell escapes from an os-release value.
+fn unquote(value: &str) -> String {
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Returns the effective message locale following POSIX precedence
+/// (`LC_ALL`, then `LC_MESSAGES`, then `LANG`).
+pub fn detect_locale(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env(key))
+        .find(|value| !value.is_empty())
+}
+
+/// Extracts the zone name from an `/etc/localtime` symlink target,
+/// e.g. `/usr/share/zoneinfo/Europe/Berlin` -> `Europe/Berlin`.
+pub fn timezone_from_localtime_target(target: &Path) -> Option<String> {
+    let target = target.to_string_lossy();
+    let (_, zone) = target.split_once("zoneinfo/")?;
+    (!zone.is_empty()).then(|| zone.to_string())
+}
+
+/// Determines the session type from `XDG_SESSION_TYPE`, falling back to
+/// which display server variables are set.
+pub fn detect_session_type(env: impl Fn(&str) -> Option<String>) -> SessionType {
+    match env("XDG_SESSION_TYPE").as_deref() {
+        Some("wayland") => return SessionType::Wayland,
+        Some("x11") => return SessionType::X11,
+        Some("tty") => return SessionType::Tty,
+        _ => {}
+    }
+
+    if env("WAYLAND_DISPLAY").is_some() {
+        SessionType::Wayland
+    } else if env("DISPLAY").is_some() {
+        SessionType::X11
+    } else {
+        SessionType::Unknown
+    }
+}
+
+/// Determines the packaging format from the variables each sandbox sets.
+pub fn detect_packaging(
+    env: impl Fn(&str) -> Option<String>,
+    exists: impl Fn(&Path) -> bool,
+) -> Packaging {
+    if env("FLATPAK_ID").is_some() || exists(Path::new("/.flatpak-info")) {
+        Packaging::Flatpak
+    } else if env("SNAP").is_some() {
+        Packaging::Snap
+    } else if env("APPIMAGE").is_some() {
+        Packaging::AppImage
+    } else {
+        Packaging::Native
+    }
+}
+
+/// Detects a container engine from marker files, the `container` variable
+/// and the cgroup of PID 1.
+pub fn detect_container(
+    env: impl Fn(&str) -> Option<String>,
+    exists: impl Fn(&Path) -> bool,
+    init_cgroup: Option<&str>,
+) -> Option<String> {
+    if let Some(engine) = env("container").filter(|v| !v.is_empty()) {
+        return Some(engine);
+    }
+    if exists(Path::new("/.dockerenv")) {
+        return Some("docker".to_string());
+    }
+    if exists(Path::new("/run/.containerenv")) {
+        return Some("podman".to_string());
+    }
+
+    let cgroup = init_cgroup?;
+    ["docker", "kubepods", "containerd", "lxc", "libpod"]
+        .iter()
+        .find(|marker| cgroup.contains(*marker))
+        .map(|marker| marker.to_string())
+}
+
+/// Collects OS identity, locale, session and sandbox information.
+pub fn get_environment_info(system: &dyn SystemProvider) -> EnvironmentInfo {
+    let env = |key: &str| system.env_var(key);
+    let exists = |path: &Path| system.exists(path);
+    let read = |path: &str| system.read_to_string(Path::new(path));
+
+    let os = read("/etc/os-release")
+        .or_else(|_| read("/usr/lib/os-release"))
+        .map(|contents| parse_os_release(&contents))
+        .unwrap_or_else(|_| OsRelease {
+            name: Some(system.os()),
+            ..OsRelease::default()
+        });
+
+    let timezone = env("TZ")
+        .filter(|tz| !tz.is_empty())
+        .or_else(|| {
+            read("/etc/timezone")
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        })
+        .or_else(|| {
+            system
+                .read_link(Path::new("/etc/localtime"))
+                .ok()
+                .and_then(|target| timezone_from_localtime_target(&target))
+        });
+
+    let init_cgroup = read("/proc/1/cgroup").ok();
+
+    EnvironmentInfo {
+        os,
+        kernel: get_kernel_version(system),
+        locale: detect_locale(env),
+        timezone,
+        session_type: detect_session_type(env),
+        desktop: env("XDG_CURRENT_DESKTOP").filter(|d| !d.is_empty()),
+        packaging: detect_packaging(env, exists),
+        container: detect_container(env, exists, init_cgroup.as_deref()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::provider::{FakeSystem, RealSystem};
 
     #[test]
     fn test_hello() {
-        let result = hello();
-        assert!(result.contains("Native Rust"));
-        assert!(result.contains("Neon"));
+        let system = FakeSystem::new()
+            .with_parallelism(8)
+            .with_file("/proc/sys/kernel/osrelease", "6.8.0-45-generic\n");
+
+        assert_eq!(
+            hello(&system),
+            "Native Rust via Neon (not WASM) with full system access. \
+             CPU cores: 8, Kernel: 6.8.0-45-generic"
+        );
+    }
+
+    #[test]
+    fn test_hello_without_proc() {
+        let system = FakeSystem::new().with_platform("macos", "aarch64", "unix");
+
+        assert_eq!(
+            hello(&system),
+            "Native Rust via Neon (not WASM) with full system access. \
+             CPU cores: unknown, Kernel: macos"
+        );
+    }
+
+    #[test]
+    fn test_hello_real_system() {
+        let result = hello(&RealSystem);
         assert!(result.contains("CPU cores:"));
         assert!(result.contains("Kernel:"));
     }
@@ -91,19 +326,193 @@ mod tests {
 
     #[test]
     fn test_get_system_info() {
-        let info = get_system_info();
-
-        // These should be non-empty strings
-        assert!(!info.platform.is_empty());
-        assert!(!info.arch.is_empty());
-        assert!(!info.family.is_empty());
-
-        // Platform should be a known value
-        let valid_platforms = ["linux", "macos", "windows", "ios", "android", "freebsd"];
-        assert!(
-            valid_platforms.contains(&info.platform.as_str()) || !info.platform.is_empty(),
-            "Unexpected platform: {}",
-            info.platform
+        let system = FakeSystem::new().with_platform("linux", "aarch64", "unix");
+
+        assert_eq!(
+            get_system_info(&system),
+            SystemInfo {
+                platform: "linux".to_string(),
+                arch: "aarch64".to_string(),
+                family: "unix".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_environment_info() {
+        let system = FakeSystem::new()
+            .with_file(
+                "/etc/os-release",
+                "NAME=\"Fedora Linux\"\nVERSION=\"40 (Workstation Edition)\"\nID=fedora\nVERSION_ID=40\n",
+            )
+            .with_file("/proc/sys/kernel/osrelease", "6.10.6-200.fc40.x86_64\n")
+            .with_file("/proc/1/cgroup", "0::/init.scope\n")
+            .with_link("/etc/localtime", "../usr/share/zoneinfo/Europe/Berlin")
+            .with_env("LANG", "de_DE.UTF-8")
+            .with_env("XDG_SESSION_TYPE", "wayland")
+            .with_env("XDG_CURRENT_DESKTOP", "GNOME")
+            .with_env("FLATPAK_ID", "com.example.App");
+
+        assert_eq!(
+            get_environment_info(&system),
+            EnvironmentInfo {
+                os: OsRelease {
+                    name: Some("Fedora Linux".to_string()),
+                    pretty_name: None,
+                    version: Some("40 (Workstation Edition)".to_string()),
+                    version_id: Some("40".to_string()),
+                    id: Some("fedora".to_string()),
+                },
+                kernel: "6.10.6-200.fc40.x86_64".to_string(),
+                locale: Some("de_DE.UTF-8".to_string()),
+                timezone: Some("Europe/Berlin".to_string()),
+                session_type: SessionType::Wayland,
+                desktop: Some("GNOME".to_string()),
+                packaging: Packaging::Flatpak,
+                container: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_environment_info_fallbacks() {
+        let system = FakeSystem::new()
+            .with_platform("freebsd", "x86_64", "unix")
+            .with_file("/run/.containerenv", "")
+            .with_env("TZ", "UTC");
+
+        let info = get_environment_info(&system);
+        assert_eq!(info.os.name.as_deref(), Some("freebsd"));
+        assert_eq!(info.kernel, "freebsd");
+        assert_eq!(info.timezone.as_deref(), Some("UTC"));
+        assert_eq!(info.session_type, SessionType::Unknown);
+        assert_eq!(info.packaging, Packaging::Native);
+        assert_eq!(info.container.as_deref(), Some("podman"));
+    }
+
+    fn fake_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_parse_os_release() {
+        let contents = r#"
+# comment
+PRETTY_NAME="Debian GNU/Linux 12 (bookworm)"
+NAME="Debian GNU/Linux"
+VERSION_ID="12"
+VERSION='12 (bookworm)'
+ID=debian
+HOME_URL="https://www.debian.org/"
+"#;
+        let release = parse_os_release(contents);
+
+        assert_eq!(
+            release,
+            OsRelease {
+                name: Some("Debian GNU/Linux".to_string()),
+                pretty_name: Some("Debian GNU/Linux 12 (bookworm)".to_string()),
+                version: Some("12 (bookworm)".to_string()),
+                version_id: Some("12".to_string()),
+                id: Some("debian".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_os_release_escapes() {
+        let release = parse_os_release(r#"NAME="My \"Quoted\" OS""#);
+        assert_eq!(release.name.as_deref(), Some(r#"My "Quoted" OS"#));
+    }
+
+    #[test]
+    fn test_detect_locale_precedence() {
+        let env = fake_env(&[("LANG", "en_US.UTF-8"), ("LC_ALL", "de_DE.UTF-8")]);
+        assert_eq!(detect_locale(env).as_deref(), Some("de_DE.UTF-8"));
+
+        let env = fake_env(&[("LANG", "en_US.UTF-8"), ("LC_ALL", "")]);
+        assert_eq!(detect_locale(env).as_deref(), Some("en_US.UTF-8"));
+
+        assert_eq!(detect_locale(fake_env(&[])), None);
+    }
+
+    #[test]
+    fn test_timezone_from_localtime_target() {
+        assert_eq!(
+            timezone_from_localtime_target(Path::new("/usr/share/zoneinfo/Europe/Berlin"))
+                .as_deref(),
+            Some("Europe/Berlin")
+        );
+        assert_eq!(
+            timezone_from_localtime_target(Path::new("../usr/share/zoneinfo/UTC")).as_deref(),
+            Some("UTC")
+        );
+        assert_eq!(
+            timezone_from_localtime_target(Path::new("/etc/other")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_detect_session_type() {
+        let env = fake_env(&[("XDG_SESSION_TYPE", "wayland"), ("DISPLAY", ":0")]);
+        assert_eq!(detect_session_type(env), SessionType::Wayland);
+
+        let env = fake_env(&[("DISPLAY", ":0")]);
+        assert_eq!(detect_session_type(env), SessionType::X11);
+
+        assert_eq!(detect_session_type(fake_env(&[])), SessionType::Unknown);
+    }
+
+    #[test]
+    fn test_detect_packaging() {
+        let no_files = |_: &Path| false;
+
+        let env = fake_env(&[("FLATPAK_ID", "com.example.App")]);
+        assert_eq!(detect_packaging(env, no_files), Packaging::Flatpak);
+
+        let env = fake_env(&[("SNAP", "/snap/app/1")]);
+        assert_eq!(detect_packaging(env, no_files), Packaging::Snap);
+
+        let env = fake_env(&[("APPIMAGE", "/home/u/App.AppImage")]);
+        assert_eq!(detect_packaging(env, no_files), Packaging::AppImage);
+
+        assert_eq!(detect_packaging(fake_env(&[]), no_files), Packaging::Native);
+        assert_eq!(
+            detect_packaging(fake_env(&[]), |p: &Path| p == Path::new("/.flatpak-info")),
+            Packaging::Flatpak
+        );
+    }
+
+    #[test]
+    fn test_detect_container() {
+        let no_files = |_: &Path| false;
+
+        let env = fake_env(&[("container", "podman")]);
+        assert_eq!(
+            detect_container(env, no_files, None).as_deref(),
+            Some("podman")
+        );
+
+        let dockerenv = |p: &Path| p == Path::new("/.dockerenv");
+        assert_eq!(
+            detect_container(fake_env(&[]), dockerenv, None).as_deref(),
+            Some("docker")
+        );
+
+        let cgroup = "0::/kubepods/besteffort/pod1234";
+        assert_eq!(
+            detect_container(fake_env(&[]), no_files, Some(cgroup)).as_deref(),
+            Some("kubepods")
+        );
+
+        assert_eq!(
+            detect_container(fake_env(&[]), no_files, Some("0::/init.scope")),
+            None
         );
     }
 }
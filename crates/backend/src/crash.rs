@@ -0,0 +1,358 @@
+//! Crash records for panics in the backend and the renderer.
+//!
+//! Every Neon export runs inside [`catch`], which turns a panic into a JS
+//! exception with the code [`PANIC_ERROR_CODE`] instead of taking down the
+//! main process. The panic hook installed by [`install_panic_hook`] captures
+//! the backtrace while the panicking stack still exists; the caught panic is
+//! then written as a [`CrashRecord`] to the crashes directory together with
+//! build information and the last lines of the log. Panics in the WASM
+//! frontend are reported over IPC and recorded the same way.
+
+use crate::fsutil::write_atomic;
+use crate::logging;
+use crate::logview;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::path::{Path, PathBuf};
+
+/// `code` of the JS error thrown when a backend export panics.
+pub const PANIC_ERROR_CODE: &str = "ERR_BACKEND_PANIC";
+
+/// Number of crash records kept; older ones are deleted.
+pub const MAX_CRASH_RECORDS: usize = 20;
+
+/// Number of log lines included in a crash record.
+pub const RECENT_LOG_LINES: usize = 50;
+
+/// Returns the directory crash records are written to.
+pub fn crash_dir() -> Option<PathBuf> {
+    paths::current().map(|paths| paths.state.join("crashes"))
+}
+
+/// Which part of the app crashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrashSource {
+    Backend,
+    Renderer,
+}
+
+/// Version and platform of the build that crashed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    /// "debug" or "release".
+    pub profile: String,
+}
+
+impl BuildInfo {
+    /// Describes the running backend build.
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+            .to_string(),
+        }
+    }
+}
+
+/// One crash, as stored in the crashes directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashRecord {
+    pub timestamp_ms: u64,
+    pub source: CrashSource,
+    /// Export that panicked, or the route shown in the crashed window.
+    pub context: String,
+    pub message: String,
+    /// Source location of the panic, e.g. "src/lib.rs:10:5".
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub build: BuildInfo,
+    /// Last lines of the log before the crash, oldest first.
+    #[serde(default)]
+    pub recent_logs: Vec<String>,
+}
+
+/// Details of a panic captured by the hook, before unwinding.
+#[derive(Debug, Clone)]
+struct PanicDetails {
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+}
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<PanicDetails>> = const { RefCell::new(None) };
+}
+
+/// Extracts the message from a panic payload.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Installs a panic hook that remembers each panic's location and backtrace
+/// for [`catch`], then runs the previous hook.
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+        let details = PanicDetails {
+            message: panic_message(info.payload()),
+            location: info.location().map(|l| l.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+        };
+        LAST_PANIC.with(|last| *last.borrow_mut() = Some(details));
+        previous(info);
+    }));
+}
+
+/// Runs `f`, returning a crash record instead if it panics.
+///
+/// The record has no log lines yet; [`report`] adds them when saving it.
+pub fn catch<T>(context: &str, f: impl FnOnce() -> T) -> Result<T, Box<CrashRecord>> {
+    let payload = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => return Ok(value),
+        Err(payload) => payload,
+    };
+    let details = LAST_PANIC.with(|last| last.borrow_mut().take());
+    let (message, location, backtrace) = match details {
+        Some(d) => (d.message, d.location, d.backtrace),
+        None => (panic_message(payload.as_ref()), None, String::new()),
+    };
+    Err(Box::new(CrashRecord {
+        timestamp_ms: crate::metrics::now_ms(),
+        source: CrashSource::Backend,
+        context: context.to_string(),
+        message,
+        location,
+        backtrace,
+        build: BuildInfo::current(),
+        recent_logs: Vec::new(),
+    }))
+}
+
+/// Adds the recent log lines to `record`, saves it and logs the crash.
+///
+/// Returns where the record was saved, or `None` if it couldn't be.
+pub fn report(mut record: CrashRecord) -> Option<PathBuf> {
+    record.recent_logs = recent_logs();
+    let saved = save(&record);
+    match &saved {
+        Ok(path) => tracing::error!(
+            source = ?record.source,
+            context = %record.context,
+            message = %record.message,
+            crash = %path.display(),
+            "crash recorded"
+        ),
+        Err(e) => tracing::error!(
+            source = ?record.source,
+            context = %record.context,
+            message = %record.message,
+            error = %e,
+            "could not write crash record"
+        ),
+    }
+    saved.ok()
+}
+
+/// Returns the last lines of the log, or nothing if it can't be read.
+pub fn recent_logs() -> Vec<String> {
+    logging::log_dir()
+        .and_then(|dir| logview::tail(&dir, RECENT_LOG_LINES).ok())
+        .unwrap_or_default()
+}
+
+/// Writes a crash record to the crashes directory.
+pub fn save(record: &CrashRecord) -> io::Result<PathBuf> {
+    let dir =
+        crash_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    write_record(&dir, record, MAX_CRASH_RECORDS)
+}
+
+/// Returns the file name of a crash record, e.g. `crash-1700000000000-backend.json`.
+/// Further records from the same source in the same millisecond get a
+/// sequence number, e.g. `crash-1700000000000-backend-1.json`.
+fn file_name(record: &CrashRecord, sequence: u32) -> String {
+    let source = match record.source {
+        CrashSource::Backend => "backend",
+        CrashSource::Renderer => "renderer",
+    };
+    match sequence {
+        0 => format!("crash-{}-{}.json", record.timestamp_ms, source),
+        n => format!("crash-{}-{}-{}.json", record.timestamp_ms, source, n),
+    }
+}
+
+/// Writes `record` to `dir` and deletes all but the `keep` newest records.
+pub fn write_record(dir: &Path, record: &CrashRecord, keep: usize) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    // The name is claimed by creating the file, so records written at the
+    // same time, e.g. by a panic loop, don't replace each other
+    let mut sequence = 0;
+    let path = loop {
+        let path = dir.join(file_name(record, sequence));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(_) => break path,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => sequence += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    let json = serde_json::to_string_pretty(record).map_err(io::Error::other)?;
+    write_atomic(&path, json.as_bytes())?;
+
+    for old in list(dir)?.into_iter().skip(keep) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Returns the crash record files in `dir`, newest first.
+pub fn list(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut records: Vec<(u64, u32, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let mut parts = name
+                .strip_prefix("crash-")?
+                .strip_suffix(".json")?
+                .split('-');
+            let timestamp = parts.next()?.parse().ok()?;
+            let sequence = parts.nth(1).map_or(Some(0), |n| n.parse().ok())?;
+            Some((timestamp, sequence, entry.path()))
+        })
+        .collect();
+    records.sort_by(|a, b| b.cmp(a));
+    Ok(records.into_iter().map(|(_, _, path)| path).collect())
+}
+
+/// A panic reported by a renderer window.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RendererPanic {
+    pub message: String,
+    #[serde(default)]
+    pub location: Option<String>,
+    /// JS stack trace at the point of the panic.
+    #[serde(default)]
+    pub stack: String,
+    #[serde(default)]
+    pub route: String,
+    /// Version of the frontend build.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// Builds the crash record for a panic reported by window `window_id`.
+///
+/// Like [`catch`], the record is completed and saved by [`report`].
+pub fn renderer_record(report: RendererPanic, window_id: u32, timestamp_ms: u64) -> CrashRecord {
+    let mut build = BuildInfo::current();
+    if let Some(version) = report.version {
+        build.version = version;
+    }
+    CrashRecord {
+        timestamp_ms,
+        source: CrashSource::Renderer,
+        context: format!("window {} ({})", window_id, report.route),
+        message: report.message,
+        location: report.location,
+        backtrace: report.stack,
+        build,
+        recent_logs: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp_ms: u64, source: CrashSource) -> CrashRecord {
+        CrashRecord {
+            timestamp_ms,
+            source,
+            context: "getSensors".to_string(),
+            message: "index out of bounds".to_string(),
+            location: Some("src/sensors.rs:10:5".to_string()),
+            backtrace: String::new(),
+            build: BuildInfo::current(),
+            recent_logs: vec!["{}".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_catch_returns_value_or_record() {
+        install_panic_hook();
+        assert_eq!(catch("add", || 1 + 2).unwrap(), 3);
+
+        let record = catch("getSensors", || -> u32 { panic!("boom {}", 42) }).unwrap_err();
+        assert_eq!(record.source, CrashSource::Backend);
+        assert_eq!(record.context, "getSensors");
+        assert_eq!(record.message, "boom 42");
+        assert!(record.location.unwrap().contains("crash.rs"));
+        assert!(!record.backtrace.is_empty());
+    }
+
+    #[test]
+    fn test_write_record_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..4 {
+            write_record(dir.path(), &record(i, CrashSource::Backend), 3).unwrap();
+        }
+        let path = write_record(dir.path(), &record(10, CrashSource::Renderer), 3).unwrap();
+        assert!(path.ends_with("crash-10-renderer.json"));
+
+        let files = list(dir.path()).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0], path);
+        assert!(files[2].ends_with("crash-2-backend.json"));
+
+        let saved: CrashRecord =
+            serde_json::from_str(&fs::read_to_string(&files[0]).unwrap()).unwrap();
+        assert_eq!(saved, record(10, CrashSource::Renderer));
+
+        // Records in the same millisecond are all kept, newest first
+        let again = write_record(dir.path(), &record(10, CrashSource::Renderer), 3).unwrap();
+        assert!(again.ends_with("crash-10-renderer-1.json"));
+        let files = list(dir.path()).unwrap();
+        assert_eq!(files[0], again);
+        assert_eq!(files[1], path);
+    }
+
+    #[test]
+    fn test_renderer_record() {
+        let report: RendererPanic = serde_json::from_str(
+            r#"{"message": "called `Option::unwrap()` on a `None` value",
+                "location": "src/lib.rs:5:1", "stack": "Error\n at x", "route": "Logs",
+                "version": "0.2.0"}"#,
+        )
+        .unwrap();
+        let crash = renderer_record(report, 7, 1);
+        assert_eq!(crash.source, CrashSource::Renderer);
+        assert_eq!(crash.context, "window 7 (Logs)");
+        assert_eq!(crash.build.version, "0.2.0");
+        assert_eq!(crash.backtrace, "Error\n at x");
+    }
+}
@@ -0,0 +1,305 @@
+//! Writing exported charts and chart data to user-chosen files.
+//!
+//! The frontend renders charts itself and hands over the result: a PNG as a
+//! base64 data URL, SVG markup, or a table of strings that is serialized to
+//! CSV here. Files are written atomically and recorded in a short list of
+//! recent exports kept in the app's data directory.
+
+use crate::fsutil::write_atomic;
+use crate::paths;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Number of entries kept in the recent exports list.
+pub const MAX_RECENT_EXPORTS: usize = 10;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Error type for export operations.
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    InvalidPayload(String),
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "IO error: {}", e),
+            ExportError::Json(e) => write!(f, "JSON error: {}", e),
+            ExportError::InvalidPayload(reason) => write!(f, "Invalid export data: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(e: serde_json::Error) -> Self {
+        ExportError::Json(e)
+    }
+}
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Png,
+    Svg,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Parses a format name such as "png".
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(ExportFormat::Png),
+            "svg" => Some(ExportFormat::Svg),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Tabular chart data to be written as CSV.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    #[serde(default)]
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Serializes a table as RFC 4180 CSV with CRLF line endings.
+pub fn to_csv(table: &CsvTable) -> String {
+    std::iter::once(&table.headers)
+        .chain(&table.rows)
+        .map(|row| {
+            let fields: Vec<Cow<str>> = row.iter().map(|f| csv_field(f)).collect();
+            fields.join(",") + "\r\n"
+        })
+        .collect()
+}
+
+/// Decodes a PNG from a `data:image/png;base64,` URL or plain base64.
+pub fn decode_png(payload: &str) -> Result<Vec<u8>, ExportError> {
+    let encoded = match payload.split_once(',') {
+        Some((prefix, data)) if prefix.starts_with("data:") => {
+            if prefix != "data:image/png;base64" {
+                return Err(ExportError::InvalidPayload(format!(
+                    "expected a base64 PNG data URL, got {}",
+                    prefix
+                )));
+            }
+            data
+        }
+        _ => payload,
+    };
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| ExportError::InvalidPayload(format!("bad base64: {}", e)))?;
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return Err(ExportError::InvalidPayload("not a PNG image".to_string()));
+    }
+    Ok(bytes)
+}
+
+/// Converts the payload sent by the frontend into file contents.
+pub fn render(format: ExportFormat, payload: &str) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Png => decode_png(payload),
+        ExportFormat::Svg => {
+            if !payload.trim_start().starts_with("<svg") {
+                return Err(ExportError::InvalidPayload(
+                    "not an SVG document".to_string(),
+                ));
+            }
+            Ok(format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}\n", payload).into_bytes())
+        }
+        ExportFormat::Csv => {
+            let table: CsvTable = serde_json::from_str(payload)?;
+            Ok(to_csv(&table).into_bytes())
+        }
+    }
+}
+
+/// One entry of the recent exports list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentExport {
+    pub path: String,
+    pub format: ExportFormat,
+    pub bytes: u64,
+    pub timestamp_ms: u64,
+}
+
+/// Returns where the recent exports list is stored.
+pub fn recent_exports_path() -> Option<PathBuf> {
+    paths::current().map(|paths| paths.state.join("recent_exports.json"))
+}
+
+/// Loads the recent exports list, newest first.
+///
+/// A missing or unreadable list is treated as empty, and entries whose file
+/// has since been deleted are left out.
+pub fn recent_exports(list_path: &Path) -> Vec<RecentExport> {
+    fs::read_to_string(list_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Vec<RecentExport>>(&json).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| Path::new(&entry.path).exists())
+        .collect()
+}
+
+/// Adds an entry to the front of the recent exports list, replacing any
+/// older entry for the same path.
+pub fn remember_export(list_path: &Path, entry: RecentExport) -> Result<(), ExportError> {
+    let mut entries = recent_exports(list_path);
+    entries.retain(|e| e.path != entry.path);
+    entries.insert(0, entry);
+    entries.truncate(MAX_RECENT_EXPORTS);
+
+    if let Some(dir) = list_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(
+        list_path,
+        serde_json::to_string_pretty(&entries)?.as_bytes(),
+    )?;
+    Ok(())
+}
+
+/// Writes an export to `dest` atomically and returns its recent exports entry.
+pub fn write_export(
+    dest: &Path,
+    format: ExportFormat,
+    payload: &str,
+    timestamp_ms: u64,
+) -> Result<RecentExport, ExportError> {
+    let contents = render(format, payload)?;
+    write_atomic(dest, &contents)?;
+
+    Ok(RecentExport {
+        path: dest.to_string_lossy().to_string(),
+        format,
+        bytes: contents.len() as u64,
+        timestamp_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1x1 transparent PNG
+    const PNG_BASE64: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAQAAAC1HAwCAAAAC0lEQVR42mNkYAAAAAYAAjCB0C8AAAAASUVORK5CYII=";
+
+    #[test]
+    fn test_csv_escaping() {
+        let table = CsvTable {
+            headers: vec!["label".to_string(), "value, MiB".to_string()],
+            rows: vec![
+                vec!["plain".to_string(), "1.5".to_string()],
+                vec!["say \"hi\"".to_string(), "line\nbreak".to_string()],
+            ],
+        };
+        assert_eq!(
+            to_csv(&table),
+            "label,\"value, MiB\"\r\nplain,1.5\r\n\"say \"\"hi\"\"\",\"line\nbreak\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_decode_png() {
+        let url = format!("data:image/png;base64,{}", PNG_BASE64);
+        assert!(decode_png(&url).unwrap().starts_with(PNG_SIGNATURE));
+        assert!(decode_png(PNG_BASE64).is_ok());
+
+        assert!(matches!(
+            decode_png("data:image/jpeg;base64,AAAA"),
+            Err(ExportError::InvalidPayload(_))
+        ));
+        assert!(matches!(
+            decode_png("aGVsbG8="),
+            Err(ExportError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn test_render_svg_and_csv() {
+        let svg = render(
+            ExportFormat::Svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"/>",
+        )
+        .unwrap();
+        assert!(String::from_utf8(svg).unwrap().starts_with("<?xml"));
+        assert!(render(ExportFormat::Svg, "<html>").is_err());
+
+        let csv = render(ExportFormat::Csv, r#"{"headers": ["a"], "rows": [["1"]]}"#).unwrap();
+        assert_eq!(csv, b"a\r\n1\r\n");
+        assert!(matches!(
+            render(ExportFormat::Csv, "not json"),
+            Err(ExportError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_export_and_recent_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("data/recent_exports.json");
+
+        for i in 0..12 {
+            let dest = dir.path().join(format!("chart{}.csv", i));
+            let entry = write_export(&dest, ExportFormat::Csv, r#"{"headers": ["x"]}"#, i).unwrap();
+            assert_eq!(entry.bytes, 3);
+            remember_export(&list, entry).unwrap();
+        }
+
+        // Re-exporting to a known path moves it to the front
+        let dest = dir.path().join("chart5.csv");
+        let entry = write_export(&dest, ExportFormat::Csv, r#"{"headers": ["y"]}"#, 99).unwrap();
+        remember_export(&list, entry).unwrap();
+
+        let entries = recent_exports(&list);
+        assert_eq!(entries.len(), MAX_RECENT_EXPORTS);
+        assert_eq!(entries[0].timestamp_ms, 99);
+        assert_eq!(entries[1].timestamp_ms, 11);
+        assert_eq!(
+            entries
+                .iter()
+                .filter(|e| e.path.ends_with("chart5.csv"))
+                .count(),
+            1
+        );
+
+        // Deleted files drop out of the list
+        fs::remove_file(dir.path().join("chart11.csv")).unwrap();
+        assert_eq!(recent_exports(&list)[1].timestamp_ms, 10);
+    }
+
+    #[test]
+    fn test_format_parse() {
+        assert_eq!(ExportFormat::parse("PNG"), Some(ExportFormat::Png));
+        assert_eq!(ExportFormat::parse("gif"), None);
+    }
+}
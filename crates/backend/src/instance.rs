@@ -0,0 +1,322 @@
+//! Single-instance enforcement.
+//!
+//! The first instance takes an exclusive `flock` on `instance.lock` in the
+//! runtime directory and listens on the Unix socket `instance.sock` next to
+//! it. A later launch finds the lock taken, sends its command line over the
+//! socket and exits; the first instance passes the message to its handler.
+//!
+//! The kernel releases the lock when its owner exits, however it exits, so a
+//! lock file or socket left behind by a crash is detected as stale simply by
+//! the lock being free, and is reused.
+
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Longest message accepted from another instance.
+pub const MAX_MESSAGE_BYTES: u64 = 64 * 1024;
+
+/// Command line of a later launch, forwarded to the running instance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forwarded {
+    /// Arguments after the executable.
+    pub args: Vec<String>,
+    /// Working directory the launch was started from.
+    pub cwd: String,
+}
+
+impl Forwarded {
+    /// Describes the current process's launch with the given arguments.
+    pub fn current(args: Vec<String>) -> Self {
+        Self {
+            args,
+            cwd: std::env::current_dir()
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Called on the listener thread for each forwarded launch.
+pub type ForwardHandler = Box<dyn Fn(Forwarded) + Send + 'static>;
+
+/// Result of [`acquire`].
+pub enum Acquired {
+    /// This is the only instance; it stays so while the guard is alive.
+    Primary(InstanceGuard),
+    /// Another instance is running and has received the command line.
+    Forwarded,
+}
+
+/// Returns the directory holding the lock and socket; see [`paths::Paths::runtime`].
+pub fn runtime_dir() -> Option<PathBuf> {
+    paths::current().map(|paths| paths.runtime.clone())
+}
+
+/// Guard of this process, while it is the primary instance.
+fn guard() -> &'static Mutex<Option<InstanceGuard>> {
+    static GUARD: OnceLock<Mutex<Option<InstanceGuard>>> = OnceLock::new();
+    GUARD.get_or_init(|| Mutex::new(None))
+}
+
+/// Makes this process the app's single instance, holding on to the lock
+/// until [`release`], or forwards `args` to the running instance.
+///
+/// Returns whether this process is the primary instance.
+pub fn init(args: Vec<String>, handler: ForwardHandler) -> io::Result<bool> {
+    let dir = runtime_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no runtime directory"))?;
+    match acquire(&dir, &Forwarded::current(args), handler)? {
+        Acquired::Primary(instance) => {
+            *guard().lock().unwrap_or_else(|e| e.into_inner()) = Some(instance);
+            Ok(true)
+        }
+        Acquired::Forwarded => Ok(false),
+    }
+}
+
+/// Gives up the instance lock so the next launch becomes primary.
+pub fn release() {
+    guard().lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+#[cfg(unix)]
+pub use unix::{InstanceGuard, acquire};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// How long a later launch waits for a starting instance to listen.
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+    const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+    const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Keeps this process the primary instance until dropped.
+    pub struct InstanceGuard {
+        // Closing the file releases the lock
+        lock: File,
+        socket: PathBuf,
+        stop: Arc<AtomicBool>,
+    }
+
+    impl Drop for InstanceGuard {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+            // Wake the listener thread so it sees `stop`
+            let _ = UnixStream::connect(&self.socket);
+            let _ = fs::remove_file(&self.socket);
+            // An empty lock file tells the next instance this one exited cleanly
+            let _ = self.lock.set_len(0);
+        }
+    }
+
+    /// Becomes the primary instance, or forwards `message` to the running one.
+    ///
+    /// `handler` is called for each message forwarded to this instance while
+    /// the returned guard is alive.
+    pub fn acquire(
+        dir: &Path,
+        message: &Forwarded,
+        handler: ForwardHandler,
+    ) -> io::Result<Acquired> {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+        let lock_path = dir.join("instance.lock");
+        let socket = dir.join("instance.sock");
+        let deadline = Instant::now() + CONNECT_TIMEOUT;
+
+        loop {
+            let mut lock = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .mode(0o600)
+                .open(&lock_path)?;
+            if try_lock(&lock)? {
+                check_stale(&mut lock, &socket);
+                return listen(lock, socket, handler).map(Acquired::Primary);
+            }
+
+            match forward(&socket, message) {
+                Ok(()) => return Ok(Acquired::Forwarded),
+                // The running instance may not be listening yet
+                Err(_) if Instant::now() < deadline => thread::sleep(RETRY_INTERVAL),
+                Err(e) => {
+                    return Err(io::Error::new(
+                        e.kind(),
+                        format!("another instance is running but not responding: {}", e),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Takes the lock without blocking; returns false if another process holds it.
+    fn try_lock(file: &File) -> io::Result<bool> {
+        // SAFETY: the descriptor belongs to `file`, which outlives the call.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+            return Ok(true);
+        }
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            Ok(false)
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Logs a lock left behind by an instance that didn't exit cleanly, and
+    /// records this process as the owner.
+    fn check_stale(lock: &mut File, socket: &Path) {
+        let mut previous = String::new();
+        let _ = lock.read_to_string(&mut previous);
+        if !previous.trim().is_empty() || socket.exists() {
+            tracing::warn!(
+                pid = previous.trim(),
+                "previous instance exited without releasing its lock, taking over"
+            );
+        }
+        let _ = lock.set_len(0);
+        let _ = lock.seek(SeekFrom::Start(0));
+        let _ = lock.write_all(std::process::id().to_string().as_bytes());
+    }
+
+    fn listen(lock: File, socket: PathBuf, handler: ForwardHandler) -> io::Result<InstanceGuard> {
+        // Only reached while holding the lock, so any socket file is stale
+        match fs::remove_file(&socket) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let listener = UnixListener::bind(&socket)?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let stopped = Arc::clone(&stop);
+        thread::Builder::new()
+            .name("instance-listener".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream.and_then(receive) {
+                        Ok(message) => {
+                            tracing::info!(args = ?message.args, "another launch forwarded its arguments");
+                            handler(message);
+                        }
+                        Err(e) => tracing::warn!(error = %e, "invalid message from another instance"),
+                    }
+                }
+            })?;
+
+        Ok(InstanceGuard { lock, socket, stop })
+    }
+
+    /// Reads one message and acknowledges it.
+    fn receive(mut stream: UnixStream) -> io::Result<Forwarded> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        let mut line = String::new();
+        BufReader::new((&stream).take(MAX_MESSAGE_BYTES)).read_line(&mut line)?;
+        let message = serde_json::from_str(&line).map_err(io::Error::other)?;
+        stream.write_all(b"ok\n")?;
+        Ok(message)
+    }
+
+    /// Sends `message` to the running instance and waits for the acknowledgement.
+    fn forward(socket: &Path, message: &Forwarded) -> io::Result<()> {
+        let mut stream = UnixStream::connect(socket)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let mut line = serde_json::to_string(message).map_err(io::Error::other)?;
+        line.push('\n');
+        stream.write_all(line.as_bytes())?;
+
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply)?;
+        if reply.trim() == "ok" {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "running instance didn't acknowledge the message",
+            ))
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub struct InstanceGuard;
+
+/// Unix domain sockets aren't available here, so every launch is primary.
+#[cfg(not(unix))]
+pub fn acquire(
+    _dir: &Path,
+    _message: &Forwarded,
+    _handler: ForwardHandler,
+) -> io::Result<Acquired> {
+    tracing::warn!("single-instance enforcement is not supported on this platform");
+    Ok(Acquired::Primary(InstanceGuard))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn message(args: &[&str]) -> Forwarded {
+        Forwarded {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            cwd: "/home".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_second_launch_forwards_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let first = acquire(
+            dir.path(),
+            &message(&[]),
+            Box::new(move |m| tx.send(m).unwrap()),
+        )
+        .unwrap();
+        assert!(matches!(first, Acquired::Primary(_)));
+
+        let second = acquire(dir.path(), &message(&["--route", "logs"]), Box::new(|_| {})).unwrap();
+        assert!(matches!(second, Acquired::Forwarded));
+        let received = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(received, message(&["--route", "logs"]));
+
+        // Once the first instance is gone the next launch takes over
+        drop(first);
+        let third = acquire(dir.path(), &message(&[]), Box::new(|_| {})).unwrap();
+        assert!(matches!(third, Acquired::Primary(_)));
+    }
+
+    #[test]
+    fn test_stale_lock_and_socket_are_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("instance.lock"), "999999").unwrap();
+        std::fs::write(dir.path().join("instance.sock"), "").unwrap();
+
+        let acquired = acquire(dir.path(), &message(&[]), Box::new(|_| {})).unwrap();
+        assert!(matches!(acquired, Acquired::Primary(_)));
+        let pid = std::fs::read_to_string(dir.path().join("instance.lock")).unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+    }
+}
@@ -1,79 +1,588 @@
+pub mod cache;
 pub mod config;
+pub mod configcache;
+pub mod configformat;
+pub mod configstore;
 pub mod core;
+pub mod crash;
+pub mod export;
+pub mod fsutil;
+pub mod instance;
+pub mod logging;
+pub mod logview;
+pub mod metrics;
+pub mod mounts;
+pub mod patch;
+pub mod paths;
+pub mod process;
+pub mod profiles;
+pub mod provider;
+pub mod sensors;
+pub mod support;
+pub mod timeseries;
 
-use config::AppConfig;
+use config::{AppConfig, ConfigError, SaveOutcome, Versioned};
+use configcache::ConfigCache;
 use neon::prelude::*;
+use provider::RealSystem;
+use std::path::Path;
+use std::sync::Arc;
 
 /// Returns a greeting from the Rust backend (Neon wrapper)
-fn hello(mut cx: FunctionContext) -> JsResult<JsString> {
-    Ok(cx.string(core::hello()))
+fn hello<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    Ok(cx.string(core::hello(&RealSystem)))
 }
 
 /// Adds two numbers together (Neon wrapper)
-fn add(mut cx: FunctionContext) -> JsResult<JsNumber> {
-    let a = cx.argument::<JsNumber>(0)?.value(&mut cx);
-    let b = cx.argument::<JsNumber>(1)?.value(&mut cx);
+fn add<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsNumber> {
+    let a = cx.argument::<JsNumber>(0)?.value(cx);
+    let b = cx.argument::<JsNumber>(1)?.value(cx);
     Ok(cx.number(core::add(a, b)))
 }
 
 /// Returns system information (Neon wrapper)
-fn get_system_info(mut cx: FunctionContext) -> JsResult<JsObject> {
-    let info = core::get_system_info();
+fn get_system_info<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsObject> {
+    let info = core::get_system_info(&RealSystem);
     let obj = cx.empty_object();
 
     let platform = cx.string(&info.platform);
-    obj.set(&mut cx, "platform", platform)?;
+    obj.set(cx, "platform", platform)?;
 
     let arch = cx.string(&info.arch);
-    obj.set(&mut cx, "arch", arch)?;
+    obj.set(cx, "arch", arch)?;
 
     let family = cx.string(&info.family);
-    obj.set(&mut cx, "family", family)?;
+    obj.set(cx, "family", family)?;
 
     Ok(obj)
 }
 
-/// Loads configuration from disk and returns it as JSON (Neon wrapper)
-fn load_config(mut cx: FunctionContext) -> JsResult<JsString> {
-    match AppConfig::load() {
-        Ok(config) => {
-            let json = serde_json::to_string(&config).unwrap_or_else(|_| "{}".to_string());
+/// Returns OS identity and runtime environment as JSON (Neon wrapper)
+fn get_environment_info<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let info = core::get_environment_info(&RealSystem);
+    let json = serde_json::to_string(&info).unwrap_or_else(|_| "{}".to_string());
+    Ok(cx.string(json))
+}
+
+/// Returns mounted filesystems and their usage as JSON (Neon wrapper)
+fn get_storage_report<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let include_pseudo = cx.argument::<JsBoolean>(0)?.value(cx);
+    let config_dir = AppConfig::config_dir();
+
+    match mounts::storage_report(&RealSystem, include_pseudo, config_dir.as_deref()) {
+        Ok(report) => {
+            let json = serde_json::to_string(&report).unwrap_or_else(|_| "{}".to_string());
             Ok(cx.string(json))
         }
-        Err(_) => {
-            let default = AppConfig::default();
-            let json = serde_json::to_string(&default).unwrap_or_else(|_| "{}".to_string());
+        Err(e) => cx.throw_error(format!("Could not read mounts: {}", e)),
+    }
+}
+
+/// Returns hardware sensor readings from sysfs as JSON (Neon wrapper)
+fn get_sensors<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let readings = sensors::read_sensors(&RealSystem, Path::new(sensors::DEFAULT_SYSFS_ROOT));
+    let json = serde_json::to_string(&readings).unwrap_or_else(|_| "[]".to_string());
+    Ok(cx.string(json))
+}
+
+/// Samples the app's own process tree and returns the sample as JSON (Neon wrapper)
+fn collect_metrics<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let sample = metrics::collect(&RealSystem);
+    // The live stream keeps working even if the history can't be written
+    let _ = timeseries::record(&sample);
+    let json = serde_json::to_string(&sample).unwrap_or_else(|_| "{}".to_string());
+    Ok(cx.string(json))
+}
+
+/// Returns the bounded history of metrics samples as JSON (Neon wrapper)
+fn get_metrics_history<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let json = serde_json::to_string(&metrics::history()).unwrap_or_else(|_| "[]".to_string());
+    Ok(cx.string(json))
+}
+
+/// Returns downsampled history of one metrics series as JSON (Neon wrapper)
+fn query_metrics_history<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let series = cx.argument::<JsString>(0)?.value(cx);
+    let from_ms = cx.argument::<JsNumber>(1)?.value(cx).max(0.0) as u64;
+    let to_ms = cx.argument::<JsNumber>(2)?.value(cx).max(0.0) as u64;
+    let max_points = cx.argument::<JsNumber>(3)?.value(cx).max(1.0) as usize;
+
+    let Some(index) = timeseries::series_index(&series) else {
+        return cx.throw_error(format!("Unknown metrics series: {}", series));
+    };
+    match timeseries::query(index, from_ms, to_ms, max_points) {
+        Ok(result) => {
+            let json = serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string());
             Ok(cx.string(json))
         }
+        Err(e) => cx.throw_error(format!("Could not read metrics history: {}", e)),
+    }
+}
+
+/// Records the WASM memory size reported by a renderer window (Neon wrapper)
+fn report_wasm_memory<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsUndefined> {
+    let window_id = cx.argument::<JsNumber>(0)?.value(cx) as u32;
+    let bytes = cx.argument::<JsNumber>(1)?.value(cx) as u64;
+    metrics::report_wasm_memory(window_id, bytes);
+    Ok(cx.undefined())
+}
+
+/// Forgets the WASM memory of a closed renderer window (Neon wrapper)
+fn forget_window<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsUndefined> {
+    let window_id = cx.argument::<JsNumber>(0)?.value(cx) as u32;
+    metrics::forget_window(window_id);
+    Ok(cx.undefined())
+}
+
+/// Returns the process-wide config cache.
+fn config_cache() -> Result<&'static Arc<ConfigCache>, ConfigError> {
+    configcache::global().ok_or(ConfigError::NoConfigDir)
+}
+
+/// Returns the current configuration with its revision as JSON (Neon wrapper)
+fn load_config<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let loaded = config_cache()
+        .map(|cache| cache.get())
+        .unwrap_or_else(|_| Versioned {
+            config: AppConfig::default(),
+            revision: config::NO_REVISION.to_string(),
+        });
+    let json = serde_json::to_string(&loaded).unwrap_or_else(|_| "{}".to_string());
+    Ok(cx.string(json))
+}
+
+/// Returns the optional expected-revision argument at `index`.
+fn expected_revision(cx: &mut FunctionContext, index: usize) -> Option<String> {
+    let value = cx.argument_opt(index)?;
+    value
+        .downcast::<JsString, _>(cx)
+        .ok()
+        .map(|revision| revision.value(cx))
+}
+
+/// Applies the parts of a saved config that take effect without a restart,
+/// and reports the outcome of the save as JSON.
+fn saved(what: &str, result: Result<Versioned, ConfigError>) -> String {
+    match &result {
+        Ok(saved) => {
+            timeseries::set_retention(saved.config.metrics_retention);
+            logging::reconfigure(&saved.config);
+            cache::set_quota_mb(saved.config.cache_quota_mb);
+        }
+        Err(e) => tracing::warn!(error = %e, "could not {}", what),
     }
+    serde_json::to_string(&SaveOutcome::from(result)).unwrap_or_else(|_| "{}".to_string())
 }
 
-/// Saves configuration to disk (Neon wrapper)
-fn save_config(mut cx: FunctionContext) -> JsResult<JsBoolean> {
-    let json = cx.argument::<JsString>(0)?.value(&mut cx);
+/// Saves configuration if it is still at the expected revision, when one is
+/// given, and returns the outcome as JSON (Neon wrapper)
+fn save_config<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let json = cx.argument::<JsString>(0)?.value(cx);
+    let expected = expected_revision(cx, 1);
 
     let result = serde_json::from_str::<AppConfig>(&json)
-        .map_err(|_| ())
-        .and_then(|config| config.save().map_err(|_| ()));
+        .map_err(ConfigError::from)
+        .and_then(|config| config_cache()?.save(config, expected.as_deref()));
+    Ok(cx.string(saved("save config", result)))
+}
+
+/// Applies a JSON Merge Patch to the stored config, validates and saves it,
+/// and returns the outcome as JSON (Neon wrapper)
+fn patch_config<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let json = cx.argument::<JsString>(0)?.value(cx);
+    let expected = expected_revision(cx, 1);
+
+    let result = serde_json::from_str::<serde_json::Value>(&json)
+        .map_err(ConfigError::from)
+        .and_then(|merge_patch| {
+            config_cache()?.update(expected.as_deref(), |config| config.patched(&merge_patch))
+        });
+    Ok(cx.string(saved("patch config", result)))
+}
+
+/// Returns the setting at a JSON Pointer as JSON (Neon wrapper)
+fn get_config_value<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let pointer = cx.argument::<JsString>(0)?.value(cx);
+    let value = config_cache()
+        .and_then(|cache| cache.get().config.pointer(&pointer))
+        .or_else(|e| cx.throw_error(e.to_string()))?;
+    Ok(cx.string(value.to_string()))
+}
+
+/// Sets the setting at a JSON Pointer to a JSON value, validates and saves
+/// the config, and returns the outcome as JSON (Neon wrapper)
+fn set_config_value<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let pointer = cx.argument::<JsString>(0)?.value(cx);
+    let json = cx.argument::<JsString>(1)?.value(cx);
+    let expected = expected_revision(cx, 2);
+
+    let result = serde_json::from_str::<serde_json::Value>(&json)
+        .map_err(ConfigError::from)
+        .and_then(|value| {
+            config_cache()?.update(expected.as_deref(), |mut config| {
+                config.set_pointer(&pointer, value)?;
+                Ok(config)
+            })
+        });
+    Ok(cx.string(saved("set config value", result)))
+}
+
+/// Re-reads the config after it was changed outside the app, applies its
+/// log levels and retention, and returns it with its revision as JSON.
+/// Returns null if the file holds the config the app already has, e.g.
+/// after the app's own save, or couldn't be read; the config in memory is
+/// then kept as it is.
+///
+/// If the file changed settings the app has yet to write, returns a
+/// conflict outcome with the file's config and the unwritten `changes`,
+/// so the Settings card can offer to reload or overwrite (Neon wrapper)
+fn reload_config<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsValue> {
+    let cache = match config_cache() {
+        Ok(cache) => cache,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not reload config, keeping the current settings");
+            return Ok(cx.null().upcast());
+        }
+    };
+    let loaded = match cache.reload() {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) => return Ok(cx.null().upcast()),
+        Err(e @ ConfigError::Conflict { .. }) => {
+            tracing::warn!("config changed on disk conflicts with unsaved changes");
+            let mut outcome = serde_json::to_value(SaveOutcome::from(Err(e))).unwrap_or_default();
+            outcome["changes"] = cache.pending_changes().ok().flatten().unwrap_or_default();
+            return Ok(cx.string(outcome.to_string()).upcast());
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "could not reload config, keeping the current settings");
+            return Ok(cx.null().upcast());
+        }
+    };
+    timeseries::set_retention(loaded.config.metrics_retention);
+    logging::reconfigure(&loaded.config);
+    cache::set_quota_mb(loaded.config.cache_quota_mb);
+    tracing::info!(revision = %loaded.revision, "config changed on disk, reloaded");
+
+    let json = serde_json::to_string(&loaded).unwrap_or_else(|_| "{}".to_string());
+    Ok(cx.string(json).upcast())
+}
+
+/// Writes config changes that are still waiting to be saved, e.g. before
+/// the app quits (Neon wrapper)
+fn flush_config<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsUndefined> {
+    configcache::flush().or_else(|e| cx.throw_error(e.to_string()))?;
+    Ok(cx.undefined())
+}
+
+/// Writes an exported chart or its data to a file and returns the recent
+/// exports entry as JSON (Neon wrapper)
+fn export_file<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let path = cx.argument::<JsString>(0)?.value(cx);
+    let format = cx.argument::<JsString>(1)?.value(cx);
+    let payload = cx.argument::<JsString>(2)?.value(cx);
+
+    let Some(format) = export::ExportFormat::parse(&format) else {
+        return cx.throw_error(format!("Unknown export format: {}", format));
+    };
+    match export::write_export(Path::new(&path), format, &payload, metrics::now_ms()) {
+        Ok(entry) => {
+            tracing::info!(path = %entry.path, format = ?entry.format, bytes = entry.bytes, "exported chart");
+            // The file is written either way; a stale recent list is harmless
+            if let Some(list) = export::recent_exports_path()
+                && let Err(e) = export::remember_export(&list, entry.clone())
+            {
+                tracing::warn!(error = %e, "could not update recent exports");
+            }
+            let json = serde_json::to_string(&entry).unwrap_or_else(|_| "{}".to_string());
+            Ok(cx.string(json))
+        }
+        Err(e) => {
+            tracing::warn!(path = %path, error = %e, "chart export failed");
+            cx.throw_error(format!("Could not export to {}: {}", path, e))
+        }
+    }
+}
+
+/// Returns the recent exports list as JSON, newest first (Neon wrapper)
+fn get_recent_exports<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let entries = export::recent_exports_path()
+        .map(|list| export::recent_exports(&list))
+        .unwrap_or_default();
+    let json = serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string());
+    Ok(cx.string(json))
+}
+
+/// Writes a batch of renderer log entries to the log file (Neon wrapper)
+fn write_renderer_logs<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsNumber> {
+    let json = cx.argument::<JsString>(0)?.value(cx);
+    let window_id = cx.argument::<JsNumber>(1)?.value(cx) as u32;
+
+    match logging::write_renderer_logs(&json, window_id) {
+        Ok(written) => Ok(cx.number(written as f64)),
+        Err(e) => cx.throw_error(format!("Invalid renderer logs: {}", e)),
+    }
+}
+
+/// Reads one page of log records for the log viewer and returns it as JSON (Neon wrapper)
+fn read_logs<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let json = cx.argument::<JsString>(0)?.value(cx);
+
+    let query = match serde_json::from_str::<logview::LogQuery>(&json) {
+        Ok(query) => query,
+        Err(e) => return cx.throw_error(format!("Invalid log query: {}", e)),
+    };
+    let Some(dir) = logging::log_dir() else {
+        return cx.throw_error("No data directory for logs");
+    };
+    match logview::read_page(&dir, &query) {
+        Ok(page) => {
+            let json = serde_json::to_string(&page).unwrap_or_else(|_| "{}".to_string());
+            Ok(cx.string(json))
+        }
+        Err(e) => cx.throw_error(format!("Could not read logs: {}", e)),
+    }
+}
+
+/// Calls `callback` with each new log record as JSON and returns the
+/// subscription id for `unsubscribeLogs` (Neon wrapper)
+fn subscribe_logs<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsNumber> {
+    let callback = Arc::new(cx.argument::<JsFunction>(0)?.root(cx));
+    let mut channel = cx.channel();
+    // A subscription shouldn't keep the app alive on its own
+    channel.unref(cx);
+
+    let id = logging::subscribe(Box::new(move |record| {
+        let Ok(json) = serde_json::to_string(record) else {
+            return;
+        };
+        let callback = Arc::clone(&callback);
+        channel.send(move |mut cx| {
+            let callback = callback.to_inner(&mut cx);
+            callback.call_with(&cx).arg(cx.string(json)).exec(&mut cx)
+        });
+    }));
+    match id {
+        Some(id) => Ok(cx.number(id as f64)),
+        None => cx.throw_error("Logging is not running"),
+    }
+}
+
+/// Ends a log subscription (Neon wrapper)
+fn unsubscribe_logs<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsUndefined> {
+    let id = cx.argument::<JsNumber>(0)?.value(cx) as u64;
+    logging::unsubscribe(id);
+    Ok(cx.undefined())
+}
+
+/// Makes this process the single app instance, or forwards the JSON array
+/// of arguments to the running one. Returns whether this is the primary
+/// instance; `callback` is called with each forwarded launch as JSON
+/// (Neon wrapper)
+fn acquire_single_instance<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsBoolean> {
+    let args = cx.argument::<JsString>(0)?.value(cx);
+    let callback = Arc::new(cx.argument::<JsFunction>(1)?.root(cx));
+    let mut channel = cx.channel();
+    channel.unref(cx);
+
+    let args: Vec<String> = match serde_json::from_str(&args) {
+        Ok(args) => args,
+        Err(e) => return cx.throw_error(format!("Invalid arguments: {}", e)),
+    };
+    let handler = Box::new(move |message: instance::Forwarded| {
+        let Ok(json) = serde_json::to_string(&message) else {
+            return;
+        };
+        let callback = Arc::clone(&callback);
+        channel.send(move |mut cx| {
+            let callback = callback.to_inner(&mut cx);
+            callback.call_with(&cx).arg(cx.string(json)).exec(&mut cx)
+        });
+    });
+    match instance::init(args, handler) {
+        Ok(primary) => Ok(cx.boolean(primary)),
+        Err(e) => cx.throw_error(format!("Could not check for a running instance: {}", e)),
+    }
+}
+
+/// Releases the single-instance lock before quitting (Neon wrapper)
+fn release_single_instance<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsUndefined> {
+    instance::release();
+    Ok(cx.undefined())
+}
+
+/// Records a panic reported by a renderer window as a crash (Neon wrapper)
+fn write_renderer_crash<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsValue> {
+    let json = cx.argument::<JsString>(0)?.value(cx);
+    let window_id = cx.argument::<JsNumber>(1)?.value(cx) as u32;
+
+    let report = match serde_json::from_str::<crash::RendererPanic>(&json) {
+        Ok(report) => report,
+        Err(e) => return cx.throw_error(format!("Invalid crash report: {}", e)),
+    };
+    let record = crash::renderer_record(report, window_id, metrics::now_ms());
+    match crash::report(record) {
+        Some(path) => Ok(cx.string(path.to_string_lossy()).upcast()),
+        None => Ok(cx.null().upcast()),
+    }
+}
+
+/// Parses the runtime versions passed by the main process for a support bundle
+fn bundle_sources(runtime_json: &str) -> support::BundleSources {
+    let runtime = serde_json::from_str(runtime_json).unwrap_or(serde_json::Value::Null);
+    support::BundleSources::current(&RealSystem, runtime)
+}
+
+/// Returns the files a support bundle would contain as JSON (Neon wrapper)
+fn preview_support_bundle<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let runtime_json = cx.argument::<JsString>(0)?.value(cx);
+    let items = support::plan(&bundle_sources(&runtime_json));
+    let json =
+        serde_json::to_string(&support::preview(&items)).unwrap_or_else(|_| "[]".to_string());
+    Ok(cx.string(json))
+}
+
+/// Writes a support bundle to a file and returns its summary as JSON (Neon wrapper)
+fn create_support_bundle<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let path = cx.argument::<JsString>(0)?.value(cx);
+    let runtime_json = cx.argument::<JsString>(1)?.value(cx);
+
+    let items = support::plan(&bundle_sources(&runtime_json));
+    match support::write_bundle(Path::new(&path), &items, metrics::now_ms()) {
+        Ok(summary) => {
+            tracing::info!(path = %summary.path, bytes = summary.bytes, files = summary.files.len(), "created support bundle");
+            let json = serde_json::to_string(&summary).unwrap_or_else(|_| "{}".to_string());
+            Ok(cx.string(json))
+        }
+        Err(e) => {
+            tracing::warn!(path = %path, error = %e, "support bundle failed");
+            cx.throw_error(format!(
+                "Could not create support bundle at {}: {}",
+                path, e
+            ))
+        }
+    }
+}
+
+/// Returns the app's directories and how they were chosen as JSON (Neon
+/// wrapper)
+fn get_paths<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let json = serde_json::to_string(&paths::current()).unwrap_or_else(|_| "null".to_string());
+    Ok(cx.string(json))
+}
+
+/// Returns the cache's size and quota as JSON (Neon wrapper)
+fn get_cache_usage<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let usage = cache::global().map(|cache| cache.usage());
+    let json = serde_json::to_string(&usage).unwrap_or_else(|_| "null".to_string());
+    Ok(cx.string(json))
+}
 
-    Ok(cx.boolean(result.is_ok()))
+/// Empties the cache and returns its size and quota afterwards as JSON
+/// (Neon wrapper)
+fn clear_cache<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    let Some(cache) = cache::global() else {
+        return cx.throw_error("No cache directory");
+    };
+    cache
+        .clear()
+        .or_else(|e| cx.throw_error(format!("Could not clear cache: {}", e)))?;
+    let json = serde_json::to_string(&cache.usage()).unwrap_or_else(|_| "null".to_string());
+    Ok(cx.string(json))
 }
 
 /// Returns the config file path (Neon wrapper)
-fn get_config_path(mut cx: FunctionContext) -> JsResult<JsString> {
+fn get_config_path<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
     let path = AppConfig::config_path()
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
     Ok(cx.string(path))
 }
 
+/// Returns the JSON Schema of the config as a JSON string (Neon wrapper)
+fn get_config_schema<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsString> {
+    Ok(cx.string(AppConfig::schema().to_string()))
+}
+
+/// Exports `f` as `name`, turning a panic into a crash record and a JS error
+/// with `code` set to [`crash::PANIC_ERROR_CODE`].
+fn export<V: Value>(
+    cx: &mut ModuleContext,
+    name: &'static str,
+    f: for<'a> fn(&mut FunctionContext<'a>) -> JsResult<'a, V>,
+) -> NeonResult<()> {
+    cx.export_function(name, move |mut cx: FunctionContext| {
+        let record = match crash::catch(name, || f(&mut cx)) {
+            Ok(result) => return result,
+            Err(record) => record,
+        };
+        let message = format!("Backend panicked in {}: {}", name, record.message);
+        let path = crash::report(*record);
+
+        let error = cx.error(message)?;
+        let code = cx.string(crash::PANIC_ERROR_CODE);
+        error.set(&mut cx, "code", code)?;
+        if let Some(path) = path {
+            let path = cx.string(path.to_string_lossy());
+            error.set(&mut cx, "crashRecord", path)?;
+        }
+        cx.throw(error)
+    })
+}
+
 #[neon::main]
 fn main(mut cx: ModuleContext) -> NeonResult<()> {
-    cx.export_function("hello", hello)?;
-    cx.export_function("add", add)?;
-    cx.export_function("getSystemInfo", get_system_info)?;
-    cx.export_function("loadConfig", load_config)?;
-    cx.export_function("saveConfig", save_config)?;
-    cx.export_function("getConfigPath", get_config_path)?;
+    // Moved before logging starts, which would create the new logs folder
+    let moved = paths::current().map(paths::Paths::migrate);
+    // Without a log file the app still works; say why on stderr instead
+    if let Err(e) = logging::init_default() {
+        eprintln!("Logging disabled: {}", e);
+    }
+    for (moved, result) in moved.unwrap_or_default() {
+        match result {
+            Ok(()) => {
+                tracing::info!(from = %moved.from.display(), to = %moved.to.display(), "moved file to new location")
+            }
+            Err(e) => {
+                tracing::warn!(from = %moved.from.display(), to = %moved.to.display(), error = %e, "could not move file to new location")
+            }
+        }
+    }
+    crash::install_panic_hook();
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "backend loaded");
+
+    export(&mut cx, "hello", hello)?;
+    export(&mut cx, "add", add)?;
+    export(&mut cx, "getSystemInfo", get_system_info)?;
+    export(&mut cx, "getEnvironmentInfo", get_environment_info)?;
+    export(&mut cx, "getStorageReport", get_storage_report)?;
+    export(&mut cx, "getSensors", get_sensors)?;
+    export(&mut cx, "collectMetrics", collect_metrics)?;
+    export(&mut cx, "getMetricsHistory", get_metrics_history)?;
+    export(&mut cx, "queryMetricsHistory", query_metrics_history)?;
+    export(&mut cx, "reportWasmMemory", report_wasm_memory)?;
+    export(&mut cx, "forgetWindow", forget_window)?;
+    export(&mut cx, "loadConfig", load_config)?;
+    export(&mut cx, "saveConfig", save_config)?;
+    export(&mut cx, "patchConfig", patch_config)?;
+    export(&mut cx, "getConfigValue", get_config_value)?;
+    export(&mut cx, "setConfigValue", set_config_value)?;
+    export(&mut cx, "getConfigPath", get_config_path)?;
+    export(&mut cx, "getConfigSchema", get_config_schema)?;
+    export(&mut cx, "getPaths", get_paths)?;
+    export(&mut cx, "getCacheUsage", get_cache_usage)?;
+    export(&mut cx, "clearCache", clear_cache)?;
+    export(&mut cx, "reloadConfig", reload_config)?;
+    export(&mut cx, "flushConfig", flush_config)?;
+    export(&mut cx, "exportFile", export_file)?;
+    export(&mut cx, "getRecentExports", get_recent_exports)?;
+    export(&mut cx, "writeRendererLogs", write_renderer_logs)?;
+    export(&mut cx, "readLogs", read_logs)?;
+    export(&mut cx, "subscribeLogs", subscribe_logs)?;
+    export(&mut cx, "unsubscribeLogs", unsubscribe_logs)?;
+    export(&mut cx, "writeRendererCrash", write_renderer_crash)?;
+    export(&mut cx, "acquireSingleInstance", acquire_single_instance)?;
+    export(&mut cx, "releaseSingleInstance", release_single_instance)?;
+    export(&mut cx, "previewSupportBundle", preview_support_bundle)?;
+    export(&mut cx, "createSupportBundle", create_support_bundle)?;
     Ok(())
 }
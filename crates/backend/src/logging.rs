@@ -0,0 +1,793 @@
+//! Structured logging to a rotating JSON lines file.
+//!
+//! Backend code logs with the `tracing` macros. Each event is written as one
+//! JSON object per line to `app.log` in the logs directory, which is rotated
+//! to `app.log.1`, `app.log.2`, ... when it grows past the configured size.
+//! Levels come from [`LoggingConfig`] and can be set per module (tracing
+//! target) and changed at runtime. Before a record is written, fields that
+//! identify the user are redacted and paths under the home directory are
+//! shortened to `~`.
+
+use crate::config::{AppConfig, LoggingConfig};
+use crate::configcache;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::{Registry, reload};
+
+/// Name of the current log file; rotated files get a numeric suffix.
+pub const LOG_FILE_NAME: &str = "app.log";
+
+/// Field names whose values are always replaced by [`REDACTED`].
+const SENSITIVE_FIELDS: [&str; 3] = ["username", "password", "token"];
+
+/// Replacement for redacted values.
+pub const REDACTED: &str = "[redacted]";
+
+/// Returns the directory log files are written to.
+pub fn log_dir() -> Option<PathBuf> {
+    paths::current().map(|paths| paths.logs.clone())
+}
+
+/// One line of the log file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub timestamp_ms: u64,
+    /// One of "TRACE", "DEBUG", "INFO", "WARN" or "ERROR".
+    pub level: String,
+    /// Module path or other tracing target, e.g. "backend::config".
+    pub target: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+/// Builds the level filter described by `config`.
+///
+/// Entries with an unknown level are skipped and returned so they can be
+/// reported once logging is running.
+pub fn filter(config: &LoggingConfig) -> (Targets, Vec<String>) {
+    let mut invalid = Vec::new();
+    let default = LevelFilter::from_str(&config.level).unwrap_or_else(|_| {
+        invalid.push(format!("level = {:?}", config.level));
+        LevelFilter::INFO
+    });
+
+    let mut targets = Targets::new().with_default(default);
+    for (module, level) in &config.modules {
+        match LevelFilter::from_str(level) {
+            Ok(level) => targets = targets.with_target(module.clone(), level),
+            Err(_) => invalid.push(format!("{} = {:?}", module, level)),
+        }
+    }
+    (targets, invalid)
+}
+
+/// Removes user-identifying data from log records.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    home: Option<String>,
+    usernames: Vec<String>,
+}
+
+impl Redactor {
+    /// Creates a redactor for the given home directory and configured username.
+    ///
+    /// Very short values are ignored, since replacing them would mangle
+    /// unrelated text.
+    pub fn new(home: Option<&Path>, username: &str) -> Self {
+        Self {
+            home: home
+                .map(|h| h.to_string_lossy().trim_end_matches('/').to_string())
+                .filter(|h| h.len() > 1),
+            usernames: Vec::new(),
+        }
+        .with_username(username)
+    }
+
+    /// Creates a redactor for the current user's home directory, the
+    /// configured username and the name the user logged in with.
+    pub fn current(username: &str) -> Self {
+        let dirs = directories::BaseDirs::new();
+        let home = dirs.as_ref().map(|d| d.home_dir());
+        let login = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok()
+            .or_else(|| Some(home?.file_name()?.to_string_lossy().into_owned()));
+        Self::new(home, username).with_username(login.as_deref().unwrap_or_default())
+    }
+
+    /// Also redacts `username`, unless it is too short.
+    pub fn with_username(mut self, username: &str) -> Self {
+        let username = username.trim();
+        if username.chars().count() >= 3 && !self.usernames.iter().any(|u| u == username) {
+            self.usernames.push(username.to_string());
+        }
+        self
+    }
+
+    /// Redacts a string.
+    ///
+    /// The home directory is only replaced where a path continues or ends
+    /// after it, and usernames only as whole words, so `/home/al` doesn't
+    /// match in `/home/alice` and `ann` doesn't match in "annual".
+    pub fn redact_str(&self, text: &str) -> String {
+        let mut text = match &self.home {
+            // Followed by a separator or the end of the path, e.g. a space
+            Some(home) => replace_where(text, home, "~", |_, after| {
+                !after.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            }),
+            None => text.to_string(),
+        };
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        for username in &self.usernames {
+            text = replace_where(&text, username, REDACTED, |before, after| {
+                !is_word(before) && !is_word(after)
+            });
+        }
+        text
+    }
+
+    /// Redacts all strings in a JSON value, and the whole value of sensitive fields.
+    pub fn redact(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.redact_str(s),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.redact(v)),
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if SENSITIVE_FIELDS.contains(&key.to_ascii_lowercase().as_str()) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact(value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Redacts the message and fields of a record.
+    pub fn redact_record(&self, record: &mut LogRecord) {
+        record.message = self.redact_str(&record.message);
+        for (key, value) in record.fields.iter_mut() {
+            if SENSITIVE_FIELDS.contains(&key.to_ascii_lowercase().as_str()) {
+                *value = Value::String(REDACTED.to_string());
+            } else {
+                self.redact(value);
+            }
+        }
+    }
+}
+
+/// Replaces the occurrences of `from` in `text` for which `fits` returns
+/// true, given the characters before and after the occurrence.
+fn replace_where(
+    text: &str,
+    from: &str,
+    to: &str,
+    fits: impl Fn(Option<char>, Option<char>) -> bool,
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, _) in text.match_indices(from) {
+        let end = start + from.len();
+        if fits(
+            text[..start].chars().next_back(),
+            text[end..].chars().next(),
+        ) {
+            out.push_str(&text[copied..start]);
+            out.push_str(to);
+            copied = end;
+        }
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Returns the path of log file `index`, where 0 is the current file.
+pub fn log_file_path(dir: &Path, index: u32) -> PathBuf {
+    match index {
+        0 => dir.join(LOG_FILE_NAME),
+        n => dir.join(format!("{}.{}", LOG_FILE_NAME, n)),
+    }
+}
+
+/// Log file that is rotated when it would grow past a size limit.
+pub struct RotatingFile {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_files: u32,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// Opens (appending to) the current log file in `dir`, creating `dir` if needed.
+    pub fn open(dir: &Path, max_bytes: u64, max_files: u32) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = Self::open_current(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes: max_bytes.max(1),
+            max_files,
+            size: file.metadata()?.len(),
+            file,
+        })
+    }
+
+    fn open_current(dir: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file_path(dir, 0))
+    }
+
+    /// Changes the rotation limits; they take effect with the next write.
+    pub fn set_limits(&mut self, max_bytes: u64, max_files: u32) {
+        self.max_bytes = max_bytes.max(1);
+        self.max_files = max_files;
+    }
+
+    /// Shifts every file up one index, dropping the oldest, and starts a new current file.
+    ///
+    /// With `max_files` of 0 the oldest file is the current one, which is simply removed.
+    fn rotate(&mut self) -> io::Result<()> {
+        let _ = fs::remove_file(log_file_path(&self.dir, self.max_files));
+        for index in (0..self.max_files).rev() {
+            let from = log_file_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(&from, log_file_path(&self.dir, index + 1))?;
+            }
+        }
+        self.file = Self::open_current(&self.dir)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Appends one line, rotating first if it would not fit.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+/// Collects event fields into a JSON map, keeping numbers and booleans typed.
+struct FieldVisitor<'a> {
+    message: &'a mut String,
+    fields: &'a mut Map<String, Value>,
+}
+
+impl FieldVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            *self.message = match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+}
+
+/// Callback receiving every record written to the log, after redaction.
+///
+/// Listeners run on the logging thread while the listener list is locked,
+/// so they must be quick and must not log themselves.
+pub type LogListener = Box<dyn Fn(&LogRecord) + Send + Sync>;
+
+/// Shared state of the JSON layer, kept so limits and redaction can change at runtime.
+pub struct LogSink {
+    file: Mutex<RotatingFile>,
+    redactor: Mutex<Redactor>,
+    listeners: Mutex<Vec<(u64, LogListener)>>,
+    next_listener_id: AtomicU64,
+}
+
+impl LogSink {
+    pub fn new(file: RotatingFile, redactor: Redactor) -> Self {
+        Self {
+            file: Mutex::new(file),
+            redactor: Mutex::new(redactor),
+            listeners: Mutex::new(Vec::new()),
+            next_listener_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Registers a listener for new records and returns its id.
+    pub fn subscribe(&self, listener: LogListener) -> u64 {
+        let id = self.next_listener_id.fetch_add(1, Ordering::Relaxed);
+        self.listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, listener));
+        id
+    }
+
+    /// Removes a listener; unknown ids are ignored.
+    pub fn unsubscribe(&self, id: u64) {
+        self.listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(listener_id, _)| *listener_id != id);
+    }
+
+    /// Redacts a record, appends it to the log file and passes it to listeners.
+    pub fn write(&self, mut record: LogRecord) {
+        self.redactor
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .redact_record(&mut record);
+        if let Ok(line) = serde_json::to_string(&record) {
+            // There is nowhere left to report a failure to write the log
+            let _ = self
+                .file
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .write_line(&line);
+        }
+        for (_, listener) in self
+            .listeners
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+        {
+            listener(&record);
+        }
+    }
+
+    fn reconfigure(&self, config: &AppConfig) {
+        self.file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .set_limits(config.logging.max_file_bytes, config.logging.max_files);
+        *self.redactor.lock().unwrap_or_else(|e| e.into_inner()) =
+            Redactor::current(&config.username);
+    }
+}
+
+/// Tracing layer writing events as [`LogRecord`]s to a [`LogSink`].
+pub struct JsonLayer {
+    sink: Arc<LogSink>,
+}
+
+impl JsonLayer {
+    pub fn new(sink: Arc<LogSink>) -> Self {
+        Self { sink }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for JsonLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut record = LogRecord {
+            timestamp_ms: crate::metrics::now_ms(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: String::new(),
+            fields: Map::new(),
+        };
+        event.record(&mut FieldVisitor {
+            message: &mut record.message,
+            fields: &mut record.fields,
+        });
+        self.sink.write(record);
+    }
+}
+
+/// Target prefix for entries forwarded from the renderer.
+pub const RENDERER_TARGET: &str = "renderer";
+
+/// A log entry sent by the renderer, as produced by the frontend's logging module.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RendererEntry {
+    pub timestamp_ms: u64,
+    pub level: String,
+    #[serde(default)]
+    pub target: String,
+    pub message: String,
+    #[serde(default)]
+    pub route: Option<String>,
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+/// Converts a renderer entry into a log record tagged with its source window.
+///
+/// Targets are nested under [`RENDERER_TARGET`] so renderer levels can be
+/// configured like any other module, and unknown levels are logged as INFO.
+pub fn renderer_record(entry: RendererEntry, window_id: u32) -> LogRecord {
+    let level = tracing::Level::from_str(&entry.level).unwrap_or(tracing::Level::INFO);
+    let target = if entry.target.is_empty() {
+        RENDERER_TARGET.to_string()
+    } else {
+        format!("{}::{}", RENDERER_TARGET, entry.target)
+    };
+    let mut fields = entry.fields;
+    fields.insert("source".to_string(), Value::from("renderer"));
+    fields.insert("window_id".to_string(), Value::from(window_id));
+    if let Some(route) = entry.route {
+        fields.insert("route".to_string(), Value::from(route));
+    }
+    LogRecord {
+        timestamp_ms: entry.timestamp_ms,
+        level: level.to_string(),
+        target,
+        message: entry.message,
+        fields,
+    }
+}
+
+/// Writes the records in `entries` that pass `targets` to `sink`.
+///
+/// Returns how many were written.
+fn write_filtered(
+    sink: &LogSink,
+    targets: &Targets,
+    entries: Vec<RendererEntry>,
+    window_id: u32,
+) -> usize {
+    let mut written = 0;
+    for entry in entries {
+        let record = renderer_record(entry, window_id);
+        let level = tracing::Level::from_str(&record.level).unwrap_or(tracing::Level::INFO);
+        if targets.would_enable(&record.target, &level) {
+            sink.write(record);
+            written += 1;
+        }
+    }
+    written
+}
+
+/// Handles for changing the running logger.
+struct Logger {
+    filter: reload::Handle<Targets, Registry>,
+    /// Copy of the active filter, used for records that bypass `tracing`.
+    targets: Mutex<Targets>,
+    sink: Arc<LogSink>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Installs the global logger writing to `dir`, configured from `config`.
+///
+/// Does nothing if logging was already initialized.
+pub fn init(dir: &Path, config: &AppConfig) -> io::Result<()> {
+    if LOGGER.get().is_some() {
+        return Ok(());
+    }
+    let file = RotatingFile::open(dir, config.logging.max_file_bytes, config.logging.max_files)?;
+    let sink = Arc::new(LogSink::new(file, Redactor::default()));
+    sink.reconfigure(config);
+
+    let (targets, invalid) = filter(&config.logging);
+    let (filter, handle) = reload::Layer::new(targets.clone());
+    let subscriber = Registry::default()
+        .with(filter)
+        .with(JsonLayer::new(Arc::clone(&sink)));
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let _ = LOGGER.set(Logger {
+        filter: handle,
+        targets: Mutex::new(targets),
+        sink,
+    });
+
+    for entry in invalid {
+        tracing::warn!(entry = %entry, "ignoring invalid log level");
+    }
+    Ok(())
+}
+
+/// Installs the global logger in [`log_dir`] using the saved config.
+pub fn init_default() -> io::Result<()> {
+    let dir =
+        log_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    init(&dir, &configcache::current())
+}
+
+/// Applies changed levels, rotation limits and username to the running logger.
+pub fn reconfigure(config: &AppConfig) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    logger.sink.reconfigure(config);
+    let (targets, invalid) = filter(&config.logging);
+    if logger.filter.reload(targets.clone()).is_err() {
+        return;
+    }
+    *logger.targets.lock().unwrap_or_else(|e| e.into_inner()) = targets;
+    for entry in invalid {
+        tracing::warn!(entry = %entry, "ignoring invalid log level");
+    }
+}
+
+/// Writes a JSON array of renderer entries from window `window_id` to the log.
+///
+/// Entries below the configured level are dropped. Returns how many were
+/// written, which is zero if logging isn't running.
+pub fn write_renderer_logs(json: &str, window_id: u32) -> Result<usize, serde_json::Error> {
+    let entries: Vec<RendererEntry> = serde_json::from_str(json)?;
+    let Some(logger) = LOGGER.get() else {
+        return Ok(0);
+    };
+    let targets = logger
+        .targets
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    Ok(write_filtered(&logger.sink, &targets, entries, window_id))
+}
+
+/// Calls `listener` with every record written from now on.
+///
+/// Returns the id to pass to [`unsubscribe`], or `None` if logging isn't running.
+pub fn subscribe(listener: LogListener) -> Option<u64> {
+    LOGGER.get().map(|logger| logger.sink.subscribe(listener))
+}
+
+/// Stops calling the listener registered as `id`.
+pub fn unsubscribe(id: u64) {
+    if let Some(logger) = LOGGER.get() {
+        logger.sink.unsubscribe(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn read_records(path: &Path) -> Vec<LogRecord> {
+        fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rotation_keeps_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 50, 2).unwrap();
+        for i in 0..10 {
+            file.write_line(&format!("line {:02} padding padding padding", i))
+                .unwrap();
+        }
+
+        // Two 32-byte lines don't fit in 50 bytes, so every line starts a new file
+        let current = fs::read_to_string(log_file_path(dir.path(), 0)).unwrap();
+        assert_eq!(current, "line 09 padding padding padding\n");
+        let oldest = fs::read_to_string(log_file_path(dir.path(), 2)).unwrap();
+        assert_eq!(oldest, "line 07 padding padding padding\n");
+        assert!(!log_file_path(dir.path(), 3).exists());
+    }
+
+    #[test]
+    fn test_rotation_appends_until_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 100, 1).unwrap();
+        for _ in 0..3 {
+            file.write_line("0123456789").unwrap();
+        }
+        drop(file);
+
+        // Reopening continues the same file and counts its existing size
+        let mut file = RotatingFile::open(dir.path(), 40, 1).unwrap();
+        file.write_line("abcdefghij").unwrap();
+        assert_eq!(
+            fs::read_to_string(log_file_path(dir.path(), 1))
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+        assert_eq!(
+            fs::read_to_string(log_file_path(dir.path(), 0)).unwrap(),
+            "abcdefghij\n"
+        );
+    }
+
+    #[test]
+    fn test_redaction() {
+        let redactor = Redactor::new(Some(Path::new("/home/alice/")), "alice");
+        let mut record = LogRecord {
+            timestamp_ms: 0,
+            level: "INFO".to_string(),
+            target: "backend::config".to_string(),
+            message: "saved /home/alice/.config/app/config.json for alice".to_string(),
+            fields: serde_json::from_str(
+                r#"{"username": "bob", "path": "/home/alice/x", "nested": {"token": 5, "other": "/tmp"}}"#,
+            )
+            .unwrap(),
+        };
+        redactor.redact_record(&mut record);
+
+        assert_eq!(
+            record.message,
+            "saved ~/.config/app/config.json for [redacted]"
+        );
+        assert_eq!(record.fields["username"], REDACTED);
+        assert_eq!(record.fields["path"], "~/x");
+        assert_eq!(record.fields["nested"]["token"], REDACTED);
+        assert_eq!(record.fields["nested"]["other"], "/tmp");
+
+        // Short usernames and a root home directory are left alone
+        let redactor = Redactor::new(Some(Path::new("/")), "al");
+        assert_eq!(redactor.redact_str("/usr/al"), "/usr/al");
+
+        // Only whole paths and whole words are replaced
+        let redactor = Redactor::new(Some(Path::new("/home/ann")), "ann").with_username("annl");
+        assert_eq!(
+            redactor.redact_str("/home/ann, /home/ann/x and /home/anna/y"),
+            "~, ~/x and /home/anna/y"
+        );
+        assert_eq!(
+            redactor.redact_str("ann's annual report, by annl"),
+            "[redacted]'s annual report, by [redacted]"
+        );
+    }
+
+    #[test]
+    fn test_filter_per_module() {
+        let config = LoggingConfig {
+            level: "warn".to_string(),
+            modules: BTreeMap::from([
+                ("backend::timeseries".to_string(), "debug".to_string()),
+                ("backend::export".to_string(), "loud".to_string()),
+            ]),
+            ..LoggingConfig::default()
+        };
+        let (targets, invalid) = filter(&config);
+
+        assert!(targets.would_enable("backend::timeseries", &tracing::Level::DEBUG));
+        assert!(!targets.would_enable("backend::config", &tracing::Level::INFO));
+        assert!(targets.would_enable("backend::config", &tracing::Level::WARN));
+        assert_eq!(invalid, vec!["backend::export = \"loud\"".to_string()]);
+    }
+
+    #[test]
+    fn test_json_layer_writes_filtered_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = RotatingFile::open(dir.path(), 1024 * 1024, 1).unwrap();
+        let sink = Arc::new(LogSink::new(file, Redactor::new(None, "carol")));
+        let (targets, _) = filter(&LoggingConfig::default());
+        let subscriber = Registry::default()
+            .with(targets)
+            .with(JsonLayer::new(Arc::clone(&sink)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("not written at the default level");
+            tracing::info!(count = 3, ok = true, user = "carol", "hello {}", "world");
+            tracing::warn!(target: "backend::config", username = "carol", "save failed");
+        });
+
+        let records = read_records(&log_file_path(dir.path(), 0));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level, "INFO");
+        assert_eq!(records[0].message, "hello world");
+        assert_eq!(records[0].fields["count"], 3);
+        assert_eq!(records[0].fields["ok"], true);
+        assert_eq!(records[0].fields["user"], REDACTED);
+        assert_eq!(records[1].target, "backend::config");
+        assert_eq!(records[1].fields["username"], REDACTED);
+    }
+
+    #[test]
+    fn test_renderer_record() {
+        let entry: RendererEntry = serde_json::from_str(
+            r#"{"timestamp_ms": 7, "level": "WARN", "target": "settings",
+                "message": "save failed", "route": "Settings", "fields": {"error": "busy"}}"#,
+        )
+        .unwrap();
+        let record = renderer_record(entry, 3);
+        assert_eq!(record.target, "renderer::settings");
+        assert_eq!(record.level, "WARN");
+        assert_eq!(record.fields["source"], "renderer");
+        assert_eq!(record.fields["window_id"], 3);
+        assert_eq!(record.fields["route"], "Settings");
+        assert_eq!(record.fields["error"], "busy");
+
+        let bare: RendererEntry =
+            serde_json::from_str(r#"{"timestamp_ms": 1, "level": "loud", "message": "x"}"#)
+                .unwrap();
+        let record = renderer_record(bare, 1);
+        assert_eq!(record.target, "renderer");
+        assert_eq!(record.level, "INFO");
+        assert!(!record.fields.contains_key("route"));
+    }
+
+    #[test]
+    fn test_renderer_entries_are_filtered_and_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = RotatingFile::open(dir.path(), 1024 * 1024, 1).unwrap();
+        let sink = LogSink::new(file, Redactor::new(None, "carol"));
+        let config = LoggingConfig {
+            modules: BTreeMap::from([("renderer::chart".to_string(), "debug".to_string())]),
+            ..LoggingConfig::default()
+        };
+        let (targets, _) = filter(&config);
+        let entries: Vec<RendererEntry> = serde_json::from_str(
+            r#"[
+                {"timestamp_ms": 1, "level": "DEBUG", "target": "ui", "message": "dropped"},
+                {"timestamp_ms": 2, "level": "DEBUG", "target": "chart", "message": "kept"},
+                {"timestamp_ms": 3, "level": "ERROR", "target": "ui", "message": "failed",
+                 "fields": {"password": "hunter2"}}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(write_filtered(&sink, &targets, entries, 2), 2);
+        let records = read_records(&log_file_path(dir.path(), 0));
+        assert_eq!(records[0].message, "kept");
+        assert_eq!(records[1].target, "renderer::ui");
+        assert_eq!(records[1].fields["password"], REDACTED);
+    }
+
+    #[test]
+    fn test_listeners_receive_redacted_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = RotatingFile::open(dir.path(), 1024 * 1024, 1).unwrap();
+        let sink = LogSink::new(file, Redactor::new(None, "carol"));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let id = sink.subscribe(Box::new({
+            let received = Arc::clone(&received);
+            move |record: &LogRecord| received.lock().unwrap().push(record.message.clone())
+        }));
+
+        let record = |message: &str| LogRecord {
+            timestamp_ms: 1,
+            level: "INFO".to_string(),
+            target: "backend".to_string(),
+            message: message.to_string(),
+            fields: Map::new(),
+        };
+        sink.write(record("hello carol"));
+        sink.unsubscribe(id);
+        sink.write(record("not received"));
+
+        assert_eq!(*received.lock().unwrap(), vec!["hello [redacted]"]);
+    }
+}
@@ -0,0 +1,521 @@
+//! Reading the log files back for the log viewer.
+//!
+//! Pages are read newest first, walking each file backwards in fixed-size
+//! chunks from the current `app.log` through the rotated files, so only
+//! the part of the log a page covers is ever in memory. A [`Cursor`]
+//! marks where the previous page stopped; it remembers which file it points
+//! into by the timestamp of that file's first record and the file's inode,
+//! so paging keeps its place when the files are rotated in between.
+
+use crate::logging::{LogRecord, log_file_path};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read from disk at a time while walking a file backwards.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Most lines examined for one page, so a filter that matches nothing
+/// can't make a request read every file.
+pub const MAX_SCAN_LINES: usize = 20_000;
+
+/// Upper bound for the number of records in a page.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// How much older than the records around it a record can be in the file.
+/// Renderer records are sent in batches up to a second after they were
+/// logged, so the files are only roughly in time order.
+const MAX_RECORD_DELAY_MS: u64 = 5_000;
+
+const LEVELS: [&str; 5] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR"];
+
+/// Returns the severity rank of a level name, or `None` for unknown names.
+fn level_rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| l.eq_ignore_ascii_case(level))
+}
+
+/// Which records to show. Empty fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Least severe level shown, e.g. "warn" for warnings and errors.
+    pub min_level: Option<String>,
+    /// Target or target prefix, e.g. "backend::config" or "renderer".
+    pub module: Option<String>,
+    /// Case-insensitive text searched for in the message and field values.
+    pub text: Option<String>,
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+}
+
+impl LogFilter {
+    /// Returns whether `record` passes the filter.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(min) = self.min_level.as_deref().and_then(level_rank)
+            && level_rank(&record.level).is_some_and(|rank| rank < min)
+        {
+            return false;
+        }
+        if let Some(module) = self.module.as_deref().filter(|m| !m.is_empty())
+            && !(record.target == module
+                || record
+                    .target
+                    .strip_prefix(module)
+                    .is_some_and(|rest| rest.starts_with("::")))
+        {
+            return false;
+        }
+        if self.from_ms.is_some_and(|from| record.timestamp_ms < from)
+            || self.to_ms.is_some_and(|to| record.timestamp_ms > to)
+        {
+            return false;
+        }
+        match self.text.as_deref().filter(|t| !t.is_empty()) {
+            Some(text) => {
+                let text = text.to_lowercase();
+                record.message.to_lowercase().contains(&text)
+                    || record.fields.values().any(|value| {
+                        let value = match value {
+                            serde_json::Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        };
+                        value.to_lowercase().contains(&text)
+                    })
+            }
+            None => true,
+        }
+    }
+}
+
+/// Position in the log files just before which the next page starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// File index at the time the cursor was made, 0 being `app.log`.
+    pub file: u32,
+    /// Timestamp of the first record in that file, identifying it after rotation.
+    pub file_id: u64,
+    /// Inode of that file where the platform has one, telling apart files
+    /// whose first records share a timestamp.
+    #[serde(default)]
+    pub file_key: u64,
+    /// Byte offset of the first line not yet read backwards.
+    pub offset: u64,
+}
+
+/// A page request from the log viewer.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    pub filter: LogFilter,
+    /// Where the previous page ended; `None` starts at the newest record.
+    pub before: Option<Cursor>,
+    pub limit: usize,
+}
+
+/// Matching records, newest first, and where to continue for older ones.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LogPage {
+    pub records: Vec<LogRecord>,
+    /// `None` once the oldest record has been read.
+    pub next: Option<Cursor>,
+}
+
+/// Yields the lines of a file from last to first along with their start offsets.
+struct ReverseLines {
+    file: File,
+    /// Offset of the first byte of `pending`.
+    pos: u64,
+    /// Read but not yet returned bytes, ending at the previous line start.
+    pending: Vec<u8>,
+}
+
+impl ReverseLines {
+    fn new(file: File, end: u64) -> Self {
+        Self {
+            file,
+            pos: end,
+            pending: Vec::new(),
+        }
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<(u64, Vec<u8>)>> {
+        loop {
+            if let Some(newline) = self.pending.iter().rposition(|&b| b == b'\n') {
+                let line = self.pending.split_off(newline + 1);
+                self.pending.truncate(newline);
+                if line.is_empty() {
+                    continue;
+                }
+                return Ok(Some((self.pos + newline as u64 + 1, line)));
+            }
+            if self.pos == 0 {
+                if self.pending.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some((0, std::mem::take(&mut self.pending))));
+            }
+
+            let len = CHUNK_SIZE.min(self.pos);
+            self.pos -= len;
+            let mut chunk = vec![0; len as usize];
+            self.file.seek(SeekFrom::Start(self.pos))?;
+            self.file.read_exact(&mut chunk)?;
+            chunk.append(&mut self.pending);
+            self.pending = chunk;
+        }
+    }
+}
+
+/// Returns the timestamp of the first record in a file, or 0 if it has none.
+fn file_id(path: &Path) -> u64 {
+    let Ok(file) = File::open(path) else {
+        return 0;
+    };
+    let mut line = String::new();
+    if BufReader::new(file).read_line(&mut line).is_err() {
+        return 0;
+    }
+    serde_json::from_str::<LogRecord>(&line)
+        .map(|record| record.timestamp_ms)
+        .unwrap_or(0)
+}
+
+/// Returns a number that stays with a file when it is renamed: its inode
+/// on Unix, its creation time elsewhere, or 0 if neither is available.
+fn file_key(path: &Path) -> u64 {
+    let Ok(metadata) = fs::metadata(path) else {
+        return 0;
+    };
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::MetadataExt::ino(&metadata)
+    }
+    #[cfg(not(unix))]
+    {
+        metadata
+            .created()
+            .ok()
+            .and_then(|created| created.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_nanos() as u64)
+    }
+}
+
+/// Finds the current index of the file a cursor was made for.
+///
+/// Rotation only ever moves files to higher indices, so the search starts
+/// at the cursor's index. Returns `None` if the file has since been deleted.
+fn locate(dir: &Path, cursor: &Cursor) -> Option<u32> {
+    (cursor.file..)
+        .map(|index| (index, log_file_path(dir, index)))
+        .take_while(|(_, path)| path.exists())
+        .find(|(_, path)| file_id(path) == cursor.file_id && file_key(path) == cursor.file_key)
+        .map(|(index, _)| index)
+}
+
+/// Reads one page of records matching `query`, newest first.
+pub fn read_page(dir: &Path, query: &LogQuery) -> io::Result<LogPage> {
+    let limit = query.limit.clamp(1, MAX_PAGE_SIZE);
+    let (mut index, mut end) = match &query.before {
+        Some(cursor) => match locate(dir, cursor) {
+            Some(index) => (index, Some(cursor.offset)),
+            None => return Ok(LogPage::default()),
+        },
+        None => (0, None),
+    };
+
+    let mut records = Vec::new();
+    let mut scanned = 0;
+    loop {
+        let path = log_file_path(dir, index);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(LogPage {
+                    records,
+                    next: None,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        let id = file_id(&path);
+        let key = file_key(&path);
+        let end_offset = match end.take() {
+            Some(offset) => offset,
+            None => file.metadata()?.len(),
+        };
+
+        let mut lines = ReverseLines::new(file, end_offset);
+        while let Some((offset, line)) = lines.next_line()? {
+            scanned += 1;
+            // Lines that aren't records (e.g. cut short by a crash) are skipped
+            if let Ok(record) = serde_json::from_slice::<LogRecord>(&line) {
+                // Files are in time order give or take late renderer
+                // records, so nothing much older can match
+                if query.filter.from_ms.is_some_and(|from| {
+                    record.timestamp_ms < from.saturating_sub(MAX_RECORD_DELAY_MS)
+                }) {
+                    return Ok(LogPage {
+                        records,
+                        next: None,
+                    });
+                }
+                if query.filter.matches(&record) {
+                    records.push(record);
+                }
+            }
+            if records.len() >= limit || scanned >= MAX_SCAN_LINES {
+                let next = Cursor {
+                    file: index,
+                    file_id: id,
+                    file_key: key,
+                    offset,
+                };
+                // Don't hand out a cursor to the very start of the oldest file
+                let exhausted = offset == 0 && !log_file_path(dir, index + 1).exists();
+                return Ok(LogPage {
+                    records,
+                    next: (!exhausted).then_some(next),
+                });
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Returns the last `count` lines of the current log file, oldest first.
+pub fn tail(dir: &Path, count: usize) -> io::Result<Vec<String>> {
+    let file = File::open(log_file_path(dir, 0))?;
+    let end = file.metadata()?.len();
+    let mut lines = ReverseLines::new(file, end);
+    let mut tail = Vec::new();
+    while tail.len() < count
+        && let Some((_, line)) = lines.next_line()?
+    {
+        tail.push(String::from_utf8_lossy(&line).into_owned());
+    }
+    tail.reverse();
+    Ok(tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::RotatingFile;
+    use serde_json::{Map, json};
+
+    fn record(timestamp_ms: u64, level: &str, target: &str, message: &str) -> LogRecord {
+        LogRecord {
+            timestamp_ms,
+            level: level.to_string(),
+            target: target.to_string(),
+            message: message.to_string(),
+            fields: Map::new(),
+        }
+    }
+
+    fn write(file: &mut RotatingFile, record: &LogRecord) {
+        file.write_line(&serde_json::to_string(record).unwrap())
+            .unwrap();
+    }
+
+    fn messages(page: &LogPage) -> Vec<&str> {
+        page.records.iter().map(|r| r.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut warn = record(100, "WARN", "backend::config", "Save failed");
+        warn.fields = json!({ "path": "/tmp/config.json", "attempt": 2 })
+            .as_object()
+            .cloned()
+            .unwrap();
+        let info = record(200, "INFO", "backend::configx", "loaded");
+
+        let filter = |f: LogFilter| (f.matches(&warn), f.matches(&info));
+        assert_eq!(filter(LogFilter::default()), (true, true));
+        assert_eq!(
+            filter(LogFilter {
+                min_level: Some("warn".to_string()),
+                ..LogFilter::default()
+            }),
+            (true, false)
+        );
+        assert_eq!(
+            filter(LogFilter {
+                module: Some("backend::config".to_string()),
+                ..LogFilter::default()
+            }),
+            (true, false)
+        );
+        assert_eq!(
+            filter(LogFilter {
+                module: Some("backend".to_string()),
+                ..LogFilter::default()
+            }),
+            (true, true)
+        );
+        assert_eq!(
+            filter(LogFilter {
+                text: Some("CONFIG.JSON".to_string()),
+                ..LogFilter::default()
+            }),
+            (true, false)
+        );
+        assert_eq!(
+            filter(LogFilter {
+                from_ms: Some(150),
+                ..LogFilter::default()
+            }),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_reverse_lines_across_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lines");
+        let long = "x".repeat(CHUNK_SIZE as usize + 10);
+        std::fs::write(&path, format!("first\n{}\n\nlast\n", long)).unwrap();
+
+        let file = File::open(&path).unwrap();
+        let end = file.metadata().unwrap().len();
+        let mut lines = ReverseLines::new(file, end);
+        let mut seen = Vec::new();
+        while let Some((offset, line)) = lines.next_line().unwrap() {
+            seen.push((offset, line.len()));
+        }
+        assert_eq!(
+            seen,
+            vec![(long.len() as u64 + 8, 4), (6, long.len()), (0, 5)]
+        );
+    }
+
+    #[test]
+    fn test_pages_through_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        // Every file holds two records
+        let mut file = RotatingFile::open(dir.path(), 160, 5).unwrap();
+        for i in 0..7 {
+            let level = if i % 2 == 0 { "INFO" } else { "WARN" };
+            write(&mut file, &record(i, level, "backend", &format!("m{}", i)));
+        }
+
+        let mut query = LogQuery {
+            limit: 3,
+            ..LogQuery::default()
+        };
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m6", "m5", "m4"]);
+
+        query.before = page.next;
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m3", "m2", "m1"]);
+
+        // A rotation between pages doesn't lose the place
+        write(&mut file, &record(7, "INFO", "backend", "m7"));
+        write(&mut file, &record(8, "INFO", "backend", "m8"));
+        query.before = page.next;
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m0"]);
+        assert_eq!(page.next, None);
+
+        let warnings = LogQuery {
+            filter: LogFilter {
+                min_level: Some("warn".to_string()),
+                ..LogFilter::default()
+            },
+            limit: 10,
+            before: None,
+        };
+        let page = read_page(dir.path(), &warnings).unwrap();
+        assert_eq!(messages(&page), ["m5", "m3", "m1"]);
+    }
+
+    #[test]
+    fn test_time_range_stops_early() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 1024, 1).unwrap();
+        for i in 0..5 {
+            write(
+                &mut file,
+                &record(i * 10, "INFO", "backend", &format!("m{}", i)),
+            );
+        }
+        let query = LogQuery {
+            filter: LogFilter {
+                from_ms: Some(15),
+                to_ms: Some(35),
+                ..LogFilter::default()
+            },
+            limit: 10,
+            before: None,
+        };
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m3", "m2"]);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn test_time_range_keeps_late_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 1024, 1).unwrap();
+        // m2 is a renderer record written after m1, though logged before it
+        for (i, timestamp) in [1_000, 10_500, 9_800, 11_000].into_iter().enumerate() {
+            write(
+                &mut file,
+                &record(timestamp, "INFO", "backend", &format!("m{}", i)),
+            );
+        }
+        let query = LogQuery {
+            filter: LogFilter {
+                from_ms: Some(10_000),
+                ..LogFilter::default()
+            },
+            limit: 10,
+            before: None,
+        };
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m3", "m1"]);
+    }
+
+    #[test]
+    fn test_cursor_tells_apart_files_starting_in_the_same_ms() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 160, 5).unwrap();
+        for i in 0..4 {
+            write(&mut file, &record(5, "INFO", "backend", &format!("m{}", i)));
+        }
+        let mut query = LogQuery {
+            limit: 3,
+            ..LogQuery::default()
+        };
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m3", "m2", "m1"]);
+
+        // Rotation moves another file with the same first timestamp to the
+        // cursor's old index
+        write(&mut file, &record(5, "INFO", "backend", "m4"));
+        query.before = page.next;
+        let page = read_page(dir.path(), &query).unwrap();
+        assert_eq!(messages(&page), ["m0"]);
+    }
+
+    #[test]
+    fn test_missing_log_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = read_page(&dir.path().join("logs"), &LogQuery::default()).unwrap();
+        assert_eq!(page, LogPage::default());
+    }
+
+    #[test]
+    fn test_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = RotatingFile::open(dir.path(), 1024, 1).unwrap();
+        for i in 0..5 {
+            file.write_line(&format!("line {}", i)).unwrap();
+        }
+        assert_eq!(tail(dir.path(), 2).unwrap(), ["line 3", "line 4"]);
+        assert_eq!(tail(dir.path(), 10).unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,202 @@
+//! Periodic self-monitoring samples with a bounded in-memory history.
+//!
+//! The Electron main process calls `collectMetrics` on a timer and streams
+//! each sample to the renderers. The renderers report their WASM linear
+//! memory size, which is folded into the next sample. Each sample also
+//! carries the current hardware sensor readings.
+
+use crate::process::{self, ProcessTreeStats};
+use crate::provider::SystemProvider;
+use crate::sensors::{self, SensorReading};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of samples kept in the history (10 minutes at one sample per 2 seconds).
+pub const HISTORY_CAPACITY: usize = 300;
+
+/// One self-monitoring sample.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsSample {
+    pub timestamp_ms: u64,
+    pub process: ProcessTreeStats,
+    /// CPU usage of the whole tree since the previous sample (100 = one core).
+    pub cpu_percent: Option<f64>,
+    /// Sum of the WASM linear memory reported by all renderers.
+    pub wasm_memory_bytes: Option<u64>,
+    /// Hardware sensor readings taken with the sample.
+    pub sensors: Vec<SensorReading>,
+}
+
+/// Fixed-capacity history of samples, oldest first.
+#[derive(Debug, Clone)]
+pub struct MetricsHistory {
+    samples: VecDeque<MetricsSample>,
+    capacity: usize,
+}
+
+impl MetricsHistory {
+    /// Creates an empty history holding at most `capacity` samples.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Appends a sample, evicting the oldest one when full.
+    pub fn push(&mut self, sample: MetricsSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Returns the most recent sample.
+    pub fn latest(&self) -> Option<&MetricsSample> {
+        self.samples.back()
+    }
+
+    /// Returns all samples, oldest first.
+    pub fn samples(&self) -> Vec<MetricsSample> {
+        self.samples.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// Computes CPU usage between two samples of cumulative CPU time.
+pub fn cpu_percent(prev_cpu_ms: u64, prev_ts_ms: u64, cpu_ms: u64, ts_ms: u64) -> Option<f64> {
+    let wall = ts_ms.checked_sub(prev_ts_ms).filter(|w| *w > 0)?;
+    // Exited children drop out of the total, so the delta can go negative
+    let cpu = cpu_ms.saturating_sub(prev_cpu_ms);
+    Some(cpu as f64 * 100.0 / wall as f64)
+}
+
+struct MetricsState {
+    history: MetricsHistory,
+    wasm_memory: HashMap<u32, u64>,
+}
+
+fn state() -> &'static Mutex<MetricsState> {
+    static STATE: OnceLock<Mutex<MetricsState>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        Mutex::new(MetricsState {
+            history: MetricsHistory::new(HISTORY_CAPACITY),
+            wasm_memory: HashMap::new(),
+        })
+    })
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Records the WASM linear memory size reported by one renderer window.
+pub fn report_wasm_memory(window_id: u32, bytes: u64) {
+    let mut state = state().lock().unwrap_or_else(|e| e.into_inner());
+    state.wasm_memory.insert(window_id, bytes);
+}
+
+/// Forgets the WASM memory of a window that has closed.
+pub fn forget_window(window_id: u32) {
+    let mut state = state().lock().unwrap_or_else(|e| e.into_inner());
+    state.wasm_memory.remove(&window_id);
+}
+
+/// Takes a sample of the process tree and appends it to the history.
+pub fn collect(system: &dyn SystemProvider) -> MetricsSample {
+    let process = process::collect_process_tree(system).unwrap_or_default();
+    let sensors = sensors::read_sensors(system, Path::new(sensors::DEFAULT_SYSFS_ROOT));
+    let timestamp_ms = now_ms();
+
+    let mut state = state().lock().unwrap_or_else(|e| e.into_inner());
+    let cpu_percent = state.history.latest().and_then(|prev| {
+        cpu_percent(
+            prev.process.total_cpu_time_ms,
+            prev.timestamp_ms,
+            process.total_cpu_time_ms,
+            timestamp_ms,
+        )
+    });
+    let wasm_memory_bytes =
+        (!state.wasm_memory.is_empty()).then(|| state.wasm_memory.values().sum());
+
+    let sample = MetricsSample {
+        timestamp_ms,
+        process,
+        cpu_percent,
+        wasm_memory_bytes,
+        sensors,
+    };
+    state.history.push(sample.clone());
+    sample
+}
+
+/// Returns the sample history, oldest first.
+pub fn history() -> Vec<MetricsSample> {
+    let state = state().lock().unwrap_or_else(|e| e.into_inner());
+    state.history.samples()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::FakeSystem;
+
+    fn sample(timestamp_ms: u64) -> MetricsSample {
+        MetricsSample {
+            timestamp_ms,
+            process: ProcessTreeStats::default(),
+            cpu_percent: None,
+            wasm_memory_bytes: None,
+            sensors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = MetricsHistory::new(3);
+        for ts in 0..5 {
+            history.push(sample(ts));
+        }
+
+        let timestamps: Vec<u64> = history.samples().iter().map(|s| s.timestamp_ms).collect();
+        assert_eq!(timestamps, vec![2, 3, 4]);
+        assert_eq!(history.latest().unwrap().timestamp_ms, 4);
+        assert_eq!(history.len(), 3);
+    }
+
+    #[test]
+    fn test_cpu_percent() {
+        assert_eq!(cpu_percent(1000, 0, 1500, 1000), Some(50.0));
+        assert_eq!(cpu_percent(1000, 0, 3000, 1000), Some(200.0));
+        assert_eq!(cpu_percent(1000, 0, 900, 1000), Some(0.0));
+        assert_eq!(cpu_percent(0, 1000, 10, 1000), None);
+    }
+
+    #[test]
+    fn test_collect_appends_to_history() {
+        report_wasm_memory(7, 1024);
+        let sample = collect(&FakeSystem::new());
+        forget_window(7);
+
+        assert!(sample.wasm_memory_bytes.unwrap_or(0) >= 1024);
+        assert!(
+            history()
+                .iter()
+                .any(|s| s.timestamp_ms == sample.timestamp_ms)
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Mounted filesystem and storage usage reporting.
+//!
+//! On Linux the mount table is read from `/proc/self/mountinfo` and the usage
+//! of each mount comes from `statvfs`, both through a [`SystemProvider`].
+//! Other platforms currently report no mounts.
+
+use crate::provider::SystemProvider;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Mount table of the current process.
+const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+
+/// Usage (in percent) at or above which a filesystem is considered nearly full.
+pub const NEARLY_FULL_PERCENT: f64 = 90.0;
+
+/// Filesystem types that have no backing storage and are hidden by default.
+const PSEUDO_FS_TYPES: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+/// A single entry parsed from `/proc/self/mountinfo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountEntry {
+    pub mount_id: u32,
+    pub parent_id: u32,
+    pub device: String,
+    pub root: String,
+    pub mount_point: String,
+    pub options: String,
+    pub fs_type: String,
+    pub source: String,
+}
+
+impl MountEntry {
+    /// Returns true if this mount is a pseudo-filesystem without backing storage.
+    pub fn is_pseudo(&self) -> bool {
+        is_pseudo_fs(&self.fs_type)
+    }
+}
+
+/// Space and inode usage of one mounted filesystem.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MountUsage {
+    pub mount_point: String,
+    pub fs_type: String,
+    pub source: String,
+    pub read_only: bool,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    /// Bytes available to unprivileged users.
+    pub free_bytes: u64,
+    pub total_inodes: u64,
+    pub used_inodes: u64,
+    pub free_inodes: u64,
+}
+
+impl MountUsage {
+    /// Returns the percentage of space in use, counting reserved blocks as unavailable.
+    pub fn used_percent(&self) -> f64 {
+        let usable = self.used_bytes + self.free_bytes;
+        if usable == 0 {
+            0.0
+        } else {
+            self.used_bytes as f64 * 100.0 / usable as f64
+        }
+    }
+
+    /// Returns true if the filesystem is at or above [`NEARLY_FULL_PERCENT`].
+    pub fn is_nearly_full(&self) -> bool {
+        self.used_percent() >= NEARLY_FULL_PERCENT
+    }
+}
+
+/// Storage report returned to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub mounts: Vec<MountUsage>,
+    /// Mount point of the filesystem holding the config directory, if known.
+    pub config_mount: Option<String>,
+}
+
+/// Raw numbers returned by `statvfs`, already scaled to bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FsStats {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub available_bytes: u64,
+    pub total_inodes: u64,
+    pub free_inodes: u64,
+}
+
+/// Returns true if the filesystem type has no backing storage.
+pub fn is_pseudo_fs(fs_type: &str) -> bool {
+    PSEUDO_FS_TYPES.contains(&fs_type) || fs_type.starts_with("fuse.gvfs")
+}
+
+/// Parses the contents of a `mountinfo` file. Malformed lines are skipped.
+pub fn parse_mountinfo(contents: &str) -> Vec<MountEntry> {
+    contents.lines().filter_map(parse_mountinfo_line).collect()
+}
+
+/// Parses one `mountinfo` line, e.g.
+/// `36 35 98:0 /mnt1 /mnt/parent rw,noatime master:1 - ext3 /dev/root rw`.
+fn parse_mountinfo_line(line: &str) -> Option<MountEntry> {
+    let (pre, post) = line.split_once(" - ")?;
+
+    let mut pre = pre.split_whitespace();
+    let mount_id = pre.next()?.parse().ok()?;
+    let parent_id = pre.next()?.parse().ok()?;
+    let device = pre.next()?.to_string();
+    let root = unescape(pre.next()?);
+    let mount_point = unescape(pre.next()?);
+    let options = pre.next()?.to_string();
+
+    let mut post = post.split_whitespace();
+    let fs_type = post.next()?.to_string();
+    let source = unescape(post.next().unwrap_or("none"));
+
+    Some(MountEntry {
+        mount_id,
+        parent_id,
+        device,
+        root,
+        mount_point,
+        options,
+        fs_type,
+        source,
+    })
+}
+
+/// Decodes the octal escapes (`\040` for space etc.) the kernel uses in mountinfo.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        // Escapes above \377 don't fit in a byte and are kept as text
+        if bytes[i] == b'\\'
+            && let Some(digits) = bytes.get(i + 1..i + 4)
+            && digits.iter().all(|b| (b'0'..=b'7').contains(b))
+            && let Ok(code) = u8::try_from(
+                digits
+                    .iter()
+                    .fold(0u32, |code, digit| code * 8 + u32::from(digit - b'0')),
+            )
+        {
+            out.push(code);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Combines a mount entry with its `statvfs` numbers.
+pub fn usage_from_stats(entry: &MountEntry, stats: FsStats) -> MountUsage {
+    MountUsage {
+        mount_point: entry.mount_point.clone(),
+        fs_type: entry.fs_type.clone(),
+        source: entry.source.clone(),
+        read_only: entry.options.split(',').any(|o| o == "ro"),
+        total_bytes: stats.total_bytes,
+        used_bytes: stats.total_bytes.saturating_sub(stats.free_bytes),
+        free_bytes: stats.available_bytes,
+        total_inodes: stats.total_inodes,
+        used_inodes: stats.total_inodes.saturating_sub(stats.free_inodes),
+        free_inodes: stats.free_inodes,
+    }
+}
+
+/// Returns the mount whose mount point is the longest prefix of `path`.
+pub fn mount_for_path<'a>(mounts: &'a [MountUsage], path: &Path) -> Option<&'a MountUsage> {
+    mounts
+        .iter()
+        .filter(|m| path.starts_with(&m.mount_point))
+        .max_by_key(|m| m.mount_point.len())
+}
+
+/// Calls `statvfs` on a path.
+// The statvfs field types vary between platforms, so the casts are only
+// redundant on some of them.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)]
+pub fn statvfs(path: &Path) -> io::Result<FsStats> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut buf = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: `c_path` is a valid NUL-terminated string and `buf` is large
+    // enough for `statvfs` to fill in; it is only read after success.
+    let rc = unsafe { libc::statvfs(c_path.as_ptr(), buf.as_mut_ptr()) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let st = unsafe { buf.assume_init() };
+
+    let frsize = st.f_frsize as u64;
+    Ok(FsStats {
+        total_bytes: st.f_blocks as u64 * frsize,
+        free_bytes: st.f_bfree as u64 * frsize,
+        available_bytes: st.f_bavail as u64 * frsize,
+        total_inodes: st.f_files as u64,
+        free_inodes: st.f_ffree as u64,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn statvfs(_path: &Path) -> io::Result<FsStats> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "statvfs is not available on this platform",
+    ))
+}
+
+/// Lists mounted filesystems with their usage.
+///
+/// Pseudo-filesystems are skipped unless `include_pseudo` is set. Mounts that
+/// can't be queried (e.g. permission denied) are skipped as well. Systems
+/// without `/proc/self/mountinfo` report no mounts.
+pub fn list_mounts(
+    system: &dyn SystemProvider,
+    include_pseudo: bool,
+) -> io::Result<Vec<MountUsage>> {
+    let contents = match system.read_to_string(Path::new(MOUNTINFO_PATH)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mounts = parse_mountinfo(&contents)
+        .into_iter()
+        .filter(|entry| include_pseudo || !entry.is_pseudo())
+        .filter_map(|entry| {
+            system
+                .statvfs(Path::new(&entry.mount_point))
+                .ok()
+                .map(|stats| usage_from_stats(&entry, stats))
+        })
+        .collect();
+    Ok(mounts)
+}
+
+/// Builds the storage report, locating the mount that holds `config_dir`.
+pub fn storage_report(
+    system: &dyn SystemProvider,
+    include_pseudo: bool,
+    config_dir: Option<&Path>,
+) -> io::Result<StorageReport> {
+    let mounts = list_mounts(system, include_pseudo)?;
+    let config_mount = config_dir
+        .map(|dir| existing_ancestor(system, dir))
+        .and_then(|dir| mount_for_path(&mounts, &dir).map(|m| m.mount_point.clone()));
+
+    Ok(StorageReport {
+        mounts,
+        config_mount,
+    })
+}
+
+/// Walks up from `path` to the nearest directory that exists, so a config
+/// directory that hasn't been created yet still resolves to a mount.
+fn existing_ancestor(system: &dyn SystemProvider, path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|p| system.exists(p))
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::FakeSystem;
+
+    const SAMPLE: &str = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro
+23 22 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:12 - proc proc rw
+24 22 0:22 / /sys rw,nosuid,nodev,noexec,relatime shared:7 - sysfs sysfs rw
+40 22 8:2 / /home rw,relatime shared:30 - ext4 /dev/sda2 rw
+41 22 8:3 / /mnt/my\\040disk ro,relatime - vfat /dev/sdb1 ro
+garbage line
+";
+
+    fn usage(mount_point: &str, total: u64, free: u64, avail: u64) -> MountUsage {
+        MountUsage {
+            mount_point: mount_point.to_string(),
+            fs_type: "ext4".to_string(),
+            source: "/dev/sda1".to_string(),
+            read_only: false,
+            total_bytes: total,
+            used_bytes: total - free,
+            free_bytes: avail,
+            total_inodes: 0,
+            used_inodes: 0,
+            free_inodes: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let entries = parse_mountinfo(SAMPLE);
+        assert_eq!(entries.len(), 5);
+
+        assert_eq!(
+            entries[0],
+            MountEntry {
+                mount_id: 22,
+                parent_id: 1,
+                device: "8:1".to_string(),
+                root: "/".to_string(),
+                mount_point: "/".to_string(),
+                options: "rw,relatime".to_string(),
+                fs_type: "ext4".to_string(),
+                source: "/dev/sda1".to_string(),
+            }
+        );
+        assert_eq!(entries[1].fs_type, "proc");
+        assert!(entries[1].is_pseudo());
+        assert!(!entries[3].is_pseudo());
+    }
+
+    #[test]
+    fn test_parse_mountinfo_unescapes_paths() {
+        let entries = parse_mountinfo(SAMPLE);
+        assert_eq!(entries[4].mount_point, "/mnt/my disk");
+        assert_eq!(unescape("a\\134b"), "a\\b");
+        assert_eq!(unescape("trailing\\04"), "trailing\\04");
+        assert_eq!(unescape("big\\777"), "big\\777");
+        assert_eq!(unescape("\\377\\400"), "\u{fffd}\\400");
+    }
+
+    #[test]
+    fn test_usage_from_stats() {
+        let entries = parse_mountinfo(SAMPLE);
+        let stats = FsStats {
+            total_bytes: 1000,
+            free_bytes: 300,
+            available_bytes: 250,
+            total_inodes: 100,
+            free_inodes: 40,
+        };
+
+        let usage = usage_from_stats(&entries[4], stats);
+        assert!(usage.read_only);
+        assert_eq!(usage.used_bytes, 700);
+        assert_eq!(usage.free_bytes, 250);
+        assert_eq!(usage.used_inodes, 60);
+        assert!((usage.used_percent() - 700.0 * 100.0 / 950.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_nearly_full() {
+        assert!(usage("/", 1000, 50, 50).is_nearly_full());
+        assert!(!usage("/", 1000, 500, 500).is_nearly_full());
+        assert!(!usage("/", 0, 0, 0).is_nearly_full());
+    }
+
+    #[test]
+    fn test_mount_for_path_picks_longest_prefix() {
+        let mounts = vec![
+            usage("/", 1, 1, 1),
+            usage("/home", 1, 1, 1),
+            usage("/ho", 1, 1, 1),
+        ];
+
+        let found = mount_for_path(&mounts, Path::new("/home/user/.config/app")).unwrap();
+        assert_eq!(found.mount_point, "/home");
+
+        let found = mount_for_path(&mounts, Path::new("/var/lib")).unwrap();
+        assert_eq!(found.mount_point, "/");
+    }
+
+    fn fake_system() -> FakeSystem {
+        let stats = |total, free| FsStats {
+            total_bytes: total,
+            free_bytes: free,
+            available_bytes: free,
+            total_inodes: 1000,
+            free_inodes: 900,
+        };
+        FakeSystem::new()
+            .with_file(MOUNTINFO_PATH, SAMPLE)
+            .with_file("/home/user/.bashrc", "")
+            .with_fs_stats("/", stats(1000, 500))
+            .with_fs_stats("/proc", stats(0, 0))
+            .with_fs_stats("/sys", stats(0, 0))
+            .with_fs_stats("/home", stats(2000, 100))
+    }
+
+    #[test]
+    fn test_list_mounts_filters_pseudo() {
+        let system = fake_system();
+
+        let mounts = list_mounts(&system, false).unwrap();
+        let points: Vec<&str> = mounts.iter().map(|m| m.mount_point.as_str()).collect();
+        // "/mnt/my disk" has no statvfs result and is skipped
+        assert_eq!(points, vec!["/", "/home"]);
+
+        let mounts = list_mounts(&system, true).unwrap();
+        let points: Vec<&str> = mounts.iter().map(|m| m.mount_point.as_str()).collect();
+        assert_eq!(points, vec!["/", "/proc", "/sys", "/home"]);
+    }
+
+    #[test]
+    fn test_list_mounts_without_mountinfo() {
+        assert_eq!(list_mounts(&FakeSystem::new(), false).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_storage_report_finds_config_mount() {
+        let system = fake_system();
+        // The config directory doesn't exist yet, so its nearest existing ancestor is used
+        let config_dir = Path::new("/home/user/.config/app");
+
+        let report = storage_report(&system, false, Some(config_dir)).unwrap();
+        assert_eq!(report.config_mount.as_deref(), Some("/home"));
+        assert!(report.mounts[1].is_nearly_full());
+    }
+}
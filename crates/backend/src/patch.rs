@@ -0,0 +1,199 @@
+//! JSON Merge Patch (RFC 7396) and JSON Pointer (RFC 6901) helpers.
+//!
+//! Used to change single settings without sending the whole config, so that
+//! settings the sender doesn't know about are left alone.
+
+use serde_json::{Map, Value};
+
+/// Applies a merge patch to `target`.
+///
+/// Objects in the patch are merged recursively, `null` removes a member and
+/// any other value replaces the target's.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Returns the merge patch that turns `old` into `new`, or `None` if they
+/// are equal.
+pub fn diff(old: &Value, new: &Value) -> Option<Value> {
+    if old == new {
+        return None;
+    }
+    let (Value::Object(old), Value::Object(new)) = (old, new) else {
+        return Some(new.clone());
+    };
+    let mut patch = Map::new();
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    for (key, value) in new {
+        let change = match old.get(key) {
+            Some(previous) => diff(previous, value),
+            None => Some(value.clone()),
+        };
+        if let Some(change) = change {
+            patch.insert(key.clone(), change);
+        }
+    }
+    Some(Value::Object(patch))
+}
+
+/// Returns whether two merge patches change any of the same members, e.g.
+/// both `{"logging": {"level": …}}` and `{"logging": null}`.
+pub fn overlaps(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => a
+            .iter()
+            .any(|(key, a)| b.get(key).is_some_and(|b| overlaps(a, b))),
+        _ => true,
+    }
+}
+
+/// Splits a JSON Pointer such as `/logging/modules/backend::config` into
+/// its unescaped reference tokens. The empty pointer refers to the whole
+/// document and has none.
+pub fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let rest = pointer.strip_prefix('/')?;
+    Some(
+        rest.split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Returns the path of the first member of `patch` that sets something
+/// `result` doesn't have, as a JSON Pointer.
+///
+/// `result` is the patched document after a round trip through a typed
+/// struct, which drops members the struct doesn't know.
+pub fn unknown_member(patch: &Value, result: &Value) -> Option<String> {
+    let Value::Object(patch) = patch else {
+        return None;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            continue;
+        }
+        let escaped = key.replace('~', "~0").replace('/', "~1");
+        match result.get(key) {
+            None => return Some(format!("/{}", escaped)),
+            Some(inner) => {
+                if let Some(path) = unknown_member(value, inner) {
+                    return Some(format!("/{}{}", escaped, path));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_patch_rfc_examples() {
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ];
+        for (mut target, patch, expected) in cases {
+            merge_patch(&mut target, &patch);
+            assert_eq!(target, expected, "patch {}", patch);
+        }
+    }
+
+    #[test]
+    fn test_diff_round_trips() {
+        let old = json!({"a": 1, "b": {"c": "x", "d": [1]}, "gone": true});
+        let new = json!({"a": 1, "b": {"c": "y", "d": [1, 2]}, "added": 2});
+        let patch = diff(&old, &new).unwrap();
+        assert_eq!(
+            patch,
+            json!({"b": {"c": "y", "d": [1, 2]}, "gone": null, "added": 2})
+        );
+        let mut patched = old.clone();
+        merge_patch(&mut patched, &patch);
+        assert_eq!(patched, new);
+        assert_eq!(diff(&new, &new), None);
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let level = json!({"logging": {"level": "debug"}});
+        assert!(overlaps(&level, &json!({"logging": {"level": "warn"}})));
+        assert!(overlaps(&level, &json!({"logging": null})));
+        assert!(!overlaps(&level, &json!({"logging": {"max_files": 2}})));
+        assert!(!overlaps(&level, &json!({"theme": "dark"})));
+    }
+
+    #[test]
+    fn test_pointer_tokens() {
+        assert_eq!(pointer_tokens(""), Some(vec![]));
+        assert_eq!(
+            pointer_tokens("/logging/modules/a~1b~0c").unwrap(),
+            ["logging", "modules", "a/b~c"]
+        );
+        assert_eq!(pointer_tokens("logging"), None);
+    }
+
+    #[test]
+    fn test_unknown_member() {
+        let result = json!({"logging": {"level": "info"}});
+        assert_eq!(
+            unknown_member(&json!({"logging": {"level": "warn"}}), &result),
+            None
+        );
+        assert_eq!(unknown_member(&json!({"colour": null}), &result), None);
+        assert_eq!(
+            unknown_member(&json!({"logging": {"lvl": "warn"}}), &result).unwrap(),
+            "/logging/lvl"
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Where the app keeps its files.
+//!
+//! Files are split by kind, following the XDG base directory spec on Linux
+//! and the closest equivalents elsewhere:
+//!
+//! - config: settings and profiles, which users may back up or edit
+//! - data: metrics history
+//! - cache: files that can be recreated, see [`crate::cache`]
+//! - state: logs, crash records and recent exports
+//! - runtime: the single-instance lock and socket
+//!
+//! They can all be moved under one root directory instead, which is taken
+//! from the first of:
+//!
+//! 1. `--data-dir <dir>` or `--data-dir=<dir>` on the command line
+//! 2. the `APP_HOME` environment variable
+//! 3. a file named `portable` next to the executable, which puts the root in
+//!    `user-data` beside it, e.g. to run the app from a USB stick
+//!
+//! Under a root the directories are `config`, `data`, `cache` and `state`.
+//! The runtime directory stays in the user's runtime directory, since
+//! sockets can't live on every filesystem.
+//!
+//! Older versions kept logs, crash records and recent exports with the
+//! data; [`Paths::migrate`] moves them to the state directory.
+
+use directories::ProjectDirs;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable naming the root directory.
+pub const APP_HOME_VAR: &str = "APP_HOME";
+
+/// Command line switch naming the root directory.
+pub const DATA_DIR_SWITCH: &str = "--data-dir";
+
+/// File next to the executable that turns on portable mode.
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// Root directory next to the executable in portable mode.
+pub const PORTABLE_ROOT: &str = "user-data";
+
+/// How the directories were chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// The platform directories.
+    Installed,
+    /// Next to the executable, because of the portable marker.
+    Portable,
+    /// Under `APP_HOME`.
+    AppHome,
+    /// Under the directory passed with `--data-dir`.
+    DataDir,
+}
+
+/// The directories the app keeps its files in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Paths {
+    pub mode: Mode,
+    /// The directory the others are in, except in [`Mode::Installed`].
+    pub root: Option<PathBuf>,
+    pub config: PathBuf,
+    pub data: PathBuf,
+    pub cache: PathBuf,
+    pub state: PathBuf,
+    pub runtime: PathBuf,
+    /// The `logs` folder in the state directory.
+    pub logs: PathBuf,
+}
+
+/// A file or folder that moves from `from` to `to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl Paths {
+    /// Returns the directories under `root`.
+    pub fn rooted(mode: Mode, root: &Path) -> Self {
+        let state = root.join("state");
+        Self {
+            mode,
+            root: Some(root.to_path_buf()),
+            config: root.join("config"),
+            data: root.join("data"),
+            cache: root.join("cache"),
+            logs: state.join("logs"),
+            state,
+            // Apps with different roots don't share files, so each gets its
+            // own instance. Hashed, since socket paths are limited to about
+            // 100 bytes.
+            runtime: user_runtime_dir(ProjectDirs::from("com", "example", "{{project-name}}"))
+                .join(format!("root-{}", short_hash(root))),
+        }
+    }
+
+    /// Returns the platform directories, if the platform has them.
+    pub fn platform() -> Option<Self> {
+        let dirs = ProjectDirs::from("com", "example", "{{project-name}}")?;
+        // Only Linux has a state directory; elsewhere it's the local
+        // (non-roaming) data directory
+        let state = dirs
+            .state_dir()
+            .unwrap_or_else(|| dirs.data_local_dir())
+            .to_path_buf();
+        Some(Self {
+            mode: Mode::Installed,
+            root: None,
+            config: dirs.config_dir().to_path_buf(),
+            data: dirs.data_dir().to_path_buf(),
+            cache: dirs.cache_dir().to_path_buf(),
+            logs: state.join("logs"),
+            state,
+            runtime: user_runtime_dir(Some(dirs)),
+        })
+    }
+
+    /// Chooses the directories from a `--data-dir` argument, the value of
+    /// `APP_HOME` and the path of the executable, in that order.
+    pub fn resolve(
+        data_dir: Option<&Path>,
+        app_home: Option<&Path>,
+        exe: Option<&Path>,
+    ) -> Option<Self> {
+        if let Some(dir) = data_dir {
+            return Some(Self::rooted(Mode::DataDir, &absolute(dir)));
+        }
+        if let Some(dir) = app_home.filter(|dir| !dir.as_os_str().is_empty()) {
+            return Some(Self::rooted(Mode::AppHome, &absolute(dir)));
+        }
+        if let Some(exe_dir) = exe.and_then(Path::parent)
+            && exe_dir.join(PORTABLE_MARKER).is_file()
+        {
+            return Some(Self::rooted(Mode::Portable, &exe_dir.join(PORTABLE_ROOT)));
+        }
+        Self::platform()
+    }
+
+    /// Returns where older versions kept files that now live elsewhere.
+    pub fn legacy_moves(&self) -> Vec<Move> {
+        let mut moves = vec![
+            Move {
+                from: self.data.join("logs"),
+                to: self.logs.clone(),
+            },
+            Move {
+                from: self.data.join("crashes"),
+                to: self.state.join("crashes"),
+            },
+            Move {
+                from: self.data.join("recent_exports.json"),
+                to: self.state.join("recent_exports.json"),
+            },
+        ];
+        if let Some(root) = &self.root {
+            // Roots used to have logs directly under them
+            moves.push(Move {
+                from: root.join("logs"),
+                to: self.logs.clone(),
+            });
+        }
+        moves.retain(|m| m.from != m.to);
+        moves
+    }
+
+    /// Moves files older versions kept elsewhere to where they belong now.
+    ///
+    /// Files whose new place is already taken are left alone. Returns the
+    /// moves that were tried and how each went; runs before logging is set
+    /// up, so the caller logs them.
+    pub fn migrate(&self) -> Vec<(Move, io::Result<()>)> {
+        self.legacy_moves()
+            .into_iter()
+            .filter(|m| m.from.exists() && !m.to.exists())
+            .map(|m| {
+                let result = move_path(&m.from, &m.to);
+                (m, result)
+            })
+            .collect()
+    }
+}
+
+/// Returns the per-user runtime directory where there is one, a per-user
+/// folder in the temp dir otherwise.
+fn user_runtime_dir(dirs: Option<ProjectDirs>) -> PathBuf {
+    if let Some(dir) = dirs.as_ref().and_then(|d| d.runtime_dir()) {
+        return dir.to_path_buf();
+    }
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid has no preconditions and cannot fail.
+        let uid = unsafe { libc::getuid() };
+        std::env::temp_dir().join(format!("{{project-name}}-{}", uid))
+    }
+    #[cfg(not(unix))]
+    {
+        std::env::temp_dir().join("{{project-name}}")
+    }
+}
+
+fn short_hash(path: &Path) -> String {
+    Sha256::digest(path.as_os_str().as_encoded_bytes())[..6]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Moves a file or folder, copying it when a rename isn't possible, e.g.
+/// across filesystems.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to)?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Returns the value of the last `--data-dir` switch in `args`.
+pub fn data_dir_arg(args: impl IntoIterator<Item = OsString>) -> Option<PathBuf> {
+    let mut found = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        if arg == DATA_DIR_SWITCH {
+            found = args.next().map(PathBuf::from);
+        } else if let Some(value) = arg
+            .strip_prefix(DATA_DIR_SWITCH)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            found = Some(PathBuf::from(value));
+        }
+    }
+    found
+}
+
+fn absolute(dir: &Path) -> PathBuf {
+    std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+fn paths_cell() -> &'static OnceLock<Option<Paths>> {
+    static PATHS: OnceLock<Option<Paths>> = OnceLock::new();
+    &PATHS
+}
+
+/// Chooses the directories, with `data_dir` in place of the command line's
+/// `--data-dir` when given. Must be called before the directories are
+/// first used; returns false if they were already chosen.
+pub fn init(data_dir: Option<&Path>) -> bool {
+    let mut initialized = false;
+    paths_cell().get_or_init(|| {
+        initialized = true;
+        resolve_for_process(data_dir)
+    });
+    initialized
+}
+
+/// Returns the directories of this process, choosing them from its command
+/// line, environment and executable on first use.
+pub fn current() -> Option<&'static Paths> {
+    paths_cell()
+        .get_or_init(|| resolve_for_process(None))
+        .as_ref()
+}
+
+fn resolve_for_process(data_dir: Option<&Path>) -> Option<Paths> {
+    let from_args = data_dir_arg(std::env::args_os().skip(1));
+    let app_home = std::env::var_os(APP_HOME_VAR).map(PathBuf::from);
+    let exe = std::env::current_exe().ok();
+    let paths = Paths::resolve(
+        data_dir.or(from_args.as_deref()),
+        app_home.as_deref(),
+        exe.as_deref(),
+    );
+    if let Some(paths) = &paths {
+        tracing::info!(mode = ?paths.mode, root = ?paths.root, "resolved app directories");
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_dir_arg() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(data_dir_arg(args(&[".", "--route", "logs"])), None);
+        assert_eq!(
+            data_dir_arg(args(&[".", "--data-dir", "/tmp/a"])).unwrap(),
+            Path::new("/tmp/a")
+        );
+        assert_eq!(
+            data_dir_arg(args(&["--data-dir=/tmp/a", "--data-dir=/tmp/b"])).unwrap(),
+            Path::new("/tmp/b")
+        );
+        assert_eq!(data_dir_arg(args(&["--data-dir"])), None);
+        assert_eq!(data_dir_arg(args(&["--data-directory=/tmp/a"])), None);
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("app");
+        let explicit = dir.path().join("explicit");
+        let home = dir.path().join("home");
+
+        let installed = Paths::resolve(None, None, Some(&exe));
+        assert_eq!(
+            installed.map(|paths| paths.mode),
+            Paths::platform().map(|paths| paths.mode)
+        );
+        // An empty APP_HOME counts as unset
+        assert_ne!(
+            Paths::resolve(None, Some(Path::new("")), Some(&exe)).map(|paths| paths.mode),
+            Some(Mode::AppHome)
+        );
+
+        fs::write(dir.path().join(PORTABLE_MARKER), "").unwrap();
+        let portable = Paths::resolve(None, None, Some(&exe)).unwrap();
+        assert_eq!(portable.mode, Mode::Portable);
+        assert_eq!(portable.config, dir.path().join("user-data/config"));
+        assert_eq!(portable.logs, dir.path().join("user-data/state/logs"));
+
+        let app_home = Paths::resolve(None, Some(&home), Some(&exe)).unwrap();
+        assert_eq!(app_home, Paths::rooted(Mode::AppHome, &home));
+
+        let data_dir = Paths::resolve(Some(&explicit), Some(&home), Some(&exe)).unwrap();
+        assert_eq!(data_dir.mode, Mode::DataDir);
+        assert_eq!(data_dir.cache, explicit.join("cache"));
+        assert_eq!(data_dir.data, explicit.join("data"));
+        // Each root has its own runtime directory, outside the root
+        assert_ne!(data_dir.runtime, app_home.runtime);
+        assert!(!data_dir.runtime.starts_with(&explicit));
+    }
+
+    #[test]
+    fn test_relative_root_is_made_absolute() {
+        let paths = Paths::resolve(Some(Path::new("relative")), None, None).unwrap();
+        assert!(paths.root.unwrap().is_absolute());
+        assert!(paths.config.is_absolute());
+    }
+
+    #[test]
+    fn test_migrate_moves_misplaced_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Paths::rooted(Mode::DataDir, dir.path());
+        fs::create_dir_all(dir.path().join("logs")).unwrap();
+        fs::write(dir.path().join("logs/backend.log"), "old log").unwrap();
+        fs::create_dir_all(&paths.data).unwrap();
+        fs::write(paths.data.join("recent_exports.json"), "[]").unwrap();
+        // Already in place, so the old copy stays where it is
+        fs::create_dir_all(paths.data.join("crashes")).unwrap();
+        fs::create_dir_all(paths.state.join("crashes")).unwrap();
+
+        let moved = paths.migrate();
+        assert_eq!(moved.len(), 2);
+        assert!(moved.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(
+            fs::read_to_string(paths.logs.join("backend.log")).unwrap(),
+            "old log"
+        );
+        assert!(paths.state.join("recent_exports.json").exists());
+        assert!(!paths.data.join("recent_exports.json").exists());
+        assert!(paths.data.join("crashes").exists());
+
+        // Nothing left to do the second time
+        assert!(paths.migrate().is_empty());
+    }
+
+    #[test]
+    fn test_copy_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        fs::create_dir_all(from.join("nested")).unwrap();
+        fs::write(from.join("nested/file"), "x").unwrap();
+        copy_recursive(&from, &dir.path().join("to")).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("to/nested/file")).unwrap(),
+            "x"
+        );
+    }
+}
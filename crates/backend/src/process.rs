@@ -0,0 +1,290 @@
+//! Resource usage of the application's own process tree.
+//!
+//! The Neon module runs inside the Electron main process, so `/proc/self` is
+//! the main process and its descendants are the renderer, GPU and utility
+//! processes. Everything is read through a [`SystemProvider`]; on platforms
+//! without `/proc` the tree is empty.
+
+use crate::provider::SystemProvider;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// Fields of interest from `/proc/<pid>/stat`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub comm: String,
+    pub ppid: u32,
+    /// User plus system CPU time, in clock ticks.
+    pub cpu_ticks: u64,
+    pub num_threads: u32,
+    /// Resident set size, in pages.
+    pub rss_pages: u64,
+}
+
+/// Resource usage of one process.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessStats {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub rss_bytes: u64,
+    pub cpu_time_ms: u64,
+    pub threads: u32,
+    /// Number of open file descriptors, if they could be counted.
+    pub open_fds: Option<u32>,
+}
+
+/// Resource usage of the whole process tree, with per-process details.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ProcessTreeStats {
+    pub processes: Vec<ProcessStats>,
+    pub total_rss_bytes: u64,
+    pub total_cpu_time_ms: u64,
+    pub total_threads: u32,
+    pub total_open_fds: u32,
+}
+
+impl ProcessTreeStats {
+    /// Builds the totals from a list of processes.
+    pub fn from_processes(processes: Vec<ProcessStats>) -> Self {
+        Self {
+            total_rss_bytes: processes.iter().map(|p| p.rss_bytes).sum(),
+            total_cpu_time_ms: processes.iter().map(|p| p.cpu_time_ms).sum(),
+            total_threads: processes.iter().map(|p| p.threads).sum(),
+            total_open_fds: processes.iter().filter_map(|p| p.open_fds).sum(),
+            processes,
+        }
+    }
+}
+
+/// Parses the contents of `/proc/<pid>/stat`.
+///
+/// The command name is wrapped in parentheses and may itself contain spaces
+/// or parentheses, so the remaining fields are located after the last `)`.
+pub fn parse_stat(contents: &str) -> Option<ProcStat> {
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let pid = contents[..open].trim().parse().ok()?;
+    let comm = contents[open + 1..close].to_string();
+
+    // Fields after the command name, starting at field 3 (state).
+    let rest: Vec<&str> = contents[close + 1..].split_whitespace().collect();
+    let field = |n: usize| rest.get(n - 3).and_then(|v| v.parse::<u64>().ok());
+
+    Some(ProcStat {
+        pid,
+        comm,
+        ppid: field(4)? as u32,
+        cpu_ticks: field(14)? + field(15)?,
+        num_threads: field(20)? as u32,
+        rss_pages: field(24)?,
+    })
+}
+
+/// Returns `root` and the pids of all its descendants, given a pid -> ppid map.
+pub fn descendants(root: u32, parents: &HashMap<u32, u32>) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&pid, &ppid) in parents {
+        children.entry(ppid).or_default().push(pid);
+    }
+
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        if let Some(kids) = children.get(&tree[i]) {
+            let mut kids = kids.clone();
+            kids.sort_unstable();
+            tree.extend(kids.into_iter().filter(|k| *k != root));
+        }
+        i += 1;
+    }
+    tree
+}
+
+/// Converts a parsed stat into process stats.
+pub fn stats_from_stat(
+    stat: &ProcStat,
+    page_size: u64,
+    ticks_per_sec: u64,
+    open_fds: Option<u32>,
+) -> ProcessStats {
+    ProcessStats {
+        pid: stat.pid,
+        ppid: stat.ppid,
+        name: stat.comm.clone(),
+        rss_bytes: stat.rss_pages * page_size,
+        cpu_time_ms: stat.cpu_ticks * 1000 / ticks_per_sec.max(1),
+        threads: stat.num_threads,
+        open_fds,
+    }
+}
+
+/// Counts the entries in `/proc/<pid>/fd`; fails without permission.
+fn count_open_fds(system: &dyn SystemProvider, pid: u32) -> Option<u32> {
+    system
+        .read_dir(Path::new(&format!("/proc/{}/fd", pid)))
+        .ok()
+        .map(|entries| entries.len() as u32)
+}
+
+/// Collects resource usage for this process and all of its descendants.
+///
+/// Systems without `/proc` report an empty tree.
+pub fn collect_process_tree(system: &dyn SystemProvider) -> io::Result<ProcessTreeStats> {
+    let entries = match system.read_dir(Path::new("/proc")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ProcessTreeStats::default()),
+        Err(e) => return Err(e),
+    };
+    let page_size = system.page_size();
+    let ticks_per_sec = system.clock_ticks_per_sec();
+
+    let mut stats = HashMap::new();
+    for entry in entries {
+        let Some(pid) = entry
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        // Processes may exit between listing and reading
+        if let Some(stat) = system
+            .read_to_string(&entry.join("stat"))
+            .ok()
+            .and_then(|s| parse_stat(&s))
+        {
+            stats.insert(pid, stat);
+        }
+    }
+
+    let parents = stats.iter().map(|(&pid, s)| (pid, s.ppid)).collect();
+    let processes = descendants(system.current_pid(), &parents)
+        .into_iter()
+        .filter_map(|pid| stats.get(&pid))
+        .map(|stat| {
+            let open_fds = count_open_fds(system, stat.pid);
+            stats_from_stat(stat, page_size, ticks_per_sec, open_fds)
+        })
+        .collect();
+
+    Ok(ProcessTreeStats::from_processes(processes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{FakeSystem, RealSystem};
+
+    const STAT: &str = "4242 (Web Content (1)) S 4200 4242 4200 0 -1 4194560 1234 0 0 0 \
+                        150 50 0 0 20 0 17 0 12345 987654321 2500 18446744073709551615";
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = parse_stat(STAT).unwrap();
+        assert_eq!(
+            stat,
+            ProcStat {
+                pid: 4242,
+                comm: "Web Content (1)".to_string(),
+                ppid: 4200,
+                cpu_ticks: 200,
+                num_threads: 17,
+                rss_pages: 2500,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_stat_rejects_truncated() {
+        assert_eq!(parse_stat("12 (short) S 1 2"), None);
+        assert_eq!(parse_stat("garbage"), None);
+    }
+
+    #[test]
+    fn test_stats_from_stat() {
+        let stat = parse_stat(STAT).unwrap();
+        let stats = stats_from_stat(&stat, 4096, 100, Some(12));
+
+        assert_eq!(stats.rss_bytes, 2500 * 4096);
+        assert_eq!(stats.cpu_time_ms, 2000);
+        assert_eq!(stats.threads, 17);
+        assert_eq!(stats.open_fds, Some(12));
+    }
+
+    #[test]
+    fn test_descendants() {
+        let parents: HashMap<u32, u32> = [(10, 1), (11, 10), (12, 10), (13, 11), (20, 1)]
+            .into_iter()
+            .collect();
+
+        assert_eq!(descendants(10, &parents), vec![10, 11, 12, 13]);
+        assert_eq!(descendants(20, &parents), vec![20]);
+    }
+
+    #[test]
+    fn test_tree_totals() {
+        let process = |pid, rss, fds| ProcessStats {
+            pid,
+            ppid: 1,
+            name: "p".to_string(),
+            rss_bytes: rss,
+            cpu_time_ms: 10,
+            threads: 2,
+            open_fds: fds,
+        };
+        let tree =
+            ProcessTreeStats::from_processes(vec![process(1, 100, Some(3)), process(2, 50, None)]);
+
+        assert_eq!(tree.total_rss_bytes, 150);
+        assert_eq!(tree.total_cpu_time_ms, 20);
+        assert_eq!(tree.total_threads, 4);
+        assert_eq!(tree.total_open_fds, 3);
+    }
+
+    fn stat_line(pid: u32, comm: &str, ppid: u32, rss_pages: u64) -> String {
+        format!("{pid} ({comm}) S {ppid} 0 0 0 -1 0 0 0 0 0 100 50 0 0 20 0 4 0 0 0 {rss_pages} 0")
+    }
+
+    #[test]
+    fn test_collect_process_tree() {
+        let system = FakeSystem::new()
+            .with_pid(100)
+            .with_file("/proc/1/stat", stat_line(1, "init", 0, 10))
+            .with_file("/proc/100/stat", stat_line(100, "electron", 1, 1000))
+            .with_file("/proc/100/fd/0", "")
+            .with_file("/proc/100/fd/1", "")
+            .with_file("/proc/101/stat", stat_line(101, "zygote", 100, 200))
+            .with_file("/proc/102/stat", stat_line(102, "renderer", 101, 300))
+            .with_file("/proc/200/stat", stat_line(200, "other", 1, 50))
+            .with_file("/proc/self/stat", stat_line(100, "electron", 1, 1000));
+
+        let tree = collect_process_tree(&system).unwrap();
+
+        let pids: Vec<u32> = tree.processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![100, 101, 102]);
+        assert_eq!(tree.processes[0].open_fds, Some(2));
+        assert_eq!(tree.processes[1].open_fds, None);
+        assert_eq!(tree.total_rss_bytes, 1500 * 4096);
+        assert_eq!(tree.total_cpu_time_ms, 3 * 1500);
+        assert_eq!(tree.total_threads, 12);
+        assert_eq!(tree.total_open_fds, 2);
+    }
+
+    #[test]
+    fn test_collect_without_proc() {
+        let tree = collect_process_tree(&FakeSystem::new()).unwrap();
+        assert_eq!(tree, ProcessTreeStats::default());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_collect_real_includes_self() {
+        let tree = collect_process_tree(&RealSystem).unwrap();
+        assert_eq!(tree.processes[0].pid, std::process::id());
+        assert!(tree.total_rss_bytes > 0);
+    }
+}
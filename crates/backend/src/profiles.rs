@@ -0,0 +1,131 @@
+//! Named config profiles.
+//!
+//! A profile is a saved copy of the whole config, stored as
+//! `profiles/<name>.json` in the config directory. Using a profile copies it
+//! over the current config.
+
+use crate::config::{AppConfig, ConfigError};
+use crate::fsutil::write_atomic;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Returns the directory profiles are stored in.
+pub fn profiles_dir() -> Option<PathBuf> {
+    AppConfig::config_dir().map(|dir| dir.join("profiles"))
+}
+
+/// Checks that `name` can be used as a file name on every platform.
+fn check_name(name: &str) -> Result<(), ConfigError> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!(
+            "profile names may only contain letters, digits, '-' and '_', not {:?}",
+            name
+        )))
+    }
+}
+
+fn profile_path(dir: &Path, name: &str) -> Result<PathBuf, ConfigError> {
+    check_name(name)?;
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+/// Returns the names of the profiles in `dir`, sorted.
+pub fn list(dir: &Path) -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let name = name.strip_suffix(".json")?;
+            check_name(name).ok().map(|_| name.to_string())
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Saves `config` as the profile `name`, replacing any profile of that name.
+pub fn save(dir: &Path, name: &str, config: &AppConfig) -> Result<(), ConfigError> {
+    let path = profile_path(dir, name)?;
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(config)?;
+    write_atomic(&path, json.as_bytes())?;
+    Ok(())
+}
+
+/// Loads the profile `name`.
+pub fn load(dir: &Path, name: &str) -> Result<AppConfig, ConfigError> {
+    let path = profile_path(dir, name)?;
+    match AppConfig::read_from(&path) {
+        Err(ConfigError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+            Err(ConfigError::NoProfile(name.to_string()))
+        }
+        result => result,
+    }
+}
+
+/// Deletes the profile `name`.
+pub fn delete(dir: &Path, name: &str) -> Result<(), ConfigError> {
+    let path = profile_path(dir, name)?;
+    match fs::remove_file(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            Err(ConfigError::NoProfile(name.to_string()))
+        }
+        result => Ok(result?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_list_load_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join("profiles");
+        assert!(list(&profiles).unwrap().is_empty());
+
+        let config = AppConfig {
+            username: "work".to_string(),
+            ..AppConfig::default()
+        };
+        save(&profiles, "work", &config).unwrap();
+        save(&profiles, "home", &AppConfig::default()).unwrap();
+        fs::write(profiles.join("notes.txt"), "").unwrap();
+        assert_eq!(list(&profiles).unwrap(), ["home", "work"]);
+
+        assert_eq!(load(&profiles, "work").unwrap().username, "work");
+        delete(&profiles, "work").unwrap();
+        assert!(matches!(
+            load(&profiles, "work"),
+            Err(ConfigError::NoProfile(_))
+        ));
+        assert!(matches!(
+            delete(&profiles, "work"),
+            Err(ConfigError::NoProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unsafe_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["", "../config", "a b", "x.json"] {
+            assert!(matches!(
+                save(dir.path(), name, &AppConfig::default()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+    }
+}
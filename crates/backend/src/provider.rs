@@ -0,0 +1,365 @@
+//! Injectable access to the host system.
+//!
+//! All system information functions read the host through a [`SystemProvider`]
+//! instead of touching `/proc`, `/sys` or the environment directly. The Neon
+//! layer passes [`RealSystem`]; tests pass a [`FakeSystem`] populated with
+//! exactly the files and variables they need, so results can be compared
+//! against exact expected values.
+
+use crate::mounts::{self, FsStats};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Source of everything the backend reads from the host system.
+pub trait SystemProvider {
+    /// Reads a whole file as UTF-8.
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Lists the full paths of the entries in a directory, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Returns the target of a symbolic link.
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns true if a file or directory exists at `path`.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Returns an environment variable, or `None` if unset or not valid UTF-8.
+    fn env_var(&self, key: &str) -> Option<String>;
+
+    /// Returns the number of CPUs available to this process.
+    fn available_parallelism(&self) -> Option<usize>;
+
+    /// Returns space and inode numbers for the filesystem holding `path`.
+    fn statvfs(&self, path: &Path) -> io::Result<FsStats>;
+
+    /// Returns the pid of the current process.
+    fn current_pid(&self) -> u32;
+
+    /// Returns the memory page size in bytes.
+    fn page_size(&self) -> u64;
+
+    /// Returns the number of clock ticks per second used in `/proc/<pid>/stat`.
+    fn clock_ticks_per_sec(&self) -> u64;
+
+    /// Returns the operating system name, e.g. "linux".
+    fn os(&self) -> String;
+
+    /// Returns the CPU architecture, e.g. "x86_64".
+    fn arch(&self) -> String;
+
+    /// Returns the OS family, e.g. "unix".
+    fn family(&self) -> String;
+}
+
+/// Provider backed by the real filesystem, `/proc` and process environment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealSystem;
+
+#[cfg(target_os = "linux")]
+fn sysconf(name: libc::c_int, fallback: u64) -> u64 {
+    // SAFETY: sysconf has no preconditions; it returns -1 for unknown names.
+    let value = unsafe { libc::sysconf(name) };
+    if value > 0 { value as u64 } else { fallback }
+}
+
+impl SystemProvider for RealSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect()
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::read_link(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn env_var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+
+    fn available_parallelism(&self) -> Option<usize> {
+        std::thread::available_parallelism().map(|n| n.get()).ok()
+    }
+
+    fn statvfs(&self, path: &Path) -> io::Result<FsStats> {
+        mounts::statvfs(path)
+    }
+
+    fn current_pid(&self) -> u32 {
+        std::process::id()
+    }
+
+    fn page_size(&self) -> u64 {
+        #[cfg(target_os = "linux")]
+        {
+            sysconf(libc::_SC_PAGESIZE, 4096)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            4096
+        }
+    }
+
+    fn clock_ticks_per_sec(&self) -> u64 {
+        #[cfg(target_os = "linux")]
+        {
+            sysconf(libc::_SC_CLK_TCK, 100)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            100
+        }
+    }
+
+    fn os(&self) -> String {
+        std::env::consts::OS.to_string()
+    }
+
+    fn arch(&self) -> String {
+        std::env::consts::ARCH.to_string()
+    }
+
+    fn family(&self) -> String {
+        std::env::consts::FAMILY.to_string()
+    }
+}
+
+/// In-memory provider for tests.
+///
+/// Directories are implied by the files and links added below them.
+/// Everything not explicitly added is reported as missing.
+#[derive(Debug, Clone)]
+pub struct FakeSystem {
+    files: BTreeMap<PathBuf, String>,
+    links: BTreeMap<PathBuf, PathBuf>,
+    env: HashMap<String, String>,
+    fs_stats: HashMap<PathBuf, FsStats>,
+    parallelism: Option<usize>,
+    pid: u32,
+    page_size: u64,
+    clock_ticks_per_sec: u64,
+    os: String,
+    arch: String,
+    family: String,
+}
+
+impl Default for FakeSystem {
+    fn default() -> Self {
+        Self {
+            files: BTreeMap::new(),
+            links: BTreeMap::new(),
+            env: HashMap::new(),
+            fs_stats: HashMap::new(),
+            parallelism: None,
+            pid: 1,
+            page_size: 4096,
+            clock_ticks_per_sec: 100,
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            family: "unix".to_string(),
+        }
+    }
+}
+
+impl FakeSystem {
+    /// Creates an empty fake system: no files, no variables, pid 1, linux/x86_64.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file with the given contents.
+    pub fn with_file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
+    /// Adds a symbolic link pointing at `target`.
+    pub fn with_link(mut self, path: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        self.links.insert(path.into(), target.into());
+        self
+    }
+
+    /// Sets an environment variable.
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the `statvfs` result for a mount point.
+    pub fn with_fs_stats(mut self, path: impl Into<PathBuf>, stats: FsStats) -> Self {
+        self.fs_stats.insert(path.into(), stats);
+        self
+    }
+
+    /// Sets the number of available CPUs.
+    pub fn with_parallelism(mut self, cpus: usize) -> Self {
+        self.parallelism = Some(cpus);
+        self
+    }
+
+    /// Sets the pid of the current process.
+    pub fn with_pid(mut self, pid: u32) -> Self {
+        self.pid = pid;
+        self
+    }
+
+    /// Sets the OS name, architecture and family.
+    pub fn with_platform(mut self, os: &str, arch: &str, family: &str) -> Self {
+        self.os = os.to_string();
+        self.arch = arch.to_string();
+        self.family = family.to_string();
+        self
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .chain(self.links.keys())
+            .any(|p| p != path && p.starts_with(path))
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+impl SystemProvider for FakeSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let path = self.links.get(path).map(PathBuf::as_path).unwrap_or(path);
+        self.files.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.is_dir(path) {
+            return Err(not_found(path));
+        }
+        let children: BTreeSet<PathBuf> = self
+            .files
+            .keys()
+            .chain(self.links.keys())
+            .filter_map(|p| p.strip_prefix(path).ok())
+            .filter_map(|rel| rel.components().next())
+            .map(|first| path.join(first))
+            .collect();
+        Ok(children.into_iter().collect())
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.links.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path) || self.links.contains_key(path) || self.is_dir(path)
+    }
+
+    fn env_var(&self, key: &str) -> Option<String> {
+        self.env.get(key).cloned()
+    }
+
+    fn available_parallelism(&self) -> Option<usize> {
+        self.parallelism
+    }
+
+    fn statvfs(&self, path: &Path) -> io::Result<FsStats> {
+        self.fs_stats
+            .get(path)
+            .copied()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn current_pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn page_size(&self) -> u64 {
+        self.page_size
+    }
+
+    fn clock_ticks_per_sec(&self) -> u64 {
+        self.clock_ticks_per_sec
+    }
+
+    fn os(&self) -> String {
+        self.os.clone()
+    }
+
+    fn arch(&self) -> String {
+        self.arch.clone()
+    }
+
+    fn family(&self) -> String {
+        self.family.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_files_and_dirs() {
+        let system = FakeSystem::new()
+            .with_file("/proc/1/stat", "1 (init) S")
+            .with_file("/proc/22/stat", "22 (app) S")
+            .with_file("/proc/uptime", "1.0 2.0");
+
+        assert_eq!(
+            system.read_to_string(Path::new("/proc/1/stat")).unwrap(),
+            "1 (init) S"
+        );
+        assert!(system.exists(Path::new("/proc/22")));
+        assert!(!system.exists(Path::new("/proc/3")));
+        assert_eq!(
+            system.read_dir(Path::new("/proc")).unwrap(),
+            vec![
+                PathBuf::from("/proc/1"),
+                PathBuf::from("/proc/22"),
+                PathBuf::from("/proc/uptime"),
+            ]
+        );
+        assert_eq!(
+            system.read_dir(Path::new("/sys")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_fake_links_and_env() {
+        let system = FakeSystem::new()
+            .with_file("/usr/share/zoneinfo/UTC", "TZif")
+            .with_link("/etc/localtime", "/usr/share/zoneinfo/UTC")
+            .with_env("LANG", "C.UTF-8");
+
+        assert_eq!(
+            system.read_link(Path::new("/etc/localtime")).unwrap(),
+            PathBuf::from("/usr/share/zoneinfo/UTC")
+        );
+        assert_eq!(
+            system.read_to_string(Path::new("/etc/localtime")).unwrap(),
+            "TZif"
+        );
+        assert_eq!(system.env_var("LANG").as_deref(), Some("C.UTF-8"));
+        assert_eq!(system.env_var("LC_ALL"), None);
+    }
+
+    #[test]
+    fn test_real_system_reads_self() {
+        let system = RealSystem;
+        assert_eq!(system.current_pid(), std::process::id());
+        assert!(system.available_parallelism().unwrap_or(1) >= 1);
+        assert_eq!(system.os(), std::env::consts::OS);
+    }
+}
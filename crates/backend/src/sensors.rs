@@ -0,0 +1,349 @@
+//! Hardware sensor readings from sysfs.
+//!
+//! Temperatures, fan speeds and voltages are read from `class/hwmon`, and
+//! thermal zones from `class/thermal`, below a configurable sysfs root so the
+//! parsing can be tested against a fake directory tree (or a [`FakeSystem`]).
+//!
+//! [`FakeSystem`]: crate::provider::FakeSystem
+
+use crate::provider::SystemProvider;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Where sysfs is mounted on a real system.
+pub const DEFAULT_SYSFS_ROOT: &str = "/sys";
+
+/// What a sensor measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorKind {
+    /// Degrees Celsius.
+    Temperature,
+    /// Revolutions per minute.
+    Fan,
+    /// Volts.
+    Voltage,
+}
+
+impl SensorKind {
+    /// Returns the hwmon attribute prefix for this kind.
+    fn prefix(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "temp",
+            SensorKind::Fan => "fan",
+            SensorKind::Voltage => "in",
+        }
+    }
+
+    /// Returns the divisor converting raw hwmon values to display units.
+    fn scale(&self) -> f64 {
+        match self {
+            SensorKind::Temperature | SensorKind::Voltage => 1000.0,
+            SensorKind::Fan => 1.0,
+        }
+    }
+}
+
+/// One sensor reading, already converted to display units.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensorReading {
+    /// Sysfs device the reading came from, e.g. "hwmon0" or "thermal_zone1".
+    pub source: String,
+    /// Driver or zone type, e.g. "coretemp" or "x86_pkg_temp".
+    pub chip: String,
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+/// Reads a sysfs attribute, trimming the trailing newline.
+fn read_attr(system: &dyn SystemProvider, path: &Path) -> Option<String> {
+    system
+        .read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Reads a numeric sysfs attribute and divides it by `scale`.
+fn read_scaled(system: &dyn SystemProvider, path: &Path, scale: f64) -> Option<f64> {
+    read_attr(system, path)?
+        .parse::<f64>()
+        .ok()
+        .map(|v| v / scale)
+}
+
+/// Lists the entries of a directory sorted by name, with numeric suffixes in
+/// numeric order (`hwmon2` before `hwmon10`).
+fn sorted_entries(system: &dyn SystemProvider, dir: &Path) -> Vec<PathBuf> {
+    let mut entries = system.read_dir(dir).unwrap_or_default();
+    entries.sort_by_key(|p| {
+        let name = p
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        let (stem, number) = name.split_at(name.len() - digits);
+        (stem.to_string(), number.parse::<u64>().unwrap_or(0))
+    });
+    entries
+}
+
+/// Reads all sensors below `sysfs_root`. Missing directories yield no readings.
+pub fn read_sensors(system: &dyn SystemProvider, sysfs_root: &Path) -> Vec<SensorReading> {
+    let mut readings = read_hwmon(system, sysfs_root);
+    readings.extend(read_thermal(system, sysfs_root));
+    readings
+}
+
+/// Reads the `tempN_*`, `fanN_*` and `inN_*` attributes of every hwmon device.
+pub fn read_hwmon(system: &dyn SystemProvider, sysfs_root: &Path) -> Vec<SensorReading> {
+    let mut readings = Vec::new();
+
+    for device in sorted_entries(system, &sysfs_root.join("class/hwmon")) {
+        let source = device
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        // Older drivers keep their attributes in the `device` subdirectory
+        let attr_dir =
+            if system.exists(&device.join("name")) || !system.exists(&device.join("device/name")) {
+                device.clone()
+            } else {
+                device.join("device")
+            };
+        let chip = read_attr(system, &attr_dir.join("name")).unwrap_or_else(|| source.clone());
+
+        for kind in [
+            SensorKind::Temperature,
+            SensorKind::Fan,
+            SensorKind::Voltage,
+        ] {
+            for index in input_indices(system, &attr_dir, kind.prefix()) {
+                let attr =
+                    |suffix: &str| attr_dir.join(format!("{}{}_{}", kind.prefix(), index, suffix));
+                let Some(value) = read_scaled(system, &attr("input"), kind.scale()) else {
+                    continue;
+                };
+                readings.push(SensorReading {
+                    source: source.clone(),
+                    chip: chip.clone(),
+                    label: read_attr(system, &attr("label"))
+                        .unwrap_or_else(|| format!("{}{}", kind.prefix(), index)),
+                    kind,
+                    value,
+                    max: read_scaled(system, &attr("max"), kind.scale()),
+                    critical: read_scaled(system, &attr("crit"), kind.scale()),
+                });
+            }
+        }
+    }
+
+    readings
+}
+
+/// Returns the sorted indices N for which `{prefix}N_input` exists in `dir`.
+fn input_indices(system: &dyn SystemProvider, dir: &Path, prefix: &str) -> Vec<u32> {
+    let mut indices: Vec<u32> = system
+        .read_dir(dir)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            name.strip_prefix(prefix)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    indices.sort_unstable();
+    indices
+}
+
+/// Reads every `thermal_zoneN`, using its trip points for the thresholds.
+pub fn read_thermal(system: &dyn SystemProvider, sysfs_root: &Path) -> Vec<SensorReading> {
+    let mut readings = Vec::new();
+
+    for zone in sorted_entries(system, &sysfs_root.join("class/thermal")) {
+        let source = zone
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if !source.starts_with("thermal_zone") {
+            continue;
+        }
+        let Some(value) = read_scaled(system, &zone.join("temp"), 1000.0) else {
+            continue;
+        };
+        let zone_type = read_attr(system, &zone.join("type")).unwrap_or_else(|| source.clone());
+
+        let trip = |wanted: &str| {
+            (0..)
+                .map_while(|n| {
+                    let kind = read_attr(system, &zone.join(format!("trip_point_{}_type", n)))?;
+                    Some((n, kind))
+                })
+                .find(|(_, kind)| kind == wanted)
+                .and_then(|(n, _)| {
+                    read_scaled(system, &zone.join(format!("trip_point_{}_temp", n)), 1000.0)
+                })
+        };
+
+        readings.push(SensorReading {
+            source: source.clone(),
+            chip: zone_type.clone(),
+            label: zone_type,
+            kind: SensorKind::Temperature,
+            value,
+            max: trip("hot"),
+            critical: trip("critical"),
+        });
+    }
+
+    readings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{FakeSystem, RealSystem};
+    use std::fs;
+
+    fn write(root: &Path, rel: &str, contents: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, format!("{}\n", contents)).unwrap();
+    }
+
+    fn fake_sysfs() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        write(root, "class/hwmon/hwmon0/name", "coretemp");
+        write(root, "class/hwmon/hwmon0/temp1_input", "45000");
+        write(root, "class/hwmon/hwmon0/temp1_label", "Package id 0");
+        write(root, "class/hwmon/hwmon0/temp1_max", "80000");
+        write(root, "class/hwmon/hwmon0/temp1_crit", "100000");
+        write(root, "class/hwmon/hwmon0/temp2_input", "41500");
+
+        write(root, "class/hwmon/hwmon10/name", "nct6775");
+        write(root, "class/hwmon/hwmon10/fan1_input", "1200");
+        write(root, "class/hwmon/hwmon10/fan1_label", "CPU fan");
+        write(root, "class/hwmon/hwmon10/in0_input", "1104");
+        write(root, "class/hwmon/hwmon10/in0_crit", "1500");
+
+        write(root, "class/hwmon/hwmon2/device/name", "legacy");
+        write(root, "class/hwmon/hwmon2/device/temp1_input", "30000");
+
+        write(root, "class/thermal/thermal_zone0/type", "x86_pkg_temp");
+        write(root, "class/thermal/thermal_zone0/temp", "52000");
+        write(
+            root,
+            "class/thermal/thermal_zone0/trip_point_0_type",
+            "passive",
+        );
+        write(
+            root,
+            "class/thermal/thermal_zone0/trip_point_0_temp",
+            "90000",
+        );
+        write(
+            root,
+            "class/thermal/thermal_zone0/trip_point_1_type",
+            "critical",
+        );
+        write(
+            root,
+            "class/thermal/thermal_zone0/trip_point_1_temp",
+            "105000",
+        );
+        write(root, "class/thermal/cooling_device0/type", "Processor");
+
+        dir
+    }
+
+    #[test]
+    fn test_read_hwmon() {
+        let dir = fake_sysfs();
+        let readings = read_hwmon(&RealSystem, dir.path());
+
+        assert_eq!(
+            readings[0],
+            SensorReading {
+                source: "hwmon0".to_string(),
+                chip: "coretemp".to_string(),
+                label: "Package id 0".to_string(),
+                kind: SensorKind::Temperature,
+                value: 45.0,
+                max: Some(80.0),
+                critical: Some(100.0),
+            }
+        );
+        assert_eq!(readings[1].label, "temp2");
+        assert_eq!(readings[1].value, 41.5);
+        assert_eq!(readings[1].critical, None);
+
+        // hwmon2 sorts before hwmon10 and uses the legacy device/ layout
+        assert_eq!(readings[2].chip, "legacy");
+        assert_eq!(readings[2].value, 30.0);
+
+        assert_eq!(readings[3].kind, SensorKind::Fan);
+        assert_eq!(readings[3].label, "CPU fan");
+        assert_eq!(readings[3].value, 1200.0);
+
+        assert_eq!(readings[4].kind, SensorKind::Voltage);
+        assert_eq!(readings[4].value, 1.104);
+        assert_eq!(readings[4].critical, Some(1.5));
+        assert_eq!(readings.len(), 5);
+    }
+
+    #[test]
+    fn test_read_thermal() {
+        let dir = fake_sysfs();
+        let readings = read_thermal(&RealSystem, dir.path());
+
+        assert_eq!(
+            readings,
+            vec![SensorReading {
+                source: "thermal_zone0".to_string(),
+                chip: "x86_pkg_temp".to_string(),
+                label: "x86_pkg_temp".to_string(),
+                kind: SensorKind::Temperature,
+                value: 52.0,
+                max: None,
+                critical: Some(105.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_missing_root_yields_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_sensors(&RealSystem, &dir.path().join("nope")).is_empty());
+    }
+
+    #[test]
+    fn test_read_sensors_from_fake_system() {
+        let system = FakeSystem::new()
+            .with_file("/sys/class/hwmon/hwmon0/name", "acpitz\n")
+            .with_file("/sys/class/hwmon/hwmon0/temp1_input", "27800\n")
+            .with_file("/sys/class/thermal/thermal_zone0/type", "acpitz\n")
+            .with_file("/sys/class/thermal/thermal_zone0/temp", "27800\n");
+
+        let readings = read_sensors(&system, Path::new(DEFAULT_SYSFS_ROOT));
+        let summary: Vec<(&str, &str, f64)> = readings
+            .iter()
+            .map(|r| (r.source.as_str(), r.label.as_str(), r.value))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("hwmon0", "temp1", 27.8), ("thermal_zone0", "acpitz", 27.8)]
+        );
+    }
+}
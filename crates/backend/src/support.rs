@@ -0,0 +1,494 @@
+//! Support bundles: one zip archive with what's needed to look into a bug report.
+//!
+//! A bundle holds the config file with personal data redacted, the most
+//! recent log files, crash records, system and environment information, the
+//! app, runtime and dependency versions, and a `manifest.json` listing every
+//! file with its size and SHA-256 checksum. [`plan`] decides what goes in,
+//! so the same list can be shown as a preview before anything is written.
+
+use crate::config::AppConfig;
+use crate::configcache;
+use crate::configformat::Format;
+use crate::core;
+use crate::crash::{self, BuildInfo};
+use crate::fsutil::write_atomic_with;
+use crate::logging::{self, Redactor, log_file_path};
+use crate::provider::SystemProvider;
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Format version of the bundle layout, recorded in the manifest.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Number of log files included, counting the current one.
+pub const MAX_LOG_FILES: u32 = 3;
+
+/// Name of the manifest inside the archive.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Lockfile of the build, copied by the build script.
+const CARGO_LOCK: &str = include_str!(concat!(env!("OUT_DIR"), "/Cargo.lock"));
+
+/// Where the contents of a bundle file come from.
+#[derive(Debug, Clone, PartialEq)]
+enum Contents {
+    /// Copied from disk while writing, so large logs never sit in memory.
+    File(PathBuf),
+    Data(Vec<u8>),
+}
+
+/// One file to be put in a bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleItem {
+    /// Path inside the archive, e.g. "logs/app.log".
+    pub name: String,
+    pub description: String,
+    contents: Contents,
+}
+
+impl BundleItem {
+    fn data(name: impl Into<String>, description: &str, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.to_string(),
+            contents: Contents::Data(data),
+        }
+    }
+
+    fn file(name: impl Into<String>, description: &str, path: PathBuf) -> Self {
+        Self {
+            name: name.into(),
+            description: description.to_string(),
+            contents: Contents::File(path),
+        }
+    }
+
+    /// Size of the file in bytes, as far as is known before writing.
+    pub fn bytes(&self) -> u64 {
+        match &self.contents {
+            Contents::File(path) => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            Contents::Data(data) => data.len() as u64,
+        }
+    }
+}
+
+/// A bundle file as shown in the preview.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreviewItem {
+    pub name: String,
+    pub description: String,
+    pub bytes: u64,
+}
+
+/// Returns the preview of a planned bundle.
+pub fn preview(items: &[BundleItem]) -> Vec<PreviewItem> {
+    items
+        .iter()
+        .map(|item| PreviewItem {
+            name: item.name.clone(),
+            description: item.description.clone(),
+            bytes: item.bytes(),
+        })
+        .collect()
+}
+
+/// Everything a bundle is made from.
+#[derive(Debug, Clone)]
+pub struct BundleSources {
+    pub config_path: Option<PathBuf>,
+    pub log_dir: Option<PathBuf>,
+    pub crash_dir: Option<PathBuf>,
+    pub redactor: Redactor,
+    /// System and environment information.
+    pub system: Value,
+    /// App, runtime and dependency versions.
+    pub versions: Value,
+}
+
+impl BundleSources {
+    /// Collects the sources of the running app.
+    ///
+    /// `runtime` holds versions only the main process knows, such as Electron's.
+    pub fn current(system: &dyn SystemProvider, runtime: Value) -> Self {
+        let config = configcache::current();
+        Self {
+            config_path: AppConfig::config_path(),
+            log_dir: logging::log_dir(),
+            crash_dir: crash::crash_dir(),
+            redactor: Redactor::current(&config.username),
+            system: json!({
+                "system": core::get_system_info(system),
+                "environment": core::get_environment_info(system),
+            }),
+            versions: json!({
+                "app": BuildInfo::current(),
+                "runtime": runtime,
+                "dependencies": dependencies(CARGO_LOCK),
+            }),
+        }
+    }
+}
+
+/// Lists the packages in a `Cargo.lock` as `{ name, version }` objects.
+pub fn dependencies(lockfile: &str) -> Vec<Value> {
+    let unquote = |s: &str| s.trim().trim_matches('"').to_string();
+    let mut packages = Vec::new();
+    let mut name = None;
+    for line in lockfile.lines().map(str::trim) {
+        if line == "[[package]]" {
+            name = None;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(unquote(value));
+        } else if let Some(value) = line.strip_prefix("version = ")
+            && let Some(name) = name.take()
+        {
+            packages.push(json!({ "name": name, "version": unquote(value) }));
+        }
+    }
+    packages
+}
+
+/// Returns the config file with the username, secrets and home paths redacted.
+fn redacted_config(path: &Path, redactor: &Redactor) -> Option<Vec<u8>> {
+    let contents = fs::read(path).ok()?;
+    let format = Format::from_path(path).unwrap_or_default();
+    let redacted = match format.parse::<Value>(&contents) {
+        Ok(mut value) => {
+            redactor.redact(&mut value);
+            // TOML is rendered into the original document, so comments
+            // are kept and need redacting too
+            let rendered = format.render(&value, Some(&contents)).ok()?;
+            redactor.redact_str(&String::from_utf8_lossy(&rendered))
+        }
+        // Broken configs are worth seeing as they are, minus personal data
+        Err(_) => redactor.redact_str(&String::from_utf8_lossy(&contents)),
+    };
+    Some(redacted.into_bytes())
+}
+
+/// Returns a crash record with home paths and the username redacted from
+/// its message and backtrace.
+fn redacted_crash(path: &Path, redactor: &Redactor) -> Option<Vec<u8>> {
+    let contents = fs::read(path).ok()?;
+    match serde_json::from_slice::<Value>(&contents) {
+        Ok(mut value) => {
+            redactor.redact(&mut value);
+            serde_json::to_vec_pretty(&value).ok()
+        }
+        Err(_) => Some(
+            redactor
+                .redact_str(&String::from_utf8_lossy(&contents))
+                .into_bytes(),
+        ),
+    }
+}
+
+/// Decides which files go into a bundle, in archive order.
+///
+/// The manifest isn't listed; it is added when the bundle is written.
+pub fn plan(sources: &BundleSources) -> Vec<BundleItem> {
+    let mut items = Vec::new();
+
+    if let Some(path) = sources.config_path.as_deref()
+        && let Some(config) = redacted_config(path, &sources.redactor)
+    {
+        let format = Format::from_path(path).unwrap_or_default();
+        items.push(BundleItem::data(
+            format!("config.{}", format.extension()),
+            "Settings, with the username redacted",
+            config,
+        ));
+    }
+
+    let pretty = |value: &Value| serde_json::to_vec_pretty(value).unwrap_or_default();
+    items.push(BundleItem::data(
+        "system.json",
+        "Operating system and environment",
+        pretty(&sources.system),
+    ));
+    items.push(BundleItem::data(
+        "versions.json",
+        "App, runtime and dependency versions",
+        pretty(&sources.versions),
+    ));
+
+    if let Some(dir) = &sources.log_dir {
+        for index in 0..MAX_LOG_FILES {
+            let path = log_file_path(dir, index);
+            if !path.exists() {
+                break;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            items.push(BundleItem::file(
+                format!("logs/{}", name),
+                "Log file",
+                path.clone(),
+            ));
+        }
+    }
+
+    if let Some(dir) = &sources.crash_dir {
+        for path in crash::list(dir).unwrap_or_default() {
+            let Some(record) = redacted_crash(&path, &sources.redactor) else {
+                continue;
+            };
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            items.push(BundleItem::data(
+                format!("crashes/{}", name),
+                "Crash record, with home paths and the username redacted",
+                record,
+            ));
+        }
+    }
+
+    items
+}
+
+/// Passes writes through while computing their size and SHA-256.
+struct HashingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<W: Write> Write for HashingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A file as listed in the manifest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Result of writing a bundle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BundleSummary {
+    pub path: String,
+    /// Size of the archive.
+    pub bytes: u64,
+    /// Files in the archive, not counting the manifest.
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Writes the planned files and a manifest to a zip archive at `dest`.
+///
+/// The archive is written atomically. Files that disappear between planning
+/// and writing, such as a rotated log, are left out.
+pub fn write_bundle(
+    dest: &Path,
+    items: &[BundleItem],
+    timestamp_ms: u64,
+) -> io::Result<BundleSummary> {
+    let files = write_atomic_with(dest, |file| {
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut entries = Vec::new();
+
+        for item in items {
+            let mut source: Box<dyn Read + '_> = match &item.contents {
+                Contents::File(path) => match File::open(path) {
+                    Ok(file) => Box::new(file),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                },
+                Contents::Data(data) => Box::new(data.as_slice()),
+            };
+            zip.start_file(item.name.as_str(), options)?;
+            let mut writer = HashingWriter {
+                inner: &mut zip,
+                hasher: Sha256::new(),
+                bytes: 0,
+            };
+            io::copy(&mut source, &mut writer)?;
+            entries.push(ManifestEntry {
+                name: item.name.clone(),
+                bytes: writer.bytes,
+                sha256: format!("{:x}", writer.hasher.finalize()),
+            });
+        }
+
+        let manifest = json!({
+            "bundle_version": BUNDLE_VERSION,
+            "created_ms": timestamp_ms,
+            "app_version": env!("CARGO_PKG_VERSION"),
+            "files": entries,
+        });
+        zip.start_file(MANIFEST_NAME, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?)?;
+        zip.finish()?;
+        Ok(entries)
+    })?;
+
+    Ok(BundleSummary {
+        path: dest.to_string_lossy().to_string(),
+        bytes: fs::metadata(dest)?.len(),
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(dir: &Path) -> BundleSources {
+        let config = dir.join("config.json");
+        fs::write(
+            &config,
+            r#"{"username": "carol", "notes": "/home/carol/notes.txt", "dark_mode": true}"#,
+        )
+        .unwrap();
+        let logs = dir.join("logs");
+        fs::create_dir_all(&logs).unwrap();
+        for index in 0..4 {
+            fs::write(log_file_path(&logs, index), format!("log {}\n", index)).unwrap();
+        }
+        let crashes = dir.join("crashes");
+        fs::create_dir_all(&crashes).unwrap();
+        fs::write(
+            crashes.join("crash-5-backend.json"),
+            r#"{"message": "called unwrap on carol's None", "backtrace": "at /home/carol/.cargo/registry/src/lib.rs:7"}"#,
+        )
+        .unwrap();
+
+        BundleSources {
+            config_path: Some(config),
+            log_dir: Some(logs),
+            crash_dir: Some(crashes),
+            redactor: Redactor::new(Some(Path::new("/home/carol")), "carol"),
+            system: json!({ "system": { "platform": "linux" } }),
+            versions: json!({ "dependencies": [] }),
+        }
+    }
+
+    fn read_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> String {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = plan(&sources(dir.path()));
+        let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "config.json",
+                "system.json",
+                "versions.json",
+                "logs/app.log",
+                "logs/app.log.1",
+                "logs/app.log.2",
+                "crashes/crash-5-backend.json",
+            ]
+        );
+
+        let config = match &items[0].contents {
+            Contents::Data(data) => String::from_utf8(data.clone()).unwrap(),
+            Contents::File(_) => panic!("config should be redacted in memory"),
+        };
+        assert!(!config.contains("carol"));
+        assert!(config.contains("~/notes.txt"));
+        assert_eq!(preview(&items)[3].bytes, 6);
+
+        let Contents::Data(crash) = &items[6].contents else {
+            panic!("crash records should be redacted in memory");
+        };
+        let crash: Value = serde_json::from_slice(crash).unwrap();
+        assert_eq!(crash["message"], "called unwrap on [redacted]'s None");
+        assert_eq!(crash["backtrace"], "at ~/.cargo/registry/src/lib.rs:7");
+    }
+
+    #[test]
+    fn test_plan_redacts_toml_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sources = sources(dir.path());
+        let config = dir.path().join("config.toml");
+        fs::write(&config, "# carol's laptop\nusername = \"carol\"\n").unwrap();
+        sources.config_path = Some(config);
+
+        let items = plan(&sources);
+        assert_eq!(items[0].name, "config.toml");
+        let Contents::Data(data) = &items[0].contents else {
+            panic!("config should be redacted in memory");
+        };
+        assert_eq!(
+            String::from_utf8_lossy(data),
+            "# [redacted]'s laptop\nusername = \"[redacted]\"\n"
+        );
+    }
+
+    #[test]
+    fn test_write_bundle_with_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = plan(&sources(dir.path()));
+        let dest = dir.path().join("bundle.zip");
+        // A file removed after planning is skipped
+        fs::remove_file(dir.path().join("logs/app.log.2")).unwrap();
+
+        let summary = write_bundle(&dest, &items, 42).unwrap();
+        assert_eq!(summary.files.len(), items.len() - 1);
+        assert_eq!(summary.bytes, fs::metadata(&dest).unwrap().len());
+
+        let mut archive = zip::ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        assert_eq!(read_entry(&mut archive, "logs/app.log.1"), "log 1\n");
+
+        let manifest: Value =
+            serde_json::from_str(&read_entry(&mut archive, MANIFEST_NAME)).unwrap();
+        assert_eq!(manifest["created_ms"], 42);
+        let log = &manifest["files"][3];
+        assert_eq!(log["name"], "logs/app.log");
+        assert_eq!(log["bytes"], 6);
+        // SHA-256 of "log 0\n"
+        assert_eq!(log["sha256"], format!("{:x}", Sha256::digest(b"log 0\n")));
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let lock = r#"
+version = 4
+
+[[package]]
+name = "backend"
+version = "0.1.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+        assert_eq!(
+            dependencies(lock),
+            [
+                json!({ "name": "backend", "version": "0.1.0" }),
+                json!({ "name": "serde", "version": "1.0.200" }),
+            ]
+        );
+    }
+}
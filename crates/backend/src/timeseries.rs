@@ -0,0 +1,908 @@
+//! On-disk time-series history of collected metrics.
+//!
+//! Every metrics sample is appended to `raw.bin` as a fixed-width binary
+//! record. Completed minutes are rolled up into `1m.bin` and completed hours
+//! into `1h.bin`, each record holding the min, max and mean of every series.
+//! Because records have a fixed width, a time range is found by binary
+//! search, and a record cut short by a crash is detected from the file length
+//! and dropped when the store is opened.
+//!
+//! Each file starts with an 8-byte header: the magic `TSDB`, a format
+//! version, the resolution and the record size. All numbers are little-endian.
+
+use crate::config::{AppConfig, MetricsRetention};
+use crate::configcache;
+use crate::fsutil::write_atomic;
+use crate::metrics::MetricsSample;
+use crate::sensors::SensorKind;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Names of the recorded series, in record order.
+pub const SERIES: [&str; 6] = [
+    "rss_bytes",
+    "cpu_percent",
+    "wasm_memory_bytes",
+    "threads",
+    "open_fds",
+    "max_temperature",
+];
+
+const SERIES_COUNT: usize = SERIES.len();
+
+const MAGIC: &[u8; 4] = b"TSDB";
+const VERSION: u8 = 1;
+const HEADER_SIZE: u64 = 8;
+
+/// How often retention is enforced while appending.
+const RETENTION_INTERVAL_MS: u64 = 60 * 60 * 1000;
+
+/// Returns the index of a series in [`SERIES`].
+pub fn series_index(name: &str) -> Option<usize> {
+    SERIES.iter().position(|s| *s == name)
+}
+
+/// Values of all series at one point in time; missing values are NaN.
+pub type Values = [f32; SERIES_COUNT];
+
+/// Extracts the recorded series from a metrics sample.
+pub fn values_from_sample(sample: &MetricsSample) -> Values {
+    let optional = |v: Option<f64>| v.map_or(f32::NAN, |v| v as f32);
+    let max_temperature = sample
+        .sensors
+        .iter()
+        .filter(|r| r.kind == SensorKind::Temperature)
+        .map(|r| r.value)
+        .reduce(f64::max);
+
+    [
+        sample.process.total_rss_bytes as f32,
+        optional(sample.cpu_percent),
+        optional(sample.wasm_memory_bytes.map(|b| b as f64)),
+        sample.process.total_threads as f32,
+        sample.process.total_open_fds as f32,
+        optional(max_temperature),
+    ]
+}
+
+/// Resolution of stored records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Resolution {
+    #[serde(rename = "raw")]
+    Raw,
+    #[serde(rename = "1m")]
+    Minute,
+    #[serde(rename = "1h")]
+    Hour,
+}
+
+impl Resolution {
+    const ALL: [Resolution; 3] = [Resolution::Raw, Resolution::Minute, Resolution::Hour];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Resolution::Raw => "raw.bin",
+            Resolution::Minute => "1m.bin",
+            Resolution::Hour => "1h.bin",
+        }
+    }
+
+    /// Width of a roll-up bucket, or the nominal sample interval for raw records.
+    fn bucket_ms(&self) -> u64 {
+        match self {
+            Resolution::Raw => 2_000,
+            Resolution::Minute => 60_000,
+            Resolution::Hour => 3_600_000,
+        }
+    }
+
+    fn record_size(&self) -> usize {
+        match self {
+            Resolution::Raw => RawRecord::SIZE,
+            Resolution::Minute | Resolution::Hour => Aggregate::SIZE,
+        }
+    }
+
+    fn retention_ms(&self, retention: &MetricsRetention) -> u64 {
+        const HOUR_MS: u64 = 3_600_000;
+        match self {
+            Resolution::Raw => retention.raw_hours as u64 * HOUR_MS,
+            Resolution::Minute => retention.minute_days as u64 * 24 * HOUR_MS,
+            Resolution::Hour => retention.hour_days as u64 * 24 * HOUR_MS,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            Resolution::Raw => 0,
+            Resolution::Minute => 1,
+            Resolution::Hour => 2,
+        }
+    }
+}
+
+/// One raw sample: timestamp plus one `f32` per series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawRecord {
+    pub timestamp_ms: u64,
+    pub values: Values,
+}
+
+impl RawRecord {
+    const SIZE: usize = 8 + 4 * SERIES_COUNT;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::SIZE);
+        buf.extend_from_slice(&self.timestamp_ms.to_le_bytes());
+        for v in self.values {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let mut values = [0.0; SERIES_COUNT];
+        for (i, v) in values.iter_mut().enumerate() {
+            *v = read_f32(bytes, 8 + 4 * i);
+        }
+        Self {
+            timestamp_ms: read_u64(bytes, 0),
+            values,
+        }
+    }
+}
+
+/// Min, max and mean of every series over one bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    /// Start of the bucket.
+    pub timestamp_ms: u64,
+    /// Number of raw samples in the bucket.
+    pub count: u32,
+    pub min: Values,
+    pub max: Values,
+    pub mean: Values,
+}
+
+impl Aggregate {
+    const SIZE: usize = 12 + 12 * SERIES_COUNT;
+
+    fn empty(timestamp_ms: u64) -> Self {
+        Self {
+            timestamp_ms,
+            count: 0,
+            min: [f32::NAN; SERIES_COUNT],
+            max: [f32::NAN; SERIES_COUNT],
+            mean: [f32::NAN; SERIES_COUNT],
+        }
+    }
+
+    /// Folds another aggregate (or a raw sample, as an aggregate of one) into this one.
+    ///
+    /// NaN values are skipped, so a series missing from some samples keeps
+    /// the statistics of the samples that had it.
+    fn merge(&mut self, other: &Aggregate) {
+        let total = (self.count + other.count) as f32;
+        for i in 0..SERIES_COUNT {
+            self.min[i] = self.min[i].min(other.min[i]);
+            self.max[i] = self.max[i].max(other.max[i]);
+            self.mean[i] = match (self.mean[i].is_nan(), other.mean[i].is_nan()) {
+                (true, _) => other.mean[i],
+                (false, true) => self.mean[i],
+                (false, false) => {
+                    (self.mean[i] * self.count as f32 + other.mean[i] * other.count as f32) / total
+                }
+            };
+        }
+        self.count += other.count;
+    }
+
+    fn from_raw(record: &RawRecord) -> Self {
+        Self {
+            timestamp_ms: record.timestamp_ms,
+            count: 1,
+            min: record.values,
+            max: record.values,
+            mean: record.values,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::SIZE);
+        buf.extend_from_slice(&self.timestamp_ms.to_le_bytes());
+        buf.extend_from_slice(&self.count.to_le_bytes());
+        for values in [&self.min, &self.max, &self.mean] {
+            for v in values {
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        buf
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let mut aggregate = Self::empty(read_u64(bytes, 0));
+        aggregate.count = u32::from_le_bytes(bytes[8..12].try_into().unwrap_or_default());
+        for (n, values) in [&mut aggregate.min, &mut aggregate.max, &mut aggregate.mean]
+            .into_iter()
+            .enumerate()
+        {
+            for (i, v) in values.iter_mut().enumerate() {
+                *v = read_f32(bytes, 12 + 4 * (n * SERIES_COUNT + i));
+            }
+        }
+        aggregate
+    }
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap_or_default())
+}
+
+fn read_f32(bytes: &[u8], at: usize) -> f32 {
+    f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap_or_default())
+}
+
+/// An append-only file of fixed-width records after a short header.
+struct RecordFile {
+    path: PathBuf,
+    resolution: Resolution,
+    file: File,
+}
+
+impl RecordFile {
+    /// Opens or creates the file, dropping a truncated trailing record.
+    ///
+    /// A file with a foreign or outdated header is started afresh.
+    fn open(dir: &Path, resolution: Resolution) -> io::Result<Self> {
+        let path = dir.join(resolution.file_name());
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let header = Self::header(resolution);
+        let mut existing = [0u8; HEADER_SIZE as usize];
+        let len = file.metadata()?.len();
+        let valid = len >= HEADER_SIZE && {
+            file.read_exact(&mut existing)?;
+            existing == header
+        };
+        if !valid {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&header)?;
+        } else {
+            let size = resolution.record_size() as u64;
+            let complete = (len - HEADER_SIZE) / size * size + HEADER_SIZE;
+            if complete != len {
+                file.set_len(complete)?;
+            }
+        }
+
+        Ok(Self {
+            path,
+            resolution,
+            file,
+        })
+    }
+
+    fn header(resolution: Resolution) -> [u8; HEADER_SIZE as usize] {
+        let size = (resolution.record_size() as u16).to_le_bytes();
+        [
+            MAGIC[0],
+            MAGIC[1],
+            MAGIC[2],
+            MAGIC[3],
+            VERSION,
+            resolution.tag(),
+            size[0],
+            size[1],
+        ]
+    }
+
+    fn record_size(&self) -> u64 {
+        self.resolution.record_size() as u64
+    }
+
+    /// Returns the number of complete records.
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len().saturating_sub(HEADER_SIZE) / self.record_size())
+    }
+
+    /// Reads `count` records starting at record `index`.
+    fn read(&mut self, index: u64, count: u64) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; (count * self.record_size()) as usize];
+        self.file
+            .seek(SeekFrom::Start(HEADER_SIZE + index * self.record_size()))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn timestamp_at(&mut self, index: u64) -> io::Result<u64> {
+        Ok(read_u64(&self.read(index, 1)?, 0))
+    }
+
+    /// Returns the index of the first record at or after `timestamp_ms`.
+    fn lower_bound(&mut self, timestamp_ms: u64) -> io::Result<u64> {
+        let (mut lo, mut hi) = (0, self.len()?);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.timestamp_at(mid)? < timestamp_ms {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(lo)
+    }
+
+    /// Reads all records with `from_ms <= timestamp <= to_ms`.
+    fn read_range(&mut self, from_ms: u64, to_ms: u64) -> io::Result<Vec<Vec<u8>>> {
+        let start = self.lower_bound(from_ms)?;
+        let end = self.lower_bound(to_ms.saturating_add(1))?;
+        if end <= start {
+            return Ok(Vec::new());
+        }
+        let size = self.record_size() as usize;
+        Ok(self
+            .read(start, end - start)?
+            .chunks_exact(size)
+            .map(<[u8]>::to_vec)
+            .collect())
+    }
+
+    /// Returns the last record, if any.
+    fn last(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match self.len()? {
+            0 => Ok(None),
+            n => self.read(n - 1, 1).map(Some),
+        }
+    }
+
+    fn append(&mut self, record: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(record)
+    }
+
+    /// Drops all records older than `cutoff_ms`, rewriting the file atomically.
+    fn drop_before(&mut self, cutoff_ms: u64) -> io::Result<()> {
+        let first_kept = self.lower_bound(cutoff_ms)?;
+        if first_kept == 0 {
+            return Ok(());
+        }
+        let remaining = self.len()? - first_kept;
+        let mut contents = Self::header(self.resolution).to_vec();
+        contents.extend(self.read(first_kept, remaining)?);
+
+        write_atomic(&self.path, &contents)?;
+        self.file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// A downsampled point of one series.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RangePoint {
+    pub timestamp_ms: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Number of raw samples the point summarizes.
+    pub count: u32,
+}
+
+/// Result of a range query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeQuery {
+    pub series: String,
+    /// Resolution the points were read from before downsampling.
+    pub resolution: Resolution,
+    pub points: Vec<RangePoint>,
+}
+
+/// Append-only metrics store with minute and hour roll-ups.
+pub struct TimeSeriesStore {
+    files: [RecordFile; 3],
+    retention: MetricsRetention,
+    /// Minute and hour buckets that have not been completed yet.
+    pending_minute: Option<Aggregate>,
+    pending_hour: Option<Aggregate>,
+    last_retention_ms: u64,
+    /// Timestamp of the newest raw record.
+    last_timestamp_ms: u64,
+}
+
+impl TimeSeriesStore {
+    /// Opens the store in `dir`, creating it if needed.
+    ///
+    /// Truncated trailing records are dropped, and roll-ups interrupted by a
+    /// restart are rebuilt from the finer resolution.
+    pub fn open(dir: &Path, retention: MetricsRetention) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut store = Self {
+            files: [
+                RecordFile::open(dir, Resolution::Raw)?,
+                RecordFile::open(dir, Resolution::Minute)?,
+                RecordFile::open(dir, Resolution::Hour)?,
+            ],
+            retention,
+            pending_minute: None,
+            pending_hour: None,
+            last_retention_ms: 0,
+            last_timestamp_ms: 0,
+        };
+        store.last_timestamp_ms = store
+            .file(Resolution::Raw)
+            .last()?
+            .map_or(0, |bytes| read_u64(&bytes, 0));
+
+        let hours_done = store.bucket_end(Resolution::Hour)?;
+        for bytes in store
+            .file(Resolution::Minute)
+            .read_range(hours_done, u64::MAX)?
+        {
+            store.roll_into_hour(&Aggregate::decode(&bytes))?;
+        }
+        let minutes_done = store.bucket_end(Resolution::Minute)?;
+        for bytes in store
+            .file(Resolution::Raw)
+            .read_range(minutes_done, u64::MAX)?
+        {
+            store.roll_into_minute(&RawRecord::decode(&bytes))?;
+        }
+
+        Ok(store)
+    }
+
+    fn file(&mut self, resolution: Resolution) -> &mut RecordFile {
+        &mut self.files[resolution.tag() as usize]
+    }
+
+    /// Returns the end of the last completed bucket of a roll-up file.
+    fn bucket_end(&mut self, resolution: Resolution) -> io::Result<u64> {
+        Ok(self
+            .file(resolution)
+            .last()?
+            .map_or(0, |bytes| read_u64(&bytes, 0) + resolution.bucket_ms()))
+    }
+
+    /// Replaces the retention policy; it takes effect with the next append.
+    pub fn set_retention(&mut self, retention: MetricsRetention) {
+        self.retention = retention;
+        self.last_retention_ms = 0;
+    }
+
+    /// Appends a raw record and updates the roll-ups.
+    ///
+    /// Records older than the newest one, e.g. after the wall clock was set
+    /// back, are dropped: the files must stay in time order for lookups,
+    /// and roll-ups would get a second bucket for the same minute or hour.
+    pub fn append(&mut self, record: RawRecord) -> io::Result<()> {
+        if record.timestamp_ms < self.last_timestamp_ms {
+            tracing::debug!(
+                timestamp_ms = record.timestamp_ms,
+                newest_ms = self.last_timestamp_ms,
+                "dropping metrics sample older than the newest one"
+            );
+            return Ok(());
+        }
+        self.file(Resolution::Raw).append(&record.encode())?;
+        self.last_timestamp_ms = record.timestamp_ms;
+        self.roll_into_minute(&record)?;
+
+        if record.timestamp_ms.saturating_sub(self.last_retention_ms) >= RETENTION_INTERVAL_MS {
+            self.enforce_retention(record.timestamp_ms)?;
+        }
+        Ok(())
+    }
+
+    fn roll_into_minute(&mut self, record: &RawRecord) -> io::Result<()> {
+        let bucket = record.timestamp_ms - record.timestamp_ms % Resolution::Minute.bucket_ms();
+        if let Some(done) = self.pending_minute.take_if(|m| m.timestamp_ms != bucket) {
+            self.file(Resolution::Minute).append(&done.encode())?;
+            self.roll_into_hour(&done)?;
+        }
+        self.pending_minute
+            .get_or_insert_with(|| Aggregate::empty(bucket))
+            .merge(&Aggregate::from_raw(record));
+        Ok(())
+    }
+
+    fn roll_into_hour(&mut self, minute: &Aggregate) -> io::Result<()> {
+        let bucket = minute.timestamp_ms - minute.timestamp_ms % Resolution::Hour.bucket_ms();
+        if let Some(done) = self.pending_hour.take_if(|h| h.timestamp_ms != bucket) {
+            self.file(Resolution::Hour).append(&done.encode())?;
+        }
+        self.pending_hour
+            .get_or_insert_with(|| Aggregate::empty(bucket))
+            .merge(minute);
+        Ok(())
+    }
+
+    /// Drops records older than each resolution's retention period.
+    pub fn enforce_retention(&mut self, now_ms: u64) -> io::Result<()> {
+        for resolution in Resolution::ALL {
+            let cutoff = now_ms.saturating_sub(resolution.retention_ms(&self.retention));
+            self.file(resolution).drop_before(cutoff)?;
+            tracing::debug!(resolution = ?resolution, cutoff_ms = cutoff, "applied metrics retention");
+        }
+        self.last_retention_ms = now_ms;
+        Ok(())
+    }
+
+    /// Picks the finest resolution that still covers `from_ms` and does not
+    /// need more than a few records per requested point.
+    fn resolution_for(
+        &self,
+        from_ms: u64,
+        to_ms: u64,
+        now_ms: u64,
+        max_points: usize,
+    ) -> Resolution {
+        const RECORDS_PER_POINT: u64 = 8;
+        let window = to_ms.saturating_sub(from_ms);
+        Resolution::ALL
+            .into_iter()
+            .find(|r| {
+                let covers = now_ms.saturating_sub(r.retention_ms(&self.retention)) <= from_ms;
+                covers && window / r.bucket_ms() <= RECORDS_PER_POINT * max_points as u64
+            })
+            .unwrap_or(Resolution::Hour)
+    }
+
+    /// Returns at most `max_points` points of `series` between `from_ms` and
+    /// `to_ms` (inclusive), downsampled by merging neighbouring records so
+    /// that minima and maxima are kept.
+    pub fn query(
+        &mut self,
+        series: usize,
+        from_ms: u64,
+        to_ms: u64,
+        now_ms: u64,
+        max_points: usize,
+    ) -> io::Result<RangeQuery> {
+        let resolution = self.resolution_for(from_ms, to_ms, now_ms, max_points);
+        let mut aggregates: Vec<Aggregate> = match resolution {
+            Resolution::Raw => self
+                .file(resolution)
+                .read_range(from_ms, to_ms)?
+                .iter()
+                .map(|b| Aggregate::from_raw(&RawRecord::decode(b)))
+                .collect(),
+            _ => self
+                .file(resolution)
+                .read_range(from_ms, to_ms)?
+                .iter()
+                .map(|b| Aggregate::decode(b))
+                .collect(),
+        };
+
+        // Include the bucket still being filled
+        let pending = match resolution {
+            Resolution::Raw => None,
+            Resolution::Minute => self.pending_minute,
+            Resolution::Hour => self.pending_hour.map(|mut hour| {
+                if let Some(minute) = &self.pending_minute {
+                    hour.merge(minute);
+                }
+                hour
+            }),
+        };
+        if let Some(p) = pending.filter(|p| (from_ms..=to_ms).contains(&p.timestamp_ms)) {
+            aggregates.push(p);
+        }
+
+        Ok(RangeQuery {
+            series: SERIES.get(series).unwrap_or(&"").to_string(),
+            resolution,
+            points: downsample(&aggregates, series, from_ms, to_ms, max_points),
+        })
+    }
+}
+
+/// Merges aggregates into at most `max_points` equal-width time buckets and
+/// extracts one series. Buckets where the series has no value are skipped.
+fn downsample(
+    aggregates: &[Aggregate],
+    series: usize,
+    from_ms: u64,
+    to_ms: u64,
+    max_points: usize,
+) -> Vec<RangePoint> {
+    let max_points = max_points.max(1);
+    let width = (to_ms.saturating_sub(from_ms) / max_points as u64).max(1);
+
+    let mut buckets: Vec<Aggregate> = Vec::new();
+    for aggregate in aggregates {
+        let bucket = if aggregates.len() <= max_points {
+            aggregate.timestamp_ms
+        } else {
+            from_ms + (aggregate.timestamp_ms.saturating_sub(from_ms) / width) * width
+        };
+        match buckets.last_mut() {
+            Some(last) if last.timestamp_ms == bucket => last.merge(aggregate),
+            _ => {
+                let mut first = *aggregate;
+                first.timestamp_ms = bucket;
+                buckets.push(first);
+            }
+        }
+    }
+
+    buckets
+        .iter()
+        .filter(|b| !b.mean[series].is_nan())
+        .map(|b| RangePoint {
+            timestamp_ms: b.timestamp_ms,
+            min: b.min[series] as f64,
+            max: b.max[series] as f64,
+            mean: b.mean[series] as f64,
+            count: b.count,
+        })
+        .collect()
+}
+
+fn store() -> &'static Mutex<Option<TimeSeriesStore>> {
+    static STORE: OnceLock<Mutex<Option<TimeSeriesStore>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+/// Runs `f` on the app's store, opening it under the data directory on first use.
+fn with_store<T>(f: impl FnOnce(&mut TimeSeriesStore) -> io::Result<T>) -> io::Result<T> {
+    let mut guard = store().lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let dir = AppConfig::data_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?
+            .join("metrics");
+        let retention = configcache::current().metrics_retention;
+        *guard = Some(TimeSeriesStore::open(&dir, retention)?);
+    }
+    let store = guard
+        .as_mut()
+        .ok_or_else(|| io::Error::other("metrics store is not open"))?;
+    f(store)
+}
+
+/// Appends a metrics sample to the app's history.
+pub fn record(sample: &MetricsSample) -> io::Result<()> {
+    // Only changes are logged, so an unwritable data directory doesn't flood the log
+    static FAILING: AtomicBool = AtomicBool::new(false);
+
+    let result = with_store(|store| {
+        store.append(RawRecord {
+            timestamp_ms: sample.timestamp_ms,
+            values: values_from_sample(sample),
+        })
+    });
+    match &result {
+        Err(e) if !FAILING.swap(true, Ordering::Relaxed) => {
+            tracing::warn!(error = %e, "could not record metrics history")
+        }
+        Ok(()) if FAILING.swap(false, Ordering::Relaxed) => {
+            tracing::info!("recording metrics history again")
+        }
+        _ => {}
+    }
+    result
+}
+
+/// Queries the app's history; see [`TimeSeriesStore::query`].
+pub fn query(series: usize, from_ms: u64, to_ms: u64, max_points: usize) -> io::Result<RangeQuery> {
+    let now_ms = crate::metrics::now_ms();
+    with_store(|store| store.query(series, from_ms, to_ms, now_ms, max_points))
+}
+
+/// Applies a new retention policy to the app's history, if it is open.
+pub fn set_retention(retention: MetricsRetention) {
+    let mut guard = store().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(store) = guard.as_mut() {
+        store.set_retention(retention);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+    const HOUR: u64 = 3_600_000;
+
+    fn record(timestamp_ms: u64, rss: f32) -> RawRecord {
+        let mut values = [f32::NAN; SERIES_COUNT];
+        values[0] = rss;
+        RawRecord {
+            timestamp_ms,
+            values,
+        }
+    }
+
+    fn retention() -> MetricsRetention {
+        MetricsRetention {
+            raw_hours: 1,
+            minute_days: 1,
+            hour_days: 30,
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let raw = record(1234, 5.5);
+        assert_eq!(RawRecord::decode(&raw.encode()).timestamp_ms, 1234);
+        assert_eq!(RawRecord::decode(&raw.encode()).values[0], 5.5);
+        assert_eq!(raw.encode().len(), RawRecord::SIZE);
+
+        let mut aggregate = Aggregate::from_raw(&raw);
+        aggregate.merge(&Aggregate::from_raw(&record(1300, 7.5)));
+        let decoded = Aggregate::decode(&aggregate.encode());
+        assert_eq!(decoded.count, 2);
+        assert_eq!(
+            (decoded.min[0], decoded.max[0], decoded.mean[0]),
+            (5.5, 7.5, 6.5)
+        );
+        assert!(decoded.mean[1].is_nan());
+    }
+
+    #[test]
+    fn test_roll_ups() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+
+        // Two samples in each of the first three minutes, then two minutes
+        // into the next hour so that its first minute completes
+        for minute in 0..3 {
+            store.append(record(minute * MINUTE, 10.0)).unwrap();
+            store
+                .append(record(minute * MINUTE + 30_000, 20.0))
+                .unwrap();
+        }
+        store.append(record(HOUR, 100.0)).unwrap();
+        assert_eq!(store.file(Resolution::Minute).len().unwrap(), 3);
+        assert_eq!(store.file(Resolution::Hour).len().unwrap(), 0);
+        store.append(record(HOUR + MINUTE, 100.0)).unwrap();
+
+        assert_eq!(store.file(Resolution::Minute).len().unwrap(), 4);
+        let hour = Aggregate::decode(&store.file(Resolution::Hour).last().unwrap().unwrap());
+        assert_eq!(hour.timestamp_ms, 0);
+        assert_eq!(hour.count, 6);
+        assert_eq!((hour.min[0], hour.max[0], hour.mean[0]), (10.0, 20.0, 15.0));
+    }
+
+    #[test]
+    fn test_clock_going_back_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+            store.append(record(HOUR, 1.0)).unwrap();
+            store.append(record(HOUR + MINUTE, 2.0)).unwrap();
+            // The clock steps back by an hour
+            store.append(record(MINUTE, 9.0)).unwrap();
+        }
+
+        // Also across a restart
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        store.append(record(2 * MINUTE, 9.0)).unwrap();
+        store.append(record(HOUR + 2 * MINUTE, 3.0)).unwrap();
+
+        let result = store.query(0, 0, 2 * HOUR, 2 * HOUR, 100).unwrap();
+        let values: Vec<f64> = result.points.iter().map(|p| p.mean).collect();
+        assert_eq!(values, vec![1.0, 2.0, 3.0]);
+        let minutes = store
+            .file(Resolution::Minute)
+            .read_range(0, u64::MAX)
+            .unwrap();
+        let starts: Vec<u64> = minutes.iter().map(|m| read_u64(m, 0)).collect();
+        assert_eq!(starts, vec![HOUR, HOUR + MINUTE]);
+    }
+
+    #[test]
+    fn test_survives_truncated_record() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+            for i in 0..5 {
+                store.append(record(i * 1000, i as f32)).unwrap();
+            }
+        }
+
+        // Simulate a crash in the middle of writing the last record
+        let raw = dir.path().join("raw.bin");
+        let len = fs::metadata(&raw).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&raw)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        assert_eq!(store.file(Resolution::Raw).len().unwrap(), 4);
+        store.append(record(5000, 5.0)).unwrap();
+
+        let result = store.query(0, 0, 10_000, 10_000, 100).unwrap();
+        let values: Vec<f64> = result.points.iter().map(|p| p.mean).collect();
+        assert_eq!(result.resolution, Resolution::Raw);
+        assert_eq!(values, vec![0.0, 1.0, 2.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn test_reopen_rebuilds_pending_roll_ups() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+            store.append(record(0, 1.0)).unwrap();
+            store.append(record(30_000, 3.0)).unwrap();
+        }
+
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        store.append(record(MINUTE, 9.0)).unwrap();
+
+        let minute = Aggregate::decode(&store.file(Resolution::Minute).last().unwrap().unwrap());
+        assert_eq!(minute.count, 2);
+        assert_eq!(minute.mean[0], 2.0);
+    }
+
+    #[test]
+    fn test_foreign_file_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("raw.bin"), b"not a time series").unwrap();
+
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        assert_eq!(store.file(Resolution::Raw).len().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_retention_drops_old_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        for i in 0..=120 {
+            store.append(record(i * MINUTE, 1.0)).unwrap();
+        }
+
+        // Raw records are kept for one hour, minute roll-ups for a day
+        store.enforce_retention(120 * MINUTE).unwrap();
+        let first_raw = store.file(Resolution::Raw).timestamp_at(0).unwrap();
+        assert_eq!(first_raw, 60 * MINUTE);
+        assert_eq!(store.file(Resolution::Minute).timestamp_at(0).unwrap(), 0);
+
+        // Data survives reopening after the rewrite
+        drop(store);
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        assert_eq!(store.file(Resolution::Raw).len().unwrap(), 61);
+    }
+
+    #[test]
+    fn test_query_downsamples_and_keeps_extremes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = TimeSeriesStore::open(dir.path(), retention()).unwrap();
+        for i in 0..1000u64 {
+            let rss = if i == 500 { 1000.0 } else { (i % 10) as f32 };
+            store.append(record(i * 2000, rss)).unwrap();
+        }
+        let now = 2000 * 1000;
+
+        let result = store.query(0, 0, now, now, 50).unwrap();
+        assert_eq!(result.series, "rss_bytes");
+        assert!(result.points.len() <= 50);
+        let max = result.points.iter().map(|p| p.max).fold(0.0, f64::max);
+        let min = result
+            .points
+            .iter()
+            .map(|p| p.min)
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!((min, max), (0.0, 1000.0));
+        assert_eq!(result.points.iter().map(|p| p.count).sum::<u32>(), 1000);
+
+        // A window older than the raw retention falls back to minute roll-ups
+        let old = store.query(0, 0, now, now + 2 * HOUR, 50).unwrap();
+        assert_eq!(old.resolution, Resolution::Minute);
+
+        // Series that were never recorded yield no points
+        assert!(store.query(1, 0, now, now, 50).unwrap().points.is_empty());
+    }
+}
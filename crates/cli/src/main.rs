@@ -0,0 +1,398 @@
+//! Headless command line interface to the app's config and system info.
+//!
+//! Uses the same `config` and `core` modules as the Neon backend, so it reads
+//! and writes the same config file. A running app polls that file and applies
+//! changes made here within a second.
+//!
+//! Exit codes: 0 on success, 2 for usage errors, and for config errors:
+//!
+//! | Code | Error |
+//! |------|-------|
+//! | 3 | I/O error |
+//! | 4 | config file isn't valid JSON or has wrongly typed values |
+//! | 5 | no config directory on this platform |
+//! | 6 | unknown config key |
+//! | 7 | invalid value |
+//! | 8 | no such profile |
+//! | 9 | the config was changed by someone else while this command ran |
+//! | 10 | config file isn't valid TOML or YAML, or can't be written as one |
+
+use backend::config::{AppConfig, ConfigError, Versioned};
+use backend::configformat::Format;
+use backend::configstore;
+use backend::core;
+use backend::paths;
+use backend::profiles;
+use backend::provider::RealSystem;
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Manage the app's config and inspect the system without the GUI"
+)]
+struct Cli {
+    /// Keep config, data, cache and logs under this directory, like the
+    /// app's --data-dir
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Read and change settings
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Show system and environment information
+    Sysinfo {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Save and switch between named copies of the config
+    #[command(subcommand)]
+    Profiles(ProfilesCommand),
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print a setting, e.g. `logging.level`
+    Get { key: String },
+    /// Change a setting; the value is parsed as JSON unless the setting is a string
+    Set { key: String, value: String },
+    /// Reset a setting to its default, or remove a map entry
+    Unset { key: String },
+    /// Print every setting
+    List {
+        /// Print the whole config as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the path of the config file
+    Path,
+    /// Print the JSON Schema of the config
+    Schema,
+    /// Check a config file without changing it
+    Validate {
+        /// File to check instead of the app's config
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Replace the config with the defaults, keeping the old file as a .bak file
+    Reset,
+    /// Rewrite the config file as JSON, TOML or YAML, keeping the old file as a .bak file
+    Convert {
+        /// json, toml or yaml
+        format: Format,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfilesCommand {
+    /// List saved profiles
+    List,
+    /// Save the current config as a profile
+    Save { name: String },
+    /// Replace the current config with a profile
+    Use { name: String },
+    /// Print a profile as JSON
+    Show { name: String },
+    /// Delete a profile
+    Delete { name: String },
+}
+
+/// Returns the exit code for a config error.
+fn exit_code(error: &ConfigError) -> u8 {
+    match error {
+        ConfigError::Io(_) => 3,
+        ConfigError::Json(_) => 4,
+        ConfigError::NoConfigDir => 5,
+        ConfigError::UnknownKey(_) => 6,
+        ConfigError::Invalid(_) => 7,
+        ConfigError::NoProfile(_) => 8,
+        ConfigError::Conflict { .. } => 9,
+        ConfigError::Format { .. } => 10,
+    }
+}
+
+/// Formats a setting for printing: strings without quotes, everything else as JSON.
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Object(_) | Value::Array(_) => {
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Returns the name a unit enum is serialized as, e.g. "wayland".
+fn label(value: &impl Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn config_path() -> Result<PathBuf, ConfigError> {
+    AppConfig::config_path().ok_or(ConfigError::NoConfigDir)
+}
+
+fn profiles_dir() -> Result<PathBuf, ConfigError> {
+    profiles::profiles_dir().ok_or(ConfigError::NoConfigDir)
+}
+
+/// Loads the current config and its revision.
+///
+/// Unlike the app, which falls back to the defaults, this fails if the file
+/// is broken so that a change doesn't silently replace it.
+fn load() -> Result<Versioned, ConfigError> {
+    AppConfig::load_strict()
+}
+
+/// Saves `config` after checking that the app can use it.
+///
+/// With `expected` set, fails if the file was changed since that revision
+/// was loaded, e.g. by the app.
+fn save(config: &AppConfig, expected: Option<&str>) -> Result<(), ConfigError> {
+    config.check()?;
+    config.save(expected).map(|_| ())
+}
+
+fn run_config(command: ConfigCommand) -> Result<(), ConfigError> {
+    match command {
+        ConfigCommand::Get { key } => {
+            println!("{}", display_value(&load()?.config.get(&key)?));
+        }
+        ConfigCommand::Set { key, value } => {
+            let Versioned {
+                mut config,
+                revision,
+            } = load()?;
+            config.set(&key, &value)?;
+            save(&config, Some(&revision))?;
+        }
+        ConfigCommand::Unset { key } => {
+            let Versioned {
+                mut config,
+                revision,
+            } = load()?;
+            config.unset(&key)?;
+            save(&config, Some(&revision))?;
+        }
+        ConfigCommand::List { json } => {
+            let config = load()?.config;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            } else {
+                for (key, value) in config.entries()? {
+                    println!("{} = {}", key, value);
+                }
+            }
+        }
+        ConfigCommand::Path => println!("{}", config_path()?.display()),
+        ConfigCommand::Schema => {
+            println!("{}", serde_json::to_string_pretty(&AppConfig::schema())?);
+        }
+        ConfigCommand::Validate { file } => {
+            let report = match file {
+                Some(file) => validate(&file, true)?,
+                None => validate(&config_path()?, false)?,
+            };
+            println!("{}", report);
+        }
+        ConfigCommand::Reset => {
+            AppConfig::backup_in(&*configstore::default_store())?;
+            save(&AppConfig::default(), None)?;
+        }
+        ConfigCommand::Convert { format } => {
+            if AppConfig::convert_in(&*configstore::default_store(), format)? {
+                println!("{}", config_path()?.display());
+            } else {
+                println!("{} is already {}", config_path()?.display(), format);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the config file at `path` and returns what to report.
+///
+/// Unless the file was named on the command line, a missing file isn't an
+/// error: the app runs on the defaults, which are valid.
+fn validate(path: &Path, named: bool) -> Result<String, ConfigError> {
+    if !named && !path.exists() {
+        return Ok(format!(
+            "no config file at {}; the defaults are valid",
+            path.display()
+        ));
+    }
+    AppConfig::read_from(path)?.check()?;
+    Ok(format!("{} is valid", path.display()))
+}
+
+fn run_sysinfo(as_json: bool) -> Result<(), ConfigError> {
+    let system = core::get_system_info(&RealSystem);
+    let environment = core::get_environment_info(&RealSystem);
+
+    if as_json {
+        let info = json!({ "system": system, "environment": environment });
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    let unknown = || "unknown".to_string();
+    println!(
+        "Platform:  {} ({}, {})",
+        system.platform, system.arch, system.family
+    );
+    println!(
+        "OS:        {}",
+        environment
+            .os
+            .pretty_name
+            .clone()
+            .or(environment.os.name.clone())
+            .unwrap_or_else(unknown)
+    );
+    println!("Kernel:    {}", environment.kernel);
+    println!(
+        "Locale:    {}",
+        environment.locale.clone().unwrap_or_else(unknown)
+    );
+    println!(
+        "Timezone:  {}",
+        environment.timezone.clone().unwrap_or_else(unknown)
+    );
+    println!("Session:   {}", label(&environment.session_type));
+    println!(
+        "Desktop:   {}",
+        environment.desktop.clone().unwrap_or_else(unknown)
+    );
+    println!("Packaging: {}", label(&environment.packaging));
+    println!(
+        "Container: {}",
+        environment
+            .container
+            .clone()
+            .unwrap_or_else(|| "none".to_string())
+    );
+    Ok(())
+}
+
+fn run_profiles(command: ProfilesCommand) -> Result<(), ConfigError> {
+    let dir = profiles_dir()?;
+    match command {
+        ProfilesCommand::List => {
+            for name in profiles::list(&dir)? {
+                println!("{}", name);
+            }
+        }
+        ProfilesCommand::Save { name } => profiles::save(&dir, &name, &load()?.config)?,
+        ProfilesCommand::Use { name } => save(&profiles::load(&dir, &name)?, None)?,
+        ProfilesCommand::Show { name } => {
+            let profile = profiles::load(&dir, &name)?;
+            println!("{}", serde_json::to_string_pretty(&profile)?);
+        }
+        ProfilesCommand::Delete { name } => profiles::delete(&dir, &name)?,
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    paths::init(cli.data_dir.as_deref());
+    let result = match cli.command {
+        Command::Config(command) => run_config(command),
+        Command::Sysinfo { json } => run_sysinfo(json),
+        Command::Profiles(command) => run_profiles(command),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::core::SessionType;
+
+    #[test]
+    fn test_cli_parses() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["cli", "config", "set", "logging.level", "debug"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Config(ConfigCommand::Set { key, value }) if key == "logging.level" && value == "debug"
+        ));
+        assert!(Cli::try_parse_from(["cli", "config", "get"]).is_err());
+
+        let cli = Cli::try_parse_from(["cli", "config", "convert", "yaml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Config(ConfigCommand::Convert {
+                format: Format::Yaml
+            })
+        ));
+        assert!(Cli::try_parse_from(["cli", "config", "convert", "ini"]).is_err());
+
+        let cli = Cli::try_parse_from(["cli", "config", "list", "--data-dir", "/tmp/app"]).unwrap();
+        assert_eq!(cli.data_dir.unwrap(), Path::new("/tmp/app"));
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            ConfigError::Io(std::io::Error::other("x")),
+            ConfigError::Json(serde_json::from_str::<Value>("{").unwrap_err()),
+            ConfigError::NoConfigDir,
+            ConfigError::UnknownKey("x".to_string()),
+            ConfigError::Invalid("x".to_string()),
+            ConfigError::NoProfile("x".to_string()),
+            ConfigError::Conflict {
+                revision: "x".to_string(),
+                current: Box::default(),
+            },
+            ConfigError::Format {
+                format: Format::Toml,
+                message: "x".to_string(),
+            },
+        ];
+        let mut codes: Vec<u8> = errors.iter().map(exit_code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        // 0, 1 and clap's usage error code 2 stay free
+        assert!(codes.iter().all(|&code| code > 2));
+    }
+
+    #[test]
+    fn test_validate_missing_file() {
+        let path = std::env::temp_dir().join("cli-test-missing/config.json");
+        let report = validate(&path, false).unwrap();
+        assert!(report.starts_with("no config file at "));
+        assert!(report.ends_with("; the defaults are valid"));
+        assert!(matches!(validate(&path, true), Err(ConfigError::Io(_))));
+    }
+
+    #[test]
+    fn test_display_value_and_label() {
+        assert_eq!(display_value(&json!("info")), "info");
+        assert_eq!(display_value(&json!(24)), "24");
+        assert_eq!(display_value(&json!({})), "{}");
+        assert_eq!(label(&SessionType::Wayland), "wayland");
+    }
+}
@@ -0,0 +1,710 @@
+//! Reactive charts.
+//!
+//! A [`ChartSpec`] describes a chart in plain Rust types. The [`Chart`]
+//! component draws it with one of two backends, chosen per chart:
+//!
+//! - [`ChartBackend::ChartJs`] renders to a canvas through typed bindings to
+//!   the global `Chart` class loaded from `chart.umd.min.js`, updates the
+//!   instance in place when the spec signal changes, and destroys it on unmount.
+//! - [`ChartBackend::Svg`] renders SVG natively (see [`crate::svg_chart`]) and
+//!   needs no JavaScript.
+//!
+//! Colors come from the active DaisyUI theme, so the same spec looks right in
+//! light and dark themes.
+
+use crate::downsample::Downsampler;
+use crate::export::ExportMenu;
+use crate::logging;
+use crate::svg_chart::SvgChart;
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::rc::Rc;
+use sycamore::prelude::*;
+use sycamore::web::on_mount;
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlCanvasElement;
+
+#[wasm_bindgen]
+extern "C" {
+    /// A Chart.js chart instance.
+    #[wasm_bindgen(js_name = Chart)]
+    type ChartJs;
+
+    #[wasm_bindgen(constructor, js_class = "Chart", catch)]
+    fn new(canvas: &HtmlCanvasElement, config: &JsValue) -> Result<ChartJs, JsValue>;
+
+    #[wasm_bindgen(method, setter)]
+    fn set_data(this: &ChartJs, data: &JsValue);
+
+    #[wasm_bindgen(method, setter)]
+    fn set_options(this: &ChartJs, options: &JsValue);
+
+    #[wasm_bindgen(method)]
+    fn update(this: &ChartJs, mode: &str);
+
+    #[wasm_bindgen(method)]
+    fn destroy(this: &ChartJs);
+}
+
+/// Chart types supported by [`Chart`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartKind {
+    #[default]
+    Bar,
+    Line,
+    /// Line chart with every series filled down to the x axis.
+    Area,
+    /// Points at the x values of [`Dataset::x`].
+    Scatter,
+    Pie,
+    Doughnut,
+    Radar,
+}
+
+impl ChartKind {
+    /// Returns the Chart.js type name.
+    fn chartjs_type(&self) -> &'static str {
+        match self {
+            ChartKind::Bar => "bar",
+            ChartKind::Line | ChartKind::Area => "line",
+            ChartKind::Scatter => "scatter",
+            ChartKind::Pie => "pie",
+            ChartKind::Doughnut => "doughnut",
+            ChartKind::Radar => "radar",
+        }
+    }
+
+    /// Returns true for chart types the SVG backend can draw.
+    pub fn supports_svg(&self) -> bool {
+        matches!(
+            self,
+            ChartKind::Bar | ChartKind::Line | ChartKind::Area | ChartKind::Scatter
+        )
+    }
+
+    /// Returns true for chart types that color each data point separately.
+    fn is_circular(&self) -> bool {
+        matches!(self, ChartKind::Pie | ChartKind::Doughnut)
+    }
+
+    /// Returns true for chart types with x and y axes.
+    fn has_axes(&self) -> bool {
+        self.supports_svg()
+    }
+}
+
+/// How a [`Chart`] is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartBackend {
+    /// Chart.js on a canvas; requires `chart.umd.min.js` to be loaded.
+    #[default]
+    ChartJs,
+    /// Native SVG; supports bar, line, area and scatter charts.
+    Svg,
+}
+
+/// One series of values.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Dataset {
+    pub label: String,
+    /// The y values.
+    pub data: Vec<f64>,
+    /// The x values for scatter charts; the index of each value when `None`.
+    pub x: Option<Vec<f64>>,
+    /// CSS color overriding the theme palette.
+    pub color: Option<String>,
+    /// Gives each data point its own palette color, e.g. for a bar per category.
+    pub color_per_point: bool,
+    /// Fills the area below a line.
+    pub fill: bool,
+}
+
+impl Dataset {
+    /// Creates a dataset colored from the theme palette.
+    pub fn new(label: impl Into<String>, data: Vec<f64>) -> Self {
+        Self {
+            label: label.into(),
+            data,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a dataset of (x, y) points for scatter charts.
+    pub fn points(label: impl Into<String>, points: Vec<(f64, f64)>) -> Self {
+        let (x, data) = points.into_iter().unzip();
+        Self {
+            label: label.into(),
+            data,
+            x: Some(x),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the x value of point `index`.
+    pub fn x_at(&self, index: usize) -> f64 {
+        self.x
+            .as_ref()
+            .and_then(|x| x.get(index).copied())
+            .unwrap_or(index as f64)
+    }
+
+    /// Returns true if the area below the line is filled in a chart of `kind`.
+    pub fn is_filled(&self, kind: ChartKind) -> bool {
+        self.fill || kind == ChartKind::Area
+    }
+
+    /// Uses a fixed CSS color instead of the theme palette.
+    pub fn with_color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Gives each data point its own palette color.
+    pub fn colored_per_point(mut self) -> Self {
+        self.color_per_point = true;
+        self
+    }
+
+    /// Fills the area below a line.
+    pub fn filled(mut self) -> Self {
+        self.fill = true;
+        self
+    }
+}
+
+/// Display options shared by all chart types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartOptions {
+    pub show_legend: bool,
+    /// Animates changes; turn off for charts updated every few seconds.
+    pub animate: bool,
+    pub begin_at_zero: bool,
+    pub y_max: Option<f64>,
+    pub y_label: Option<String>,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            show_legend: true,
+            animate: true,
+            begin_at_zero: true,
+            y_max: None,
+            y_label: None,
+        }
+    }
+}
+
+/// Everything needed to draw a chart.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChartSpec {
+    pub kind: ChartKind,
+    pub labels: Vec<String>,
+    pub datasets: Vec<Dataset>,
+    pub options: ChartOptions,
+}
+
+impl ChartSpec {
+    /// Creates an empty chart of the given type.
+    pub fn new(kind: ChartKind) -> Self {
+        Self {
+            kind,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_labels<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_dataset(mut self, dataset: Dataset) -> Self {
+        self.datasets.push(dataset);
+        self
+    }
+
+    pub fn with_options(mut self, options: ChartOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Reduces every dataset to about `max_points` points.
+    ///
+    /// The points kept for each dataset are merged, so labels and datasets
+    /// stay aligned; with several datasets the result can exceed `max_points`.
+    pub fn downsampled(mut self, max_points: usize, method: Downsampler) -> Self {
+        let len = self
+            .datasets
+            .iter()
+            .map(|d| d.data.len())
+            .max()
+            .unwrap_or(0);
+        if len <= max_points {
+            return self;
+        }
+
+        let mut keep: Vec<usize> = self
+            .datasets
+            .iter()
+            .flat_map(|d| {
+                let xs: Vec<f64> = (0..d.data.len()).map(|i| d.x_at(i)).collect();
+                method.indices(&xs, &d.data, max_points)
+            })
+            .collect();
+        keep.sort_unstable();
+        keep.dedup();
+
+        let pick = |values: &[f64]| -> Vec<f64> {
+            keep.iter()
+                .filter_map(|&i| values.get(i).copied())
+                .collect()
+        };
+        if !self.labels.is_empty() {
+            self.labels = keep
+                .iter()
+                .filter_map(|&i| self.labels.get(i).cloned())
+                .collect();
+        }
+        for dataset in &mut self.datasets {
+            dataset.data = pick(&dataset.data);
+            dataset.x = dataset.x.as_deref().map(pick);
+        }
+        self
+    }
+}
+
+/// Colors taken from the active theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeColors {
+    pub text: String,
+    pub muted: String,
+    pub grid: String,
+    /// Background of overlays such as tooltips.
+    pub surface: String,
+    /// Series colors, used in order and repeated when exhausted.
+    pub palette: Vec<String>,
+}
+
+impl Default for ThemeColors {
+    /// The colors of the dark theme, used when no theme variables are defined.
+    fn default() -> Self {
+        Self {
+            text: "#eee".to_string(),
+            muted: "#aaa".to_string(),
+            grid: "rgba(255,255,255,0.1)".to_string(),
+            surface: "#1a1a2e".to_string(),
+            palette: ["#ff6b6b", "#4ecdc4", "#45b7d1", "#96ceb4", "#ffeaa7"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// DaisyUI color variables used for series, in order.
+const PALETTE_VARIABLES: [&str; 7] = [
+    "--color-primary",
+    "--color-secondary",
+    "--color-accent",
+    "--color-info",
+    "--color-success",
+    "--color-warning",
+    "--color-error",
+];
+
+impl ThemeColors {
+    /// Reads the colors of the theme currently applied to the document.
+    ///
+    /// Falls back to the app's own variables, then to [`ThemeColors::default`],
+    /// when DaisyUI is not in use.
+    pub fn current() -> Self {
+        let defaults = Self::default();
+        let style = || {
+            let window = web_sys::window()?;
+            let root = window.document()?.document_element()?;
+            window.get_computed_style(&root).ok().flatten()
+        };
+        let Some(style) = style() else {
+            return defaults;
+        };
+        let var = |name: &str| {
+            style
+                .get_property_value(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let text = var("--color-base-content").or_else(|| var("--app-text"));
+        let palette: Vec<String> = PALETTE_VARIABLES.iter().filter_map(|v| var(v)).collect();
+        Self {
+            muted: text
+                .as_ref()
+                .map(|t| translucent(t, 70))
+                .or_else(|| var("--app-text-muted"))
+                .unwrap_or(defaults.muted),
+            grid: text
+                .as_ref()
+                .map(|t| translucent(t, 10))
+                .unwrap_or(defaults.grid),
+            text: text.unwrap_or(defaults.text),
+            surface: var("--color-base-100")
+                .or_else(|| var("--app-gradient-start"))
+                .unwrap_or(defaults.surface),
+            palette: if palette.is_empty() {
+                defaults.palette
+            } else {
+                palette
+            },
+        }
+    }
+
+    /// Returns the color of dataset `index` of `spec`, honoring overrides.
+    pub fn series_color(&self, spec: &ChartSpec, index: usize) -> String {
+        spec.datasets
+            .get(index)
+            .and_then(|d| d.color.clone())
+            .unwrap_or_else(|| self.color(index))
+    }
+
+    /// Returns the palette color for series or point `index`.
+    pub fn color(&self, index: usize) -> String {
+        if self.palette.is_empty() {
+            return "gray".to_string();
+        }
+        self.palette[index % self.palette.len()].clone()
+    }
+}
+
+/// Mixes a CSS color with transparency, keeping `percent` of its opacity.
+pub(crate) fn translucent(color: &str, percent: u8) -> String {
+    format!("color-mix(in oklab, {} {}%, transparent)", color, percent)
+}
+
+impl ChartSpec {
+    /// Builds the Chart.js `data` object.
+    fn data_json(&self, theme: &ThemeColors) -> Value {
+        let datasets: Vec<Value> = self
+            .datasets
+            .iter()
+            .enumerate()
+            .map(|(i, dataset)| {
+                let color = dataset.color.clone().unwrap_or_else(|| theme.color(i));
+                let background = if dataset.color_per_point || self.kind.is_circular() {
+                    json!(
+                        (0..dataset.data.len())
+                            .map(|p| theme.color(p))
+                            .collect::<Vec<_>>()
+                    )
+                } else if dataset.is_filled(self.kind) || self.kind == ChartKind::Radar {
+                    json!(translucent(&color, 30))
+                } else {
+                    json!(color)
+                };
+                let data = if self.kind == ChartKind::Scatter {
+                    json!(
+                        dataset
+                            .data
+                            .iter()
+                            .enumerate()
+                            .map(|(p, y)| json!({ "x": dataset.x_at(p), "y": y }))
+                            .collect::<Vec<_>>()
+                    )
+                } else {
+                    json!(dataset.data)
+                };
+                let is_line = matches!(self.kind, ChartKind::Line | ChartKind::Area);
+                json!({
+                    "label": dataset.label,
+                    "data": data,
+                    "borderColor": if self.kind.is_circular() { json!(theme.grid) } else { json!(color) },
+                    "backgroundColor": background,
+                    "fill": dataset.is_filled(self.kind),
+                    "pointRadius": if is_line { 0 } else { 3 },
+                    "tension": 0.3,
+                })
+            })
+            .collect();
+
+        json!({ "labels": self.labels, "datasets": datasets })
+    }
+
+    /// Builds the Chart.js `options` object.
+    fn options_json(&self, theme: &ThemeColors) -> Value {
+        let opts = &self.options;
+        let mut options = json!({
+            "responsive": true,
+            "maintainAspectRatio": false,
+            "animation": opts.animate,
+            "color": theme.text,
+            "plugins": {
+                "legend": { "display": opts.show_legend, "labels": { "color": theme.text } },
+            },
+        });
+
+        if self.kind.has_axes() {
+            let mut y = json!({
+                "beginAtZero": opts.begin_at_zero,
+                "ticks": { "color": theme.muted },
+                "grid": { "color": theme.grid },
+            });
+            if let Some(max) = opts.y_max {
+                y["max"] = json!(max);
+            }
+            if let Some(label) = &opts.y_label {
+                y["title"] = json!({ "display": true, "text": label, "color": theme.muted });
+            }
+            options["scales"] = json!({
+                "x": { "ticks": { "color": theme.muted }, "grid": { "color": theme.grid } },
+                "y": y,
+            });
+        } else if self.kind == ChartKind::Radar {
+            options["scales"] = json!({
+                "r": {
+                    "beginAtZero": opts.begin_at_zero,
+                    "ticks": { "color": theme.muted, "backdropColor": "transparent" },
+                    "grid": { "color": theme.grid },
+                    "angleLines": { "color": theme.grid },
+                    "pointLabels": { "color": theme.text },
+                },
+            });
+        }
+
+        options
+    }
+
+    /// Builds the full Chart.js configuration object.
+    pub fn to_config(&self, theme: &ThemeColors) -> Value {
+        json!({
+            "type": self.kind.chartjs_type(),
+            "data": self.data_json(theme),
+            "options": self.options_json(theme),
+        })
+    }
+}
+
+/// Converts a JSON value into a plain JS object.
+fn to_js(value: &Value) -> JsValue {
+    js_sys::JSON::parse(&value.to_string()).unwrap_or(JsValue::NULL)
+}
+
+/// Calls `on_change` whenever the `data-theme` attribute of `<html>` changes.
+///
+/// Returns the observer and its callback, which must be kept alive until the
+/// observer is disconnected.
+fn observe_theme(
+    on_change: impl FnMut() + 'static,
+) -> Option<(web_sys::MutationObserver, Closure<dyn FnMut()>)> {
+    let root = web_sys::window()?.document()?.document_element()?;
+    let callback = Closure::<dyn FnMut()>::new(on_change);
+    let observer = web_sys::MutationObserver::new(callback.as_ref().unchecked_ref()).ok()?;
+    let init = web_sys::MutationObserverInit::new();
+    init.set_attribute_filter(&js_sys::Array::of1(&JsValue::from_str("data-theme")));
+    observer.observe_with_options(&root, &init).ok()?;
+    Some((observer, callback))
+}
+
+/// Returns the colors of the active theme, updated when the theme changes.
+///
+/// The observer is disconnected when the calling component unmounts.
+pub(crate) fn use_theme_colors() -> ReadSignal<ThemeColors> {
+    let colors = create_signal(ThemeColors::current());
+    if let Some((observer, callback)) = observe_theme(move || colors.set(ThemeColors::current())) {
+        on_cleanup(move || {
+            observer.disconnect();
+            drop(callback);
+        });
+    }
+    *colors
+}
+
+/// A live Chart.js instance and the type it was created with.
+struct Instance {
+    kind: ChartKind,
+    chart: ChartJs,
+}
+
+/// Chart that follows `spec`, drawn with `backend` (Chart.js by default).
+///
+/// The parent element needs a fixed height, e.g. the `chart-container` class.
+/// A menu in the top right corner exports the chart as PNG or SVG and its
+/// data as CSV.
+#[component(inline_props)]
+pub fn Chart(spec: ReadSignal<ChartSpec>, #[prop(default)] backend: ChartBackend) -> View {
+    let container = create_node_ref();
+    let chart = match backend {
+        ChartBackend::ChartJs => view! { ChartJsCanvas(spec=spec) },
+        ChartBackend::Svg => view! { SvgChart(spec=spec) },
+    };
+    view! {
+        div(r#ref=container, class="relative h-full") {
+            (chart)
+            ExportMenu(spec=spec, backend=backend, container=container)
+        }
+    }
+}
+
+/// Chart.js chart that follows `spec`.
+///
+/// Data and option changes update the existing chart; a change of chart type
+/// recreates it.
+#[component(inline_props)]
+fn ChartJsCanvas(spec: ReadSignal<ChartSpec>) -> View {
+    let canvas_ref = create_node_ref();
+    let instance: Rc<RefCell<Option<Instance>>> = Rc::new(RefCell::new(None));
+    let theme = use_theme_colors();
+
+    on_mount({
+        let instance = Rc::clone(&instance);
+        move || {
+            let canvas = canvas_ref.get().unchecked_into::<HtmlCanvasElement>();
+            create_effect(move || {
+                let theme = theme.get_clone();
+                spec.with(|spec| {
+                    let mut instance = instance.borrow_mut();
+                    match instance.as_ref() {
+                        Some(current) if current.kind == spec.kind => {
+                            current.chart.set_data(&to_js(&spec.data_json(&theme)));
+                            current
+                                .chart
+                                .set_options(&to_js(&spec.options_json(&theme)));
+                            current.chart.update("none");
+                        }
+                        _ => {
+                            if let Some(old) = instance.take() {
+                                old.chart.destroy();
+                            }
+                            match ChartJs::new(&canvas, &to_js(&spec.to_config(&theme))) {
+                                Ok(chart) => {
+                                    *instance = Some(Instance {
+                                        kind: spec.kind,
+                                        chart,
+                                    })
+                                }
+                                Err(e) => logging::warn(
+                                    "Could not create chart",
+                                    json!({ "error": format!("{:?}", e) }),
+                                ),
+                            }
+                        }
+                    }
+                });
+            });
+        }
+    });
+
+    on_cleanup(move || {
+        if let Some(old) = instance.borrow_mut().take() {
+            old.chart.destroy();
+        }
+    });
+
+    view! {
+        canvas(r#ref=canvas_ref) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> ThemeColors {
+        ThemeColors {
+            text: "white".to_string(),
+            muted: "gray".to_string(),
+            grid: "black".to_string(),
+            surface: "navy".to_string(),
+            palette: vec!["red".to_string(), "blue".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_bar_chart_config() {
+        let spec = ChartSpec::new(ChartKind::Bar)
+            .with_labels(["a", "b", "c"])
+            .with_dataset(Dataset::new("first", vec![1.0, 2.0, 3.0]))
+            .with_dataset(Dataset::new("second", vec![4.0, 5.0, 6.0]).with_color("green"));
+        let config = spec.to_config(&theme());
+
+        assert_eq!(config["type"], "bar");
+        assert_eq!(config["data"]["labels"], json!(["a", "b", "c"]));
+        assert_eq!(config["data"]["datasets"][0]["backgroundColor"], "red");
+        assert_eq!(config["data"]["datasets"][1]["backgroundColor"], "green");
+        assert_eq!(config["options"]["scales"]["y"]["ticks"]["color"], "gray");
+        assert_eq!(
+            config["options"]["plugins"]["legend"]["labels"]["color"],
+            "white"
+        );
+    }
+
+    #[test]
+    fn test_per_point_colors_wrap_palette() {
+        let spec = ChartSpec::new(ChartKind::Doughnut)
+            .with_dataset(Dataset::new("share", vec![1.0, 1.0, 1.0]));
+        let config = spec.to_config(&theme());
+
+        assert_eq!(config["type"], "doughnut");
+        assert_eq!(
+            config["data"]["datasets"][0]["backgroundColor"],
+            json!(["red", "blue", "red"])
+        );
+        assert!(config["options"].get("scales").is_none());
+    }
+
+    #[test]
+    fn test_scatter_and_area_config() {
+        let scatter = ChartSpec::new(ChartKind::Scatter)
+            .with_dataset(Dataset::points("xy", vec![(0.5, 1.0), (2.0, 3.0)]));
+        let config = scatter.to_config(&theme());
+        assert_eq!(config["type"], "scatter");
+        assert_eq!(
+            config["data"]["datasets"][0]["data"],
+            json!([{ "x": 0.5, "y": 1.0 }, { "x": 2.0, "y": 3.0 }])
+        );
+
+        let area = ChartSpec::new(ChartKind::Area).with_dataset(Dataset::new("a", vec![1.0]));
+        let config = area.to_config(&theme());
+        assert_eq!(config["type"], "line");
+        assert_eq!(config["data"]["datasets"][0]["fill"], true);
+    }
+
+    #[test]
+    fn test_downsampled_keeps_labels_aligned() {
+        let spec = ChartSpec::new(ChartKind::Line)
+            .with_labels((0..100).map(|i| i.to_string()))
+            .with_dataset(Dataset::new(
+                "spike",
+                (0..100).map(|i| if i == 37 { 50.0 } else { 1.0 }).collect(),
+            ))
+            .downsampled(10, Downsampler::MinMax);
+
+        let data = &spec.datasets[0].data;
+        assert!(data.len() <= 10);
+        assert_eq!(spec.labels.len(), data.len());
+        let spike = data.iter().position(|v| *v == 50.0).unwrap();
+        assert_eq!(spec.labels[spike], "37");
+
+        let small = ChartSpec::new(ChartKind::Line).with_dataset(Dataset::new("a", vec![1.0]));
+        assert_eq!(small.clone().downsampled(10, Downsampler::Lttb), small);
+    }
+
+    #[test]
+    fn test_line_options() {
+        let spec = ChartSpec::new(ChartKind::Line)
+            .with_dataset(Dataset::new("cpu", vec![10.0]).filled())
+            .with_options(ChartOptions {
+                animate: false,
+                y_max: Some(100.0),
+                y_label: Some("%".to_string()),
+                ..ChartOptions::default()
+            });
+        let config = spec.to_config(&theme());
+
+        assert_eq!(config["options"]["animation"], false);
+        assert_eq!(config["options"]["scales"]["y"]["max"], 100.0);
+        assert_eq!(config["options"]["scales"]["y"]["title"]["text"], "%");
+        assert_eq!(
+            config["data"]["datasets"][0]["backgroundColor"],
+            "color-mix(in oklab, red 30%, transparent)"
+        );
+    }
+}
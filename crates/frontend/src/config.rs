@@ -4,9 +4,11 @@
 //! when communicating via IPC.
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 
 /// Application configuration mirroring the backend struct.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct AppConfig {
     #[serde(default)]
     pub username: String,
@@ -22,6 +24,188 @@ pub struct AppConfig {
 
     #[serde(default = "default_theme")]
     pub theme: String,
+
+    #[serde(default)]
+    pub memory_warning_mb: u32,
+
+    #[serde(default)]
+    pub metrics_retention: MetricsRetention,
+
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    #[serde(default = "default_cache_quota_mb")]
+    pub cache_quota_mb: u32,
+
+    /// Settings this mirror doesn't know, kept so that saving doesn't drop
+    /// them and the schema-built form can still edit them.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A config and the revision of the file it was loaded from.
+///
+/// Passing the revision back when saving makes the save fail with a
+/// conflict if the config was changed elsewhere in the meantime.
+#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+pub struct Versioned {
+    #[serde(default)]
+    pub config: AppConfig,
+    #[serde(default)]
+    pub revision: String,
+}
+
+/// Result of saving the config.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SaveOutcome {
+    Saved {
+        revision: String,
+        config: AppConfig,
+    },
+    /// The config was changed elsewhere; holds what it contains now.
+    Conflict {
+        revision: String,
+        config: AppConfig,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// Reported by the backend's file watch when a change outside the app
+/// touched settings it had yet to write.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DiskConflict {
+    pub revision: String,
+    pub config: AppConfig,
+    /// The unwritten changes, as a merge patch.
+    #[serde(default)]
+    pub changes: Value,
+}
+
+impl DiskConflict {
+    /// Parses a watch message, returning `None` for a plain reloaded config.
+    pub fn parse(json: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(json).ok()?;
+        if value.get("status").and_then(Value::as_str) != Some("conflict") {
+            return None;
+        }
+        serde_json::from_value(value).ok()
+    }
+}
+
+/// Where the backend keeps the app's files and how they were chosen.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Paths {
+    pub mode: String,
+    /// The directory the others are in, except in the installed mode.
+    #[serde(default)]
+    pub root: Option<String>,
+    pub config: String,
+    pub data: String,
+    pub cache: String,
+    pub state: String,
+    pub runtime: String,
+    pub logs: String,
+}
+
+impl Paths {
+    /// Returns a short description of the mode for display.
+    pub fn mode_label(&self) -> &'static str {
+        match self.mode.as_str() {
+            "installed" => "Installed",
+            "portable" => "Portable",
+            "app-home" => "APP_HOME",
+            "data-dir" => "--data-dir",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// How much the backend's cache holds and may hold.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct CacheUsage {
+    pub bytes: u64,
+    pub entries: usize,
+    pub quota_bytes: u64,
+}
+
+/// Returns the JSON Merge Patch (RFC 7396) with the settings that differ
+/// between `old` and `new`, or `None` if they are the same.
+pub fn merge_patch(old: &AppConfig, new: &AppConfig) -> Option<Value> {
+    let old = serde_json::to_value(old).ok()?;
+    let new = serde_json::to_value(new).ok()?;
+    diff(&old, &new)
+}
+
+fn diff(old: &Value, new: &Value) -> Option<Value> {
+    if old == new {
+        return None;
+    }
+    let (Value::Object(old), Value::Object(new)) = (old, new) else {
+        return Some(new.clone());
+    };
+    let mut patch = Map::new();
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    for (key, value) in new {
+        let change = match old.get(key) {
+            Some(previous) => diff(previous, value),
+            None => Some(value.clone()),
+        };
+        if let Some(change) = change {
+            patch.insert(key.clone(), change);
+        }
+    }
+    Some(Value::Object(patch))
+}
+
+/// Log levels and rotation limits for the backend log file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    pub level: String,
+    pub modules: BTreeMap<String, String>,
+    pub max_file_bytes: u64,
+    pub max_files: u32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            max_file_bytes: 5 * 1024 * 1024,
+            max_files: 5,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// Retention periods for the on-disk metrics history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsRetention {
+    pub raw_hours: u32,
+    pub minute_days: u32,
+    pub hour_days: u32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for MetricsRetention {
+    fn default() -> Self {
+        Self {
+            raw_hours: 24,
+            minute_days: 7,
+            hour_days: 365,
+            extra: Map::new(),
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -35,3 +219,116 @@ fn default_auto_save_interval() -> u32 {
 fn default_theme() -> String {
     "system".to_string()
 }
+
+fn default_cache_quota_mb() -> u32 {
+    256
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_patch_has_only_changes() {
+        let old = AppConfig {
+            theme: "dark".to_string(),
+            logging: LoggingConfig {
+                modules: BTreeMap::from([("renderer".to_string(), "warn".to_string())]),
+                ..LoggingConfig::default()
+            },
+            ..AppConfig::default()
+        };
+        assert_eq!(merge_patch(&old, &old), None);
+
+        let mut new = old.clone();
+        new.dark_mode = true;
+        new.metrics_retention.raw_hours = 6;
+        new.logging.modules.clear();
+        assert_eq!(
+            merge_patch(&old, &new).unwrap(),
+            serde_json::json!({
+                "dark_mode": true,
+                "metrics_retention": { "raw_hours": 6 },
+                "logging": { "modules": { "renderer": null } }
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_settings_round_trip() {
+        let json = serde_json::json!({
+            "username": "me",
+            "proxy_url": "http://proxy",
+            "logging": { "level": "debug", "json_lines": true }
+        });
+        let config: AppConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(config.extra["proxy_url"], "http://proxy");
+        assert_eq!(config.logging.extra["json_lines"], true);
+        let back = serde_json::to_value(&config).unwrap();
+        assert_eq!(back["proxy_url"], "http://proxy");
+        assert_eq!(back["logging"]["json_lines"], true);
+    }
+
+    #[test]
+    fn test_paths_parsing() {
+        let dirs: Paths = serde_json::from_str(
+            r#"{"mode": "portable", "root": "/usb/app/user-data", "config": "/usb/app/user-data/config",
+                "data": "/usb/app/user-data/data", "cache": "/usb/app/user-data/cache",
+                "state": "/usb/app/user-data/state", "runtime": "/run/user/1000/app/root-ab12",
+                "logs": "/usb/app/user-data/state/logs"}"#,
+        )
+        .unwrap();
+        assert_eq!(dirs.mode_label(), "Portable");
+        assert_eq!(dirs.root.as_deref(), Some("/usb/app/user-data"));
+
+        let dirs: Paths = serde_json::from_str(
+            r#"{"mode": "installed", "root": null, "config": "c", "data": "d", "cache": "e",
+                "state": "s", "runtime": "r", "logs": "s/logs"}"#,
+        )
+        .unwrap();
+        assert_eq!(dirs.mode_label(), "Installed");
+        assert_eq!(dirs.root, None);
+    }
+
+    #[test]
+    fn test_versioned_and_save_outcome_parsing() {
+        let loaded: Versioned =
+            serde_json::from_str(r#"{"config": {"username": "me"}, "revision": "ab12"}"#).unwrap();
+        assert_eq!(loaded.config.username, "me");
+        assert_eq!(loaded.revision, "ab12");
+        // Fallback when the backend isn't loaded
+        assert_eq!(
+            serde_json::from_str::<Versioned>("{}").unwrap(),
+            Versioned::default()
+        );
+
+        let outcome: SaveOutcome = serde_json::from_str(
+            r#"{"status": "conflict", "revision": "cd34", "config": {"username": "other"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            outcome,
+            SaveOutcome::Conflict { ref revision, ref config } if revision == "cd34" && config.username == "other"
+        ));
+        let outcome: SaveOutcome =
+            serde_json::from_str(r#"{"status": "failed", "error": "disk full"}"#).unwrap();
+        assert_eq!(
+            outcome,
+            SaveOutcome::Failed {
+                error: "disk full".to_string()
+            }
+        );
+
+        let conflict = DiskConflict::parse(
+            r#"{"status": "conflict", "revision": "ef56", "config": {"theme": "dark"},
+                "changes": {"theme": "light"}}"#,
+        )
+        .unwrap();
+        assert_eq!(conflict.config.theme, "dark");
+        assert_eq!(conflict.changes, serde_json::json!({ "theme": "light" }));
+        assert_eq!(
+            DiskConflict::parse(r#"{"config": {}, "revision": "ab12"}"#),
+            None
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Reporting WASM panics to the backend as crash records.
+//!
+//! The panic hook still prints the panic to the DevTools console, then sends
+//! any buffered log entries and a report with the message, location, JS
+//! stack and current route to the main process, which has the backend write
+//! it to the crashes directory next to backend crash records.
+
+use crate::logging;
+use serde::Serialize;
+use std::panic::PanicHookInfo;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = reportCrash)]
+    fn report_crash(json: &str) -> js_sys::Promise;
+
+    type Error;
+
+    #[wasm_bindgen(constructor)]
+    fn new() -> Error;
+
+    #[wasm_bindgen(structural, method, getter)]
+    fn stack(error: &Error) -> String;
+}
+
+/// A panic report as sent to the backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PanicReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub stack: String,
+    pub route: String,
+    pub version: String,
+}
+
+impl PanicReport {
+    /// Builds a report from a panic hook's info.
+    pub fn new(info: &PanicHookInfo, stack: String, route: String) -> Self {
+        let payload = info.payload();
+        let message = if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else {
+            "unknown panic".to_string()
+        };
+        Self {
+            message,
+            location: info.location().map(|l| l.to_string()),
+            stack,
+            route,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+fn hook(info: &PanicHookInfo) {
+    console_error_panic_hook::hook(info);
+    logging::flush();
+    let report = PanicReport::new(info, Error::new().stack(), logging::current_route());
+    if let Ok(json) = serde_json::to_string(&report) {
+        let _ = report_crash(&json);
+    }
+}
+
+/// Installs the panic hook. Call once at startup.
+pub fn install() {
+    std::panic::set_hook(Box::new(hook));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_report_from_panic() {
+        let report = Arc::new(Mutex::new(None));
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new({
+            let report = Arc::clone(&report);
+            move |info| {
+                *report.lock().unwrap() = Some(PanicReport::new(
+                    info,
+                    "stack".to_string(),
+                    "Logs".to_string(),
+                ))
+            }
+        }));
+        let _ = std::panic::catch_unwind(|| panic!("bad index {}", 3));
+        std::panic::set_hook(previous);
+
+        let report = report.lock().unwrap().take().unwrap();
+        assert_eq!(report.message, "bad index 3");
+        assert!(report.location.as_deref().unwrap().contains("crash.rs"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["route"], "Logs");
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,410 @@
+//! Downsampling and aggregation of large series.
+//!
+//! Charts stay responsive with a few hundred points per series, so long
+//! series are reduced before they reach the [`Chart`] component. Like
+//! [`crate::math`], everything here is pure Rust that runs in WASM and is
+//! tested natively.
+//!
+//! The downsamplers return the indices of the points they keep, so several
+//! series sharing the same x values (and chart labels) can be reduced
+//! consistently; see [`ChartSpec::downsampled`].
+//!
+//! [`Chart`]: crate::chart::Chart
+//! [`ChartSpec::downsampled`]: crate::chart::ChartSpec::downsampled
+
+/// Algorithm used to reduce a series to a maximum number of points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Downsampler {
+    /// Largest-Triangle-Three-Buckets: keeps the visual shape of a line.
+    #[default]
+    Lttb,
+    /// Keeps the lowest and highest point of each bucket, so spikes and
+    /// the global extremes always survive.
+    MinMax,
+}
+
+impl Downsampler {
+    /// Returns the sorted indices of at most `max_points` points to keep.
+    ///
+    /// Min-max needs two points per bucket, so below two points it falls
+    /// back to LTTB, which keeps the first point.
+    pub fn indices(&self, xs: &[f64], ys: &[f64], max_points: usize) -> Vec<usize> {
+        match self {
+            Downsampler::MinMax if max_points >= 2 => min_max_indices(ys, max_points / 2),
+            Downsampler::Lttb | Downsampler::MinMax => lttb_indices(xs, ys, max_points),
+        }
+    }
+}
+
+/// Returns the indices of the points kept by Largest-Triangle-Three-Buckets.
+///
+/// The first and last points are always kept. Series with at most
+/// `threshold` points are returned unchanged; otherwise exactly `threshold`
+/// indices are returned, in increasing order.
+pub fn lttb_indices(xs: &[f64], ys: &[f64], threshold: usize) -> Vec<usize> {
+    let n = xs.len().min(ys.len());
+    if n <= threshold {
+        return (0..n).collect();
+    }
+    match threshold {
+        0 => return Vec::new(),
+        1 => return vec![0],
+        2 => return vec![0, n - 1],
+        _ => {}
+    }
+
+    // Points between the fixed endpoints are split into threshold - 2 buckets
+    let every = (n - 2) as f64 / (threshold - 2) as f64;
+    let bucket_start = |i: usize| ((i as f64 * every) as usize + 1).min(n - 1);
+
+    let mut kept = Vec::with_capacity(threshold);
+    kept.push(0);
+    let mut a = 0;
+
+    for bucket in 0..threshold - 2 {
+        let (start, end) = (bucket_start(bucket), bucket_start(bucket + 1));
+
+        // Average of the next bucket, or the last point for the final bucket
+        let (next_start, next_end) = (end, bucket_start(bucket + 2).max(end + 1).min(n));
+        let count = (next_end - next_start) as f64;
+        let avg_x = xs[next_start..next_end].iter().sum::<f64>() / count;
+        let avg_y = ys[next_start..next_end].iter().sum::<f64>() / count;
+
+        let (ax, ay) = (xs[a], ys[a]);
+        let mut best = start;
+        let mut best_area = f64::NEG_INFINITY;
+        for i in start..end.max(start + 1) {
+            let area = ((ax - avg_x) * (ys[i] - ay) - (ax - xs[i]) * (avg_y - ay)).abs();
+            if area > best_area {
+                best_area = area;
+                best = i;
+            }
+        }
+        kept.push(best);
+        a = best;
+    }
+
+    kept.push(n - 1);
+    kept
+}
+
+/// Returns the indices of the lowest and highest point in each of `buckets`
+/// equal-width index ranges, in increasing order.
+///
+/// At most `2 * buckets` indices are returned. The global minimum and
+/// maximum are always included. Non-finite values are never selected.
+pub fn min_max_indices(ys: &[f64], buckets: usize) -> Vec<usize> {
+    let n = ys.len();
+    if n <= buckets.saturating_mul(2) {
+        return (0..n).collect();
+    }
+    if buckets == 0 {
+        return Vec::new();
+    }
+
+    let mut kept = Vec::with_capacity(buckets * 2);
+    for bucket in 0..buckets {
+        let (start, end) = (bucket * n / buckets, (bucket + 1) * n / buckets);
+        let finite = (start..end).filter(|&i| ys[i].is_finite());
+        let Some((lo, hi)) = finite.fold(None, |acc: Option<(usize, usize)>, i| {
+            Some(match acc {
+                None => (i, i),
+                Some((lo, hi)) => (
+                    if ys[i] < ys[lo] { i } else { lo },
+                    if ys[i] > ys[hi] { i } else { hi },
+                ),
+            })
+        }) else {
+            continue;
+        };
+        kept.push(lo.min(hi));
+        if lo != hi {
+            kept.push(lo.max(hi));
+        }
+    }
+    kept
+}
+
+/// Reduces `(x, y)` points with Largest-Triangle-Three-Buckets.
+pub fn lttb(points: &[(f64, f64)], threshold: usize) -> Vec<(f64, f64)> {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = points.iter().copied().unzip();
+    lttb_indices(&xs, &ys, threshold)
+        .into_iter()
+        .map(|i| points[i])
+        .collect()
+}
+
+/// Reduces `(x, y)` points to the extremes of each of `buckets` buckets.
+pub fn min_max_buckets(points: &[(f64, f64)], buckets: usize) -> Vec<(f64, f64)> {
+    let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+    min_max_indices(&ys, buckets)
+        .into_iter()
+        .map(|i| points[i])
+        .collect()
+}
+
+/// Returns the mean of each value and the `window - 1` values before it.
+///
+/// The first values average over the shorter window available. A window of
+/// 0 is treated as 1.
+pub fn rolling_mean(values: &[f64], window: usize) -> Vec<f64> {
+    let window = window.max(1);
+    let mut sum = 0.0;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            sum += v;
+            if i >= window {
+                sum -= values[i - window];
+            }
+            sum / (i + 1).min(window) as f64
+        })
+        .collect()
+}
+
+/// Returns the finite values, sorted ascending.
+fn sorted_finite(values: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+/// Returns the `p`-th percentile (0 to 100) of sorted values, interpolating
+/// linearly between the closest ranks.
+fn percentile_of_sorted(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p.clamp(0.0, 100.0) / 100.0 * last as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    let fraction = rank - below as f64;
+    Some(sorted[below] + (sorted[above] - sorted[below]) * fraction)
+}
+
+/// Returns the `p`-th percentile (0 to 100) of the finite values.
+///
+/// # Examples
+///
+/// ```
+/// use frontend::downsample::percentile;
+/// assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 50.0), Some(2.5));
+/// assert_eq!(percentile(&[], 50.0), None);
+/// ```
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    percentile_of_sorted(&sorted_finite(values), p)
+}
+
+/// Returns several percentiles at once, sorting the values only once.
+///
+/// Returns an empty vector if there are no finite values.
+pub fn percentiles(values: &[f64], ps: &[f64]) -> Vec<f64> {
+    let sorted = sorted_finite(values);
+    ps.iter()
+        .filter_map(|&p| percentile_of_sorted(&sorted, p))
+        .collect()
+}
+
+/// Counts of values in equal-width bins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Lower edge of the first bin.
+    pub start: f64,
+    pub bin_width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Returns the lower edge of each bin.
+    pub fn lower_edges(&self) -> Vec<f64> {
+        (0..self.counts.len())
+            .map(|i| self.start + i as f64 * self.bin_width)
+            .collect()
+    }
+}
+
+/// Bins the finite values into `bins` equal-width bins spanning their range.
+///
+/// The maximum value falls into the last bin. Returns `None` if there are no
+/// finite values or `bins` is 0.
+pub fn histogram(values: &[f64], bins: usize) -> Option<Histogram> {
+    let finite = values.iter().copied().filter(|v| v.is_finite());
+    let (min, max) = finite.clone().fold(None, |acc: Option<(f64, f64)>, v| {
+        Some(acc.map_or((v, v), |(lo, hi)| (lo.min(v), hi.max(v))))
+    })?;
+    if bins == 0 {
+        return None;
+    }
+
+    // A single distinct value gets a unit-wide range centered on it
+    let (start, span) = if max > min {
+        (min, max - min)
+    } else {
+        (min - 0.5, 1.0)
+    };
+    let bin_width = span / bins as f64;
+
+    let mut counts = vec![0; bins];
+    for v in finite {
+        let bin = ((v - start) / bin_width) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    Some(Histogram {
+        start,
+        bin_width,
+        counts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_lttb_keeps_shape() {
+        let points: Vec<(f64, f64)> = (0..10)
+            .map(|i| (i as f64, if i == 4 { 100.0 } else { 0.0 }))
+            .collect();
+        let reduced = lttb(&points, 4);
+
+        assert_eq!(reduced.len(), 4);
+        assert_eq!(reduced[0], (0.0, 0.0));
+        assert_eq!(reduced[3], (9.0, 0.0));
+        assert!(reduced.contains(&(4.0, 100.0)));
+    }
+
+    #[test]
+    fn test_lttb_small_thresholds() {
+        let xs = [0.0, 1.0, 2.0];
+        let ys = [1.0, 2.0, 3.0];
+        assert_eq!(lttb_indices(&xs, &ys, 10), vec![0, 1, 2]);
+        assert_eq!(lttb_indices(&xs, &ys, 2), vec![0, 2]);
+        assert_eq!(lttb_indices(&xs, &ys, 1), vec![0]);
+        assert!(lttb_indices(&xs, &ys, 0).is_empty());
+    }
+
+    #[test]
+    fn test_min_max_indices() {
+        let ys = [5.0, 1.0, 9.0, 3.0, 3.0, 3.0, 8.0, 0.0];
+        assert_eq!(min_max_indices(&ys, 2), vec![1, 2, 6, 7]);
+        assert_eq!(min_max_indices(&ys, 4), (0..8).collect::<Vec<_>>());
+        assert_eq!(
+            min_max_indices(&[f64::NAN, 1.0, 2.0, 3.0, 4.0], 1),
+            vec![1, 4]
+        );
+    }
+
+    #[test]
+    fn test_min_max_with_few_points() {
+        let xs = [0.0, 1.0, 2.0, 3.0];
+        let ys = [4.0, 1.0, 9.0, 2.0];
+        assert_eq!(Downsampler::MinMax.indices(&xs, &ys, 1), vec![0]);
+        assert!(Downsampler::MinMax.indices(&xs, &ys, 0).is_empty());
+        assert_eq!(Downsampler::MinMax.indices(&xs, &ys, 2), vec![1, 2]);
+        assert_eq!(min_max_indices(&ys, usize::MAX), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_rolling_mean() {
+        assert_eq!(
+            rolling_mean(&[1.0, 2.0, 3.0, 4.0], 2),
+            vec![1.0, 1.5, 2.5, 3.5]
+        );
+        assert_eq!(rolling_mean(&[1.0, 3.0], 0), vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let values = [10.0, 0.0, f64::NAN, 5.0];
+        assert_eq!(
+            percentiles(&values, &[0.0, 50.0, 100.0]),
+            vec![0.0, 5.0, 10.0]
+        );
+        assert_eq!(percentile(&values, 25.0), Some(2.5));
+        assert!(percentiles(&[], &[50.0]).is_empty());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let h = histogram(&[0.0, 1.0, 2.0, 3.0, 4.0], 2).unwrap();
+        assert_eq!(h.start, 0.0);
+        assert_eq!(h.bin_width, 2.0);
+        assert_eq!(h.counts, vec![2, 3]);
+        assert_eq!(h.lower_edges(), vec![0.0, 2.0]);
+
+        let single = histogram(&[7.0, 7.0], 4).unwrap();
+        assert_eq!(single.counts.iter().sum::<usize>(), 2);
+        assert_eq!(histogram(&[], 4), None);
+        assert_eq!(histogram(&[1.0], 0), None);
+    }
+
+    fn series() -> impl Strategy<Value = Vec<f64>> {
+        prop::collection::vec(-1e6..1e6f64, 0..500)
+    }
+
+    fn extremes(values: &[f64]) -> Option<(f64, f64)> {
+        let min = values.iter().copied().reduce(f64::min)?;
+        let max = values.iter().copied().reduce(f64::max)?;
+        Some((min, max))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_min_max_keeps_extremes(ys in series(), buckets in 1usize..50) {
+            let kept = min_max_indices(&ys, buckets);
+
+            prop_assert!(kept.len() <= ys.len().min(buckets * 2));
+            prop_assert!(kept.windows(2).all(|w| w[0] < w[1]));
+            let values: Vec<f64> = kept.iter().map(|&i| ys[i]).collect();
+            prop_assert_eq!(extremes(&values), extremes(&ys));
+        }
+
+        #[test]
+        fn prop_lttb_keeps_endpoints(ys in series(), threshold in 3usize..100) {
+            let xs: Vec<f64> = (0..ys.len()).map(|i| i as f64).collect();
+            let kept = lttb_indices(&xs, &ys, threshold);
+
+            prop_assert_eq!(kept.len(), ys.len().min(threshold));
+            prop_assert!(kept.windows(2).all(|w| w[0] < w[1]));
+            if !ys.is_empty() {
+                prop_assert_eq!(kept.first(), Some(&0));
+                prop_assert_eq!(kept.last(), Some(&(ys.len() - 1)));
+            }
+        }
+
+        #[test]
+        fn prop_rolling_mean_stays_in_range(ys in series(), window in 0usize..20) {
+            let means = rolling_mean(&ys, window);
+
+            prop_assert_eq!(means.len(), ys.len());
+            if let Some((min, max)) = extremes(&ys) {
+                let tolerance = 1e-6 * max.abs().max(min.abs()).max(1.0);
+                prop_assert!(means.iter().all(|m| *m >= min - tolerance && *m <= max + tolerance));
+            }
+        }
+
+        #[test]
+        fn prop_percentiles_span_extremes(ys in series(), p in 0.0..100.0f64) {
+            let ps = percentiles(&ys, &[0.0, p, 100.0]);
+            match extremes(&ys) {
+                Some((min, max)) => {
+                    prop_assert_eq!(ps[0], min);
+                    prop_assert_eq!(ps[2], max);
+                    prop_assert!(ps[0] <= ps[1] && ps[1] <= ps[2]);
+                }
+                None => prop_assert!(ps.is_empty()),
+            }
+        }
+
+        #[test]
+        fn prop_histogram_counts_every_value(ys in series(), bins in 1usize..30) {
+            match histogram(&ys, bins) {
+                Some(h) => {
+                    prop_assert_eq!(h.counts.len(), bins);
+                    prop_assert_eq!(h.counts.iter().sum::<usize>(), ys.len());
+                }
+                None => prop_assert!(ys.is_empty()),
+            }
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! Exporting charts as PNG or SVG images and their data as CSV.
+//!
+//! Images are produced here in the renderer: SVG documents come from
+//! [`to_svg_document`], and PNGs from the Chart.js canvas or by rasterizing
+//! the SVG document. The result is handed to the backend over IPC, which asks
+//! for a destination, writes the file atomically and records it in the recent
+//! exports list.
+
+use crate::chart::{ChartBackend, ChartKind, ChartSpec, ThemeColors};
+use crate::logging;
+use crate::state::AppState;
+use crate::svg_chart::to_svg_document;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sycamore::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    CanvasRenderingContext2d, Element, HtmlCanvasElement, HtmlElement, HtmlImageElement,
+};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = exportChart)]
+    fn export_chart(format: &str, payload: &str, default_name: &str) -> js_sys::Promise;
+}
+
+/// Pixel density of exported PNGs relative to the SVG view box.
+const PNG_SCALE: f64 = 2.0;
+
+/// File name suggested in the save dialog, without extension.
+const DEFAULT_NAME: &str = "chart";
+
+/// File format of a chart export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Png,
+    Svg,
+    Csv,
+}
+
+impl ExportFormat {
+    const ALL: [ExportFormat; 3] = [ExportFormat::Png, ExportFormat::Svg, ExportFormat::Csv];
+
+    /// Returns true if a chart of `kind` drawn with `backend` can be exported in this format.
+    pub fn is_available(&self, kind: ChartKind, backend: ChartBackend) -> bool {
+        match self {
+            ExportFormat::Png => backend == ChartBackend::ChartJs || kind.supports_svg(),
+            ExportFormat::Svg => kind.supports_svg(),
+            ExportFormat::Csv => true,
+        }
+    }
+
+    /// Returns the format name used over IPC, which is also the file extension.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// Returns the menu label for this format.
+    pub fn action_label(&self) -> &'static str {
+        match self {
+            ExportFormat::Png => "Export as PNG",
+            ExportFormat::Svg => "Export as SVG",
+            ExportFormat::Csv => "Export data as CSV",
+        }
+    }
+}
+
+/// One entry of the backend's recent exports list.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RecentExport {
+    pub path: String,
+    pub format: ExportFormat,
+    pub bytes: u64,
+    pub timestamp_ms: u64,
+}
+
+impl RecentExport {
+    /// Returns the file name without its directory.
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path)
+    }
+}
+
+/// Builds the CSV table sent to the backend for `spec`.
+///
+/// Category charts get one row per label and one column per dataset; scatter
+/// charts get an x and a y column per dataset. Missing values are empty.
+pub fn csv_table(spec: &ChartSpec) -> Value {
+    let rows = spec
+        .datasets
+        .iter()
+        .map(|d| d.data.len())
+        .max()
+        .unwrap_or(0)
+        .max(spec.labels.len());
+    let value = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+
+    let (headers, rows): (Vec<String>, Vec<Vec<String>>) = if spec.kind == ChartKind::Scatter {
+        let headers = spec
+            .datasets
+            .iter()
+            .flat_map(|d| [format!("{} x", d.label), format!("{} y", d.label)])
+            .collect();
+        let rows = (0..rows)
+            .map(|i| {
+                spec.datasets
+                    .iter()
+                    .flat_map(|d| {
+                        let y = d.data.get(i).copied();
+                        [value(y.map(|_| d.x_at(i))), value(y)]
+                    })
+                    .collect()
+            })
+            .collect();
+        (headers, rows)
+    } else {
+        let headers = std::iter::once("label".to_string())
+            .chain(spec.datasets.iter().map(|d| d.label.clone()))
+            .collect();
+        let rows = (0..rows)
+            .map(|i| {
+                std::iter::once(spec.labels.get(i).cloned().unwrap_or_default())
+                    .chain(spec.datasets.iter().map(|d| value(d.data.get(i).copied())))
+                    .collect()
+            })
+            .collect();
+        (headers, rows)
+    };
+
+    json!({ "headers": headers, "rows": rows })
+}
+
+/// Waits for an image to finish loading.
+async fn load_image(image: &HtmlImageElement, src: &str) -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        image.set_onload(Some(&resolve));
+        image.set_onerror(Some(&reject));
+    });
+    image.set_src(src);
+    wasm_bindgen_futures::JsFuture::from(promise).await?;
+    Ok(())
+}
+
+/// Creates a canvas of the given size filled with `background`.
+fn filled_canvas(
+    width: f64,
+    height: f64,
+    background: &str,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let canvas: HtmlCanvasElement = document.create_element("canvas")?.unchecked_into();
+    canvas.set_width(width as u32);
+    canvas.set_height(height as u32);
+    let context: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("no 2d context"))?
+        .unchecked_into();
+    context.set_fill_style_str(background);
+    context.fill_rect(0.0, 0.0, width, height);
+    Ok((canvas, context))
+}
+
+/// Rasterizes an SVG document to a PNG data URL at [`PNG_SCALE`].
+pub async fn svg_to_png(svg: &str, background: &str) -> Result<String, JsValue> {
+    let image = HtmlImageElement::new()?;
+    let src = format!(
+        "data:image/svg+xml;charset=utf-8,{}",
+        js_sys::encode_uri_component(svg)
+    );
+    load_image(&image, &src).await?;
+
+    let (width, height) = (
+        image.natural_width() as f64 * PNG_SCALE,
+        image.natural_height() as f64 * PNG_SCALE,
+    );
+    let (canvas, context) = filled_canvas(width, height, background)?;
+    context.draw_image_with_html_image_element_and_dw_and_dh(&image, 0.0, 0.0, width, height)?;
+    canvas.to_data_url_with_type("image/png")
+}
+
+/// Copies a chart canvas onto a solid background and returns a PNG data URL.
+///
+/// Chart.js draws on a transparent canvas, which would be unreadable when
+/// pasted onto a background of a different color.
+pub fn canvas_to_png(source: &HtmlCanvasElement, background: &str) -> Result<String, JsValue> {
+    let (width, height) = (source.width() as f64, source.height() as f64);
+    let (canvas, context) = filled_canvas(width, height, background)?;
+    context.draw_image_with_html_canvas_element(source, 0.0, 0.0)?;
+    canvas.to_data_url_with_type("image/png")
+}
+
+/// Renders `spec` in `format` and saves it through the backend.
+///
+/// `canvas` is the Chart.js canvas when the chart is drawn by Chart.js; PNGs
+/// are then taken from it so the export matches what is on screen. Resolves
+/// to `None` if the user cancels the save dialog.
+pub async fn export(
+    spec: &ChartSpec,
+    format: ExportFormat,
+    canvas: Option<HtmlCanvasElement>,
+    default_name: &str,
+) -> Result<Option<RecentExport>, String> {
+    let theme = ThemeColors::current();
+    let unsupported = || {
+        format!(
+            "{:?} charts can't be exported as {}",
+            spec.kind,
+            format.as_str().to_uppercase()
+        )
+    };
+    let payload = match format {
+        ExportFormat::Csv => csv_table(spec).to_string(),
+        ExportFormat::Svg => to_svg_document(spec, &theme).ok_or_else(unsupported)?,
+        ExportFormat::Png => match canvas {
+            Some(canvas) => canvas_to_png(&canvas, &theme.surface),
+            None => {
+                let svg = to_svg_document(spec, &theme).ok_or_else(unsupported)?;
+                svg_to_png(&svg, &theme.surface).await
+            }
+        }
+        .map_err(|e| format!("Could not render PNG: {:?}", e))?,
+    };
+
+    let result =
+        wasm_bindgen_futures::JsFuture::from(export_chart(format.as_str(), &payload, default_name))
+            .await
+            .map_err(|e| {
+                e.dyn_ref::<js_sys::Error>()
+                    .map(|e| String::from(e.message()))
+                    .unwrap_or_else(|| format!("{:?}", e))
+            })?;
+    Ok(result
+        .as_string()
+        .and_then(|json| serde_json::from_str::<RecentExport>(&json).ok()))
+}
+
+/// Blurs the focused element, which closes a focus-based dropdown.
+fn close_dropdown() {
+    if let Some(element) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.active_element())
+        .and_then(|e| e.dyn_into::<HtmlElement>().ok())
+    {
+        let _ = element.blur();
+    }
+}
+
+/// Export actions shown in the top right corner of a chart.
+///
+/// `container` is the element wrapping the chart, searched for the Chart.js
+/// canvas. Results are reported through the app's notification and recent
+/// exports list when an [`AppState`] is provided.
+#[component(inline_props)]
+pub(crate) fn ExportMenu(
+    spec: ReadSignal<ChartSpec>,
+    backend: ChartBackend,
+    container: NodeRef,
+) -> View {
+    let app_state = try_use_context::<AppState>();
+
+    let run = move |format: ExportFormat| {
+        close_dropdown();
+        let canvas = (backend == ChartBackend::ChartJs)
+            .then(|| container.get().unchecked_into::<Element>())
+            .and_then(|c| c.query_selector("canvas").ok().flatten())
+            .map(|c| c.unchecked_into::<HtmlCanvasElement>());
+        let spec = spec.get_clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = export(&spec, format, canvas, DEFAULT_NAME).await;
+            if let Err(e) = &result {
+                logging::error(
+                    "Chart export failed",
+                    json!({ "format": format.as_str(), "error": e }),
+                );
+            }
+            let Some(app_state) = app_state else {
+                return;
+            };
+            match result {
+                Ok(Some(entry)) => {
+                    app_state.notify(format!("Exported {}", entry.file_name()));
+                    app_state.remember_export(entry);
+                }
+                Ok(None) => {}
+                Err(e) => app_state.notify(format!("Export failed: {}", e)),
+            }
+        });
+    };
+
+    let items = move || {
+        let kind = spec.with(|s| s.kind);
+        ExportFormat::ALL
+            .into_iter()
+            .filter(|f| f.is_available(kind, backend))
+            .map(|format| {
+                view! {
+                    li {
+                        button(on:click=move |_| run(format)) { (format.action_label()) }
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        div(class="dropdown dropdown-end absolute top-0 right-0 z-10") {
+            div(tabindex="0", role="button", class="btn btn-ghost btn-xs opacity-60 hover:opacity-100", title="Export") {
+                "⋯"
+            }
+            ul(tabindex="0", class="dropdown-content menu menu-sm bg-base-100 rounded-box shadow w-48 p-2") {
+                (items)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::Dataset;
+
+    #[test]
+    fn test_csv_table_categories() {
+        let spec = ChartSpec::new(ChartKind::Bar)
+            .with_labels(["Jan", "Feb", "Mar"])
+            .with_dataset(Dataset::new("Sales, EUR", vec![1.5, 2.0, 3.0]))
+            .with_dataset(Dataset::new("Costs", vec![1.0, 1.0]));
+
+        assert_eq!(
+            csv_table(&spec),
+            json!({
+                "headers": ["label", "Sales, EUR", "Costs"],
+                "rows": [["Jan", "1.5", "1"], ["Feb", "2", "1"], ["Mar", "3", ""]],
+            })
+        );
+    }
+
+    #[test]
+    fn test_csv_table_scatter() {
+        let spec = ChartSpec::new(ChartKind::Scatter)
+            .with_dataset(Dataset::points("A", vec![(0.5, 1.0), (2.0, 3.0)]))
+            .with_dataset(Dataset::points("B", vec![(1.0, 4.0)]));
+
+        assert_eq!(
+            csv_table(&spec),
+            json!({
+                "headers": ["A x", "A y", "B x", "B y"],
+                "rows": [["0.5", "1", "1", "4"], ["2", "3", "", ""]],
+            })
+        );
+    }
+
+    #[test]
+    fn test_available_formats() {
+        let available = |kind, backend| {
+            ExportFormat::ALL
+                .into_iter()
+                .filter(|f| f.is_available(kind, backend))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            available(ChartKind::Line, ChartBackend::Svg),
+            ExportFormat::ALL.to_vec()
+        );
+        assert_eq!(
+            available(ChartKind::Pie, ChartBackend::ChartJs),
+            vec![ExportFormat::Png, ExportFormat::Csv]
+        );
+        assert_eq!(
+            available(ChartKind::Radar, ChartBackend::Svg),
+            vec![ExportFormat::Csv]
+        );
+    }
+
+    #[test]
+    fn test_recent_export_file_name() {
+        let entry: RecentExport = serde_json::from_str(
+            r#"{"path": "C:\\Users\\me\\chart.png", "format": "png", "bytes": 10, "timestamp_ms": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.file_name(), "chart.png");
+        assert_eq!(entry.format, ExportFormat::Png);
+    }
+}
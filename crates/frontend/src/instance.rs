@@ -0,0 +1,93 @@
+//! Acting on later launches of the app.
+//!
+//! Only one instance of the app runs. Launching it again brings the running
+//! window to the front and forwards the new command line here, so that e.g.
+//! `--route logs` opens the Logs page.
+
+use crate::state::{AppState, Route};
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = onSecondInstance)]
+    fn on_second_instance(callback: &Closure<dyn FnMut(String)>) -> js_sys::Function;
+}
+
+/// Command line of a later launch.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Forwarded {
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub cwd: String,
+}
+
+/// Returns the page named by `--route <name>` or `--route=<name>`, if any.
+pub fn requested_route(args: &[String]) -> Option<Route> {
+    let name = args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--route" {
+            args.get(i + 1).map(String::as_str)
+        } else {
+            arg.strip_prefix("--route=")
+        }
+    })?;
+    match name.to_ascii_lowercase().as_str() {
+        "home" | "dashboard" => Some(Route::Home),
+        "diagnostics" => Some(Route::Diagnostics),
+        "performance" => Some(Route::Performance),
+        "logs" => Some(Route::Logs),
+        _ => None,
+    }
+}
+
+/// Navigates to the page requested by each later launch, for the lifetime
+/// of the app.
+pub fn start(app_state: AppState) {
+    let callback = Closure::<dyn FnMut(String)>::new(move |json: String| {
+        let Ok(forwarded) = serde_json::from_str::<Forwarded>(&json) else {
+            return;
+        };
+        if let Some(route) = requested_route(&forwarded.args) {
+            app_state.navigate(route);
+        }
+    });
+
+    // The subscription lives as long as the page, so the closure is never dropped
+    let _unsubscribe = on_second_instance(&callback);
+    callback.forget();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_requested_route() {
+        assert_eq!(
+            requested_route(&args(&[".", "--route", "logs"])),
+            Some(Route::Logs)
+        );
+        assert_eq!(
+            requested_route(&args(&["--route=Performance"])),
+            Some(Route::Performance)
+        );
+        assert_eq!(
+            requested_route(&args(&["--route=dashboard"])),
+            Some(Route::Home)
+        );
+        assert_eq!(requested_route(&args(&["--route"])), None);
+        assert_eq!(requested_route(&args(&["--route", "nowhere"])), None);
+        assert_eq!(requested_route(&args(&["."])), None);
+    }
+
+    #[test]
+    fn test_forwarded_parsing() {
+        let forwarded: Forwarded =
+            serde_json::from_str(r#"{"args": ["--route", "logs"], "cwd": "/home/me"}"#).unwrap();
+        assert_eq!(requested_route(&forwarded.args), Some(Route::Logs));
+    }
+}
@@ -4,36 +4,99 @@
 //! Original classes are in comments next to each component. Also see
 //! styles-vanilla.css for the original CSS.
 
+pub mod chart;
 pub mod config;
+pub mod crash;
+pub mod downsample;
+pub mod export;
+pub mod instance;
+pub mod logging;
+pub mod logview;
 pub mod math;
+pub mod schema;
 pub mod state;
-
-use config::AppConfig;
-use state::AppState;
+pub mod support;
+pub mod svg_chart;
+pub mod sysinfo;
+
+use chart::{Chart, ChartBackend, ChartKind, ChartOptions, ChartSpec, Dataset};
+use config::{AppConfig, CacheUsage, DiskConflict, Paths, SaveOutcome, Versioned};
+use downsample::Downsampler;
+use export::RecentExport;
+use serde_json::json;
+use state::{AppState, Route};
 use sycamore::prelude::*;
 use sycamore::web::on_mount;
+use sysinfo::{
+    EnvironmentInfo, HistoryRange, MetricsSample, MountUsage, SensorReading, StorageReport,
+    SystemInfo,
+};
 use wasm_bindgen::prelude::*;
 use web_sys::{Element, PointerEvent};
 
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-
     #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = rustHello)]
     fn rust_hello() -> js_sys::Promise;
 
-    #[wasm_bindgen(js_namespace = window, js_name = initDemoChart)]
-    fn init_demo_chart(canvas_id: &str);
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getSystemInfo)]
+    fn get_system_info() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getEnvironmentInfo)]
+    fn get_environment_info() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getSensors)]
+    fn get_sensors() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getStorageReport)]
+    fn get_storage_report(include_pseudo: bool) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getMetricsHistory)]
+    fn get_metrics_history() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = queryMetricsHistory)]
+    fn query_metrics_history(
+        series: &str,
+        from_ms: f64,
+        to_ms: f64,
+        max_points: u32,
+    ) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = reportWasmMemory)]
+    fn report_wasm_memory(bytes: f64) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = onMetricsSample)]
+    fn on_metrics_sample(callback: &Closure<dyn FnMut(String)>) -> js_sys::Function;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getRecentExports)]
+    fn get_recent_exports() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = showInFolder)]
+    fn show_in_folder(path: &str) -> js_sys::Promise;
 
     #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = loadConfig)]
     fn load_config() -> js_sys::Promise;
 
-    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = saveConfig)]
-    fn save_config(config_json: &str) -> js_sys::Promise;
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = patchConfig)]
+    fn patch_config(patch_json: &str, expected_revision: &str) -> js_sys::Promise;
 
     #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getConfigPath)]
     fn get_config_path() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getConfigSchema)]
+    fn get_config_schema() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getPaths)]
+    fn get_paths() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = getCacheUsage)]
+    fn get_cache_usage() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = clearCache)]
+    fn clear_cache() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = onConfigChanged)]
+    fn on_config_changed(callback: &Closure<dyn FnMut(String)>) -> js_sys::Function;
 }
 
 /// Counter component demonstrating Sycamore reactivity.
@@ -111,7 +174,10 @@ fn BackendDemo() -> View {
                     }
                 }
                 Err(e) => {
-                    log(&format!("Error calling backend: {:?}", e));
+                    logging::error(
+                        "Error calling backend",
+                        json!({ "error": format!("{:?}", e) }),
+                    );
                     message.set(String::from("Error calling backend"));
                 }
             }
@@ -138,26 +204,805 @@ fn BackendDemo() -> View {
     }
 }
 
-/// Chart demo component showing JS library integration.
+/// Chart demo component showing JS library integration and the native SVG
+/// alternative.
 ///
-/// DAISYUI classes used: card, card-body
+/// DAISYUI classes used: card, card-body, join, btn, toggle
 /// Custom class: chart-container (height required for Chart.js)
 /// Original class: "chart-demo"
 #[component]
 fn ChartDemo() -> View {
-    on_mount(move || {
-        init_demo_chart("demo-chart");
+    let kind = create_signal(ChartKind::Bar);
+    let use_svg = create_signal(false);
+    let spec = create_memo(move || {
+        ChartSpec::new(kind.get())
+            .with_labels(["Rust", "WASM", "Sycamore", "Electron", "Neon"])
+            .with_dataset(
+                Dataset::new("Template Technologies", vec![95.0, 88.0, 82.0, 78.0, 85.0])
+                    .colored_per_point(),
+            )
     });
 
+    let kind_button = move |label: &'static str, value: ChartKind| {
+        view! {
+            button(
+                class=move || if kind.get() == value { "btn btn-sm join-item btn-active" } else { "btn btn-sm join-item" },
+                on:click=move |_| kind.set(value),
+            ) { (label) }
+        }
+    };
+
     view! {
         // DAISYUI: card card-body -> Original: div(class="chart-demo")
         div(class="card bg-base-200 shadow-xl") {
             div(class="card-body") {
-                h2(class="card-title text-primary") { "Chart.js Demo" }
-                p(class="text-base-content/70") { "Example JavaScript library integration" }
+                div(class="flex items-center justify-between") {
+                    h2(class="card-title text-primary") { "Chart Demo" }
+                    div(class="join") {
+                        (kind_button("Bar", ChartKind::Bar))
+                        (kind_button("Line", ChartKind::Line))
+                        (kind_button("Area", ChartKind::Area))
+                        (kind_button("Doughnut", ChartKind::Doughnut))
+                    }
+                }
+                label(class="label gap-2 text-base-content/70") {
+                    input(r#type="checkbox", class="toggle toggle-sm", bind:checked=use_svg)
+                    (move || if use_svg.get() {
+                        "Native SVG rendering"
+                    } else {
+                        "Example JavaScript library integration (Chart.js)"
+                    })
+                }
                 // Custom class: chart-container (needs fixed height for Chart.js)
                 div(class="chart-container") {
-                    canvas(id="demo-chart") {}
+                    (move || if use_svg.get() {
+                        view! { Chart(spec=spec, backend=ChartBackend::Svg) }
+                    } else {
+                        view! { Chart(spec=spec) }
+                    })
+                }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// System Components
+// ============================================================================
+
+/// Single mount row with a usage bar.
+#[component(inline_props)]
+fn MountRow(mount: MountUsage, is_config_mount: bool) -> View {
+    let percent = mount.used_percent();
+    let bar_class = if mount.is_nearly_full() {
+        "progress progress-error w-full"
+    } else if percent >= 75.0 {
+        "progress progress-warning w-full"
+    } else {
+        "progress progress-primary w-full"
+    };
+    let summary = format!(
+        "{} used of {} ({:.0}%), {} free",
+        sysinfo::format_bytes(mount.used_bytes),
+        sysinfo::format_bytes(mount.total_bytes),
+        percent,
+        sysinfo::format_bytes(mount.free_bytes),
+    );
+    let inodes = format!("Inodes: {} / {}", mount.used_inodes, mount.total_inodes);
+
+    view! {
+        li(class="flex flex-col gap-1") {
+            div(class="flex items-center gap-2") {
+                span(class="font-mono font-medium") { (mount.mount_point.clone()) }
+                span(class="badge badge-ghost badge-sm") { (mount.fs_type.clone()) }
+                (if mount.read_only {
+                    view! { span(class="badge badge-outline badge-sm") { "read-only" } }
+                } else {
+                    view! {}
+                })
+                (if is_config_mount {
+                    view! { span(class="badge badge-info badge-sm") { "config" } }
+                } else {
+                    view! {}
+                })
+            }
+            progress(class=bar_class, value=percent.round().to_string(), max="100") {}
+            div(class="flex justify-between text-xs text-base-content/70") {
+                span { (summary) }
+                span { (inodes) }
+            }
+        }
+    }
+}
+
+/// Storage card listing mounted filesystems with per-mount usage bars.
+/// Warns through the notification system when the config mount is nearly full.
+#[component]
+fn StorageCard() -> View {
+    let app_state = use_context::<AppState>();
+
+    let report = create_signal(StorageReport::default());
+    let include_pseudo = create_signal(false);
+    let error = create_signal(None::<String>);
+
+    let refresh = move || {
+        let include = include_pseudo.get();
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(get_storage_report(include)).await {
+                Ok(result) => {
+                    let parsed = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<StorageReport>(&json).ok());
+                    if let Some(parsed) = parsed {
+                        if let Some(mount) = parsed.config_mount().filter(|m| m.is_nearly_full()) {
+                            app_state.notify(format!(
+                                "Storage for {} is {:.0}% full; settings may fail to save.",
+                                mount.mount_point,
+                                mount.used_percent()
+                            ));
+                        }
+                        error.set(None);
+                        report.set(parsed);
+                    }
+                }
+                Err(e) => {
+                    logging::error(
+                        "Error loading storage report",
+                        json!({ "error": format!("{:?}", e) }),
+                    );
+                    error.set(Some("Could not read mounted filesystems".to_string()));
+                }
+            }
+        });
+    };
+
+    // Reload whenever the pseudo-filesystem toggle changes (and on mount)
+    create_effect(move || {
+        include_pseudo.track();
+        refresh();
+    });
+
+    view! {
+        div(class="card bg-base-200 shadow-xl") {
+            div(class="card-body") {
+                div(class="flex items-center justify-between") {
+                    h2(class="card-title text-primary") { "Storage" }
+                    div(class="flex items-center gap-4") {
+                        label(class="label cursor-pointer gap-2") {
+                            span(class="label-text") { "Show pseudo filesystems" }
+                            input(
+                                r#type="checkbox",
+                                class="toggle toggle-sm",
+                                bind:checked=include_pseudo,
+                            )
+                        }
+                        button(class="btn btn-outline btn-sm", on:click=move |_| refresh()) {
+                            "Refresh"
+                        }
+                    }
+                }
+
+                (if let Some(message) = error.get_clone() {
+                    view! { div(class="alert alert-error") { span { (message) } } }
+                } else {
+                    view! {}
+                })
+
+                ul(class="flex flex-col gap-4 mt-2") {
+                    Indexed(
+                        list=move || report.with(|r| r.mounts.clone()),
+                        view=move |mount| {
+                            let is_config_mount = report
+                                .with(|r| r.config_mount.as_deref() == Some(mount.mount_point.as_str()));
+                            view! { MountRow(mount=mount, is_config_mount=is_config_mount) }
+                        },
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Label/value row in a diagnostics table.
+#[component(inline_props)]
+fn InfoRow(label: &'static str, value: String) -> View {
+    view! {
+        tr {
+            th(class="font-medium text-base-content/70 w-48") { (label) }
+            td(class="font-mono") { (value) }
+        }
+    }
+}
+
+/// Environment card showing OS identity, locale, session and sandboxing.
+#[component]
+fn EnvironmentCard() -> View {
+    let system = create_signal(None::<SystemInfo>);
+    let environment = create_signal(None::<EnvironmentInfo>);
+
+    on_mount(move || {
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(get_system_info()).await {
+                Ok(result) => {
+                    let parsed = js_sys::JSON::stringify(&result)
+                        .ok()
+                        .and_then(|json| json.as_string())
+                        .and_then(|json| serde_json::from_str::<SystemInfo>(&json).ok());
+                    system.set(parsed);
+                }
+                Err(e) => logging::error(
+                    "Error loading system info",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+
+            match wasm_bindgen_futures::JsFuture::from(get_environment_info()).await {
+                Ok(result) => {
+                    let parsed = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<EnvironmentInfo>(&json).ok());
+                    environment.set(parsed);
+                }
+                Err(e) => logging::error(
+                    "Error loading environment info",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+        });
+    });
+
+    let or_unknown = |value: Option<String>| value.unwrap_or_else(|| "unknown".to_string());
+
+    view! {
+        div(class="card bg-base-200 shadow-xl") {
+            div(class="card-body") {
+                h2(class="card-title text-primary") { "Environment" }
+                (match environment.get_clone() {
+                    Some(env) => {
+                        let platform = system
+                            .get_clone()
+                            .map(|s| format!("{} / {} ({})", s.platform, s.arch, s.family))
+                            .unwrap_or_else(|| "unknown".to_string());
+                        let rows = vec![
+                            ("Operating system", env.os.display_name()),
+                            ("Distribution ID", or_unknown(env.os.id)),
+                            ("Version ID", or_unknown(env.os.version_id)),
+                            ("Kernel", env.kernel),
+                            ("Platform", platform),
+                            ("Locale", or_unknown(env.locale)),
+                            ("Timezone", or_unknown(env.timezone)),
+                            ("Session type", env.session_type),
+                            ("Desktop", or_unknown(env.desktop)),
+                            ("Packaging", env.packaging),
+                            ("Container", env.container.unwrap_or_else(|| "none".to_string())),
+                        ];
+                        let rows = rows
+                            .into_iter()
+                            .map(|(label, value)| view! { InfoRow(label=label, value=value) })
+                            .collect::<Vec<_>>();
+                        view! {
+                            table(class="table table-sm") {
+                                tbody { (rows) }
+                            }
+                        }
+                    }
+                    None => view! {
+                        span(class="loading loading-spinner loading-sm") {}
+                    },
+                })
+            }
+        }
+    }
+}
+
+/// Sensors card showing temperatures, fan speeds and voltages.
+///
+/// Loads the readings once on mount, then follows the metrics stream.
+#[component]
+fn SensorsCard() -> View {
+    let app_state = use_context::<AppState>();
+    let readings = create_signal(Vec::<SensorReading>::new());
+
+    on_mount(move || {
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(get_sensors()).await {
+                Ok(result) => {
+                    if let Some(parsed) = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<Vec<SensorReading>>(&json).ok())
+                    {
+                        readings.set(parsed);
+                    }
+                }
+                Err(e) => logging::error(
+                    "Error loading sensors",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+        });
+    });
+
+    create_effect(move || {
+        if let Some(sample) = app_state.latest_metrics.get_clone() {
+            readings.set(sample.sensors);
+        }
+    });
+
+    let threshold = |reading: &SensorReading, value: Option<f64>| {
+        value
+            .map(|v| reading.format_value(v))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    view! {
+        div(class="card bg-base-200 shadow-xl") {
+            div(class="card-body") {
+                h2(class="card-title text-primary") { "Sensors" }
+                (if readings.with(|r| r.is_empty()) {
+                    view! {
+                        p(class="text-base-content/70") { "No hardware sensors found." }
+                    }
+                } else {
+                    view! {
+                        table(class="table table-sm") {
+                            thead {
+                                tr {
+                                    th { "Chip" }
+                                    th { "Sensor" }
+                                    th { "Value" }
+                                    th { "Max" }
+                                    th { "Critical" }
+                                }
+                            }
+                            tbody {
+                                Indexed(
+                                    list=readings,
+                                    view=move |reading| {
+                                        let badge = if reading.is_critical() {
+                                            "badge badge-error"
+                                        } else if reading.is_over_max() {
+                                            "badge badge-warning"
+                                        } else {
+                                            "badge badge-ghost"
+                                        };
+                                        let value = reading.format_value(reading.value);
+                                        let max = threshold(&reading, reading.max);
+                                        let critical = threshold(&reading, reading.critical);
+                                        view! {
+                                            tr {
+                                                td(class="font-mono") { (reading.chip) }
+                                                td { (reading.label) }
+                                                td { span(class=badge) { (value) } }
+                                                td { (max) }
+                                                td { (critical) }
+                                            }
+                                        }
+                                    },
+                                )
+                            }
+                        }
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Diagnostics page with details about the host system.
+#[component]
+fn DiagnosticsPage() -> View {
+    view! {
+        div(class="flex flex-col gap-6") {
+            EnvironmentCard {}
+            SensorsCard {}
+        }
+    }
+}
+
+/// Returns the current size of this renderer's WASM linear memory.
+fn wasm_memory_bytes() -> u64 {
+    wasm_bindgen::memory()
+        .unchecked_into::<js_sys::WebAssembly::Memory>()
+        .buffer()
+        .unchecked_into::<js_sys::ArrayBuffer>()
+        .byte_length() as u64
+}
+
+/// Subscribes to the backend metrics stream for the lifetime of the app.
+///
+/// Each sample is published to `AppState::latest_metrics`, answered with this
+/// renderer's WASM memory size, and checked against the memory warning threshold.
+fn start_metrics_monitor(app_state: AppState) {
+    let over_threshold = create_signal(false);
+
+    let callback = Closure::<dyn FnMut(String)>::new(move |json: String| {
+        let Ok(sample) = serde_json::from_str::<MetricsSample>(&json) else {
+            return;
+        };
+
+        let threshold_mb = app_state.memory_warning_mb.get_untracked();
+        let rss_mb = sample.process.total_rss_bytes / (1024 * 1024);
+        let over = threshold_mb > 0 && rss_mb > threshold_mb as u64;
+        if over && !over_threshold.get_untracked() {
+            app_state.notify(format!(
+                "App memory use is {} MiB, above the {} MiB warning threshold.",
+                rss_mb, threshold_mb
+            ));
+        }
+        over_threshold.set(over);
+
+        app_state.latest_metrics.set(Some(sample));
+        let _ = report_wasm_memory(wasm_memory_bytes() as f64);
+    });
+
+    // The subscription lives as long as the page, so the closure is never dropped
+    let _unsubscribe = on_metrics_sample(&callback);
+    callback.forget();
+}
+
+/// Subscribes to config changes made outside the app, e.g. by the CLI, for
+/// the lifetime of the app.
+///
+/// The new config is applied to the global state and published to
+/// `AppState::external_config` for the Settings card. A change that
+/// conflicts with settings the backend has yet to write is published to
+/// `AppState::config_conflict` instead.
+fn start_config_watch(app_state: AppState) {
+    let callback = Closure::<dyn FnMut(String)>::new(move |json: String| {
+        if let Some(conflict) = DiskConflict::parse(&json) {
+            let current = Versioned {
+                config: conflict.config,
+                revision: conflict.revision,
+            };
+            app_state
+                .config_conflict
+                .set(Some((current, conflict.changes)));
+            return;
+        }
+        let Ok(loaded) = serde_json::from_str::<Versioned>(&json) else {
+            return;
+        };
+        app_state.username.set(loaded.config.username.clone());
+        app_state
+            .memory_warning_mb
+            .set(loaded.config.memory_warning_mb);
+        app_state.external_config.set(Some(loaded));
+    });
+
+    // The subscription lives as long as the page, so the closure is never dropped
+    let _unsubscribe = on_config_changed(&callback);
+    callback.forget();
+}
+
+/// Single statistic tile.
+#[component(inline_props)]
+fn StatTile(title: &'static str, value: String, desc: String) -> View {
+    view! {
+        div(class="stat") {
+            div(class="stat-title") { (title) }
+            div(class="stat-value text-2xl") { (value) }
+            div(class="stat-desc") { (desc) }
+        }
+    }
+}
+
+/// Time windows offered by the metrics history card, as (label, milliseconds).
+const HISTORY_WINDOWS: [(&str, u64); 4] = [
+    ("1h", 3_600_000),
+    ("24h", 86_400_000),
+    ("7d", 7 * 86_400_000),
+    ("30d", 30 * 86_400_000),
+];
+
+/// Card charting the on-disk metrics history over a selectable time window.
+///
+/// Each bucket is drawn with its mean and its maximum, so short spikes stay
+/// visible when a long window is compressed into a few points.
+#[component]
+fn MetricsHistoryCard() -> View {
+    let window_ms = create_signal(HISTORY_WINDOWS[1].1);
+    let series = create_signal("rss_bytes".to_string());
+    let range = create_signal(None::<HistoryRange>);
+
+    create_effect(move || {
+        let window = window_ms.get();
+        let name = series.get_clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let now = js_sys::Date::now();
+            let promise = query_metrics_history(&name, now - window as f64, now, 200);
+            match wasm_bindgen_futures::JsFuture::from(promise).await {
+                Ok(result) => range.set(
+                    result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<HistoryRange>(&json).ok()),
+                ),
+                Err(e) => logging::error(
+                    "Error querying metrics history",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+        });
+    });
+
+    let spec = create_memo(move || {
+        let now = js_sys::Date::now() as u64;
+        range.with(|r| {
+            let points = r.as_ref().map(|r| r.points.as_slice()).unwrap_or_default();
+            let (scale, unit) = if series.with(|s| s == "rss_bytes") {
+                (1024.0 * 1024.0, "MiB")
+            } else {
+                (1.0, "%")
+            };
+            ChartSpec::new(ChartKind::Line)
+                .with_labels(
+                    points
+                        .iter()
+                        .map(|p| sysinfo::format_ago(now.saturating_sub(p.timestamp_ms)))
+                        .collect::<Vec<_>>(),
+                )
+                .with_dataset(
+                    Dataset::new("Mean", points.iter().map(|p| p.mean / scale).collect()).filled(),
+                )
+                .with_dataset(Dataset::new(
+                    "Max",
+                    points.iter().map(|p| p.max / scale).collect(),
+                ))
+                .with_options(ChartOptions {
+                    animate: false,
+                    y_label: Some(unit.to_string()),
+                    ..ChartOptions::default()
+                })
+        })
+    });
+
+    let resolution = move || {
+        range.with(|r| match r.as_ref().map(|r| r.resolution.as_str()) {
+            Some("raw") => "Full resolution",
+            Some("1m") => "1-minute roll-ups",
+            Some("1h") => "1-hour roll-ups",
+            _ => "",
+        })
+    };
+
+    view! {
+        div(class="card bg-base-200 shadow-xl") {
+            div(class="card-body") {
+                div(class="flex flex-wrap items-center justify-between gap-2") {
+                    h2(class="card-title text-primary") { "History" }
+                    div(class="flex flex-wrap items-center gap-2") {
+                        select(class="select select-bordered select-sm", bind:value=series) {
+                            option(value="rss_bytes") { "Memory" }
+                            option(value="cpu_percent") { "CPU" }
+                        }
+                        div(class="join") {
+                            Indexed(
+                                list=HISTORY_WINDOWS.to_vec(),
+                                view=move |(label, ms)| view! {
+                                    button(
+                                        class=move || if window_ms.get() == ms {
+                                            "btn btn-sm join-item btn-primary"
+                                        } else {
+                                            "btn btn-sm join-item"
+                                        },
+                                        on:click=move |_| window_ms.set(ms),
+                                    ) { (label) }
+                                },
+                            )
+                        }
+                    }
+                }
+                div(class="chart-container") {
+                    Chart(spec=spec, backend=ChartBackend::Svg)
+                }
+                p(class="text-xs text-base-content/60") { (resolution()) }
+            }
+        }
+    }
+}
+
+/// Performance page showing the app's own resource usage.
+///
+/// Loads the backend's bounded sample history on mount and then appends
+/// samples from the live metrics stream.
+#[component]
+fn PerformancePage() -> View {
+    let app_state = use_context::<AppState>();
+    let history = create_signal(Vec::<MetricsSample>::new());
+
+    on_mount(move || {
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(get_metrics_history()).await {
+                Ok(result) => {
+                    if let Some(samples) = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<Vec<MetricsSample>>(&json).ok())
+                    {
+                        history.set(samples);
+                    }
+                }
+                Err(e) => logging::error(
+                    "Error loading metrics history",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+        });
+    });
+
+    // Append streamed samples, keeping the same bound as the backend history
+    create_effect(move || {
+        if let Some(sample) = app_state.latest_metrics.get_clone() {
+            history.update(|h| {
+                if h.last().map(|s| s.timestamp_ms) != Some(sample.timestamp_ms) {
+                    h.push(sample);
+                }
+                let excess = h.len().saturating_sub(sysinfo::METRICS_HISTORY_CAPACITY);
+                h.drain(..excess);
+            });
+        }
+    });
+
+    // Time axis labels are seconds relative to the newest sample
+    let time_labels = move |h: &[MetricsSample]| -> Vec<String> {
+        let newest = h.last().map(|s| s.timestamp_ms).unwrap_or(0);
+        h.iter()
+            .map(|s| format!("-{}s", newest.saturating_sub(s.timestamp_ms) / 1000))
+            .collect()
+    };
+    let streaming = ChartOptions {
+        animate: false,
+        show_legend: false,
+        ..ChartOptions::default()
+    };
+    // Keep the live charts light even when the history grows
+    const CHART_POINTS: usize = 120;
+    let memory_chart = create_memo({
+        let options = streaming.clone();
+        move || {
+            history.with(|h| {
+                let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+                ChartSpec::new(ChartKind::Line)
+                    .with_labels(time_labels(h))
+                    .with_dataset(
+                        Dataset::new(
+                            "RSS",
+                            h.iter().map(|s| mib(s.process.total_rss_bytes)).collect(),
+                        )
+                        .filled(),
+                    )
+                    .with_options(ChartOptions {
+                        y_label: Some("MiB".to_string()),
+                        ..options.clone()
+                    })
+                    .downsampled(CHART_POINTS, Downsampler::MinMax)
+            })
+        }
+    });
+    let cpu_chart = create_memo(move || {
+        history.with(|h| {
+            ChartSpec::new(ChartKind::Line)
+                .with_labels(time_labels(h))
+                .with_dataset(Dataset::new(
+                    "CPU",
+                    h.iter().map(|s| s.cpu_percent.unwrap_or(0.0)).collect(),
+                ))
+                .with_options(ChartOptions {
+                    y_label: Some("%".to_string()),
+                    ..streaming.clone()
+                })
+                .downsampled(CHART_POINTS, Downsampler::Lttb)
+        })
+    });
+
+    let tiles = move || {
+        let Some(sample) = history.with(|h| h.last().cloned()) else {
+            return view! {
+                span(class="loading loading-spinner loading-sm") {}
+            };
+        };
+        let (peak_rss, samples) = history.with(|h| {
+            let peak = h
+                .iter()
+                .map(|s| s.process.total_rss_bytes)
+                .max()
+                .unwrap_or(0);
+            (peak, h.len())
+        });
+        let process = &sample.process;
+        let cpu = sample
+            .cpu_percent
+            .map(|c| format!("{:.1}%", c))
+            .unwrap_or_else(|| "-".to_string());
+        let wasm = sample
+            .wasm_memory_bytes
+            .map(sysinfo::format_bytes)
+            .unwrap_or_else(|| "-".to_string());
+
+        view! {
+            div(class="stats stats-vertical lg:stats-horizontal shadow") {
+                StatTile(
+                    title="Memory (RSS)",
+                    value=sysinfo::format_bytes(process.total_rss_bytes),
+                    desc=format!("Peak {} over {} samples", sysinfo::format_bytes(peak_rss), samples),
+                )
+                StatTile(
+                    title="CPU",
+                    value=cpu,
+                    desc=format!("{:.1} s total CPU time", process.total_cpu_time_ms as f64 / 1000.0),
+                )
+                StatTile(
+                    title="Threads",
+                    value=process.total_threads.to_string(),
+                    desc=format!("{} processes", process.processes.len()),
+                )
+                StatTile(
+                    title="Open files",
+                    value=process.total_open_fds.to_string(),
+                    desc="File descriptors".to_string(),
+                )
+                StatTile(title="WASM memory", value=wasm, desc="Renderer linear memory".to_string())
+            }
+        }
+    };
+
+    view! {
+        div(class="flex flex-col gap-6") {
+            div(class="card bg-base-200 shadow-xl") {
+                div(class="card-body") {
+                    h2(class="card-title text-primary") { "Performance" }
+                    (tiles())
+                }
+            }
+            div(class="grid grid-cols-1 lg:grid-cols-2 gap-6") {
+                div(class="card bg-base-200 shadow-xl") {
+                    div(class="card-body") {
+                        h2(class="card-title text-primary") { "Memory" }
+                        div(class="chart-container") {
+                            Chart(spec=memory_chart, backend=ChartBackend::Svg)
+                        }
+                    }
+                }
+                div(class="card bg-base-200 shadow-xl") {
+                    div(class="card-body") {
+                        h2(class="card-title text-primary") { "CPU" }
+                        div(class="chart-container") {
+                            Chart(spec=cpu_chart, backend=ChartBackend::Svg)
+                        }
+                    }
+                }
+            }
+            MetricsHistoryCard {}
+            div(class="card bg-base-200 shadow-xl") {
+                div(class="card-body") {
+                    h2(class="card-title text-primary") { "Processes" }
+                    table(class="table table-sm") {
+                        thead {
+                            tr {
+                                th { "PID" }
+                                th { "Name" }
+                                th { "RSS" }
+                                th { "CPU time" }
+                                th { "Threads" }
+                                th { "FDs" }
+                            }
+                        }
+                        tbody {
+                            Indexed(
+                                list=move || {
+                                    history.with(|h| h.last().map(|s| s.process.processes.clone()).unwrap_or_default())
+                                },
+                                view=|p| view! {
+                                    tr {
+                                        td(class="font-mono") { (p.pid) }
+                                        td { (p.name) }
+                                        td { (sysinfo::format_bytes(p.rss_bytes)) }
+                                        td { (format!("{:.1} s", p.cpu_time_ms as f64 / 1000.0)) }
+                                        td { (p.threads) }
+                                        td { (p.open_fds.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string())) }
+                                    }
+                                },
+                            )
+                        }
+                    }
                 }
             }
         }
@@ -168,18 +1013,94 @@ fn ChartDemo() -> View {
 // Settings & State Components
 // ============================================================================
 
+/// Settings changes that clash with changes made elsewhere.
+#[derive(Debug, Clone, PartialEq)]
+struct SettingsConflict {
+    /// The config as it is now.
+    current: Versioned,
+    /// The changes that weren't saved, as a merge patch.
+    patch: serde_json::Value,
+    /// Whether the backend still holds the changes, waiting to write them.
+    held: bool,
+}
+
 /// Settings card component for config interaction.
-/// Allows editing username, dark mode, notifications, and saving to disk.
+///
+/// The form is built from the config's JSON Schema. Saving sends only the
+/// changed settings, as a merge patch against the revision that was loaded;
+/// if the file changed since, the card offers to reload it or overwrite it.
 #[component]
 fn SettingsCard() -> View {
     let app_state = use_context::<AppState>();
 
-    let username = create_signal(String::new());
-    let dark_mode = create_signal(false);
-    let notifications = create_signal(true);
+    // The form's fields, read from the config's JSON Schema
+    let fields = create_signal(Vec::<schema::Field>::new());
+    // The config being edited, as JSON
+    let values = create_signal(serde_json::Value::Null);
+    // Config as last loaded or saved; only the fields that differ from it are sent
+    let loaded = create_signal(AppConfig::default());
+    // Revision of the loaded config; saving fails if the file has moved on
+    let revision = create_signal(String::new());
+    // Set when the config changed elsewhere in a way that clashes with this
+    // card's changes
+    let conflict = create_signal(None::<SettingsConflict>);
     let config_path = create_signal(String::from("Loading..."));
+    let app_paths = create_signal(None::<Paths>);
+    let cache_usage = create_signal(None::<CacheUsage>);
     let save_status = create_signal(String::new());
 
+    let apply = move |versioned: Versioned| {
+        let config = versioned.config;
+        values.set(serde_json::to_value(&config).unwrap_or_default());
+        app_state.memory_warning_mb.set(config.memory_warning_mb);
+        app_state.username.set(config.username.clone());
+        loaded.set(config);
+        revision.set(versioned.revision);
+    };
+
+    // The loaded config with the form's edits applied
+    let edited = move || {
+        serde_json::from_value::<AppConfig>(values.get_clone())
+            .unwrap_or_else(|_| loaded.get_clone())
+    };
+
+    // Shows how full the cache is; the quota may have just evicted entries
+    let refresh_cache_usage = move || {
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Ok(result) = wasm_bindgen_futures::JsFuture::from(get_cache_usage()).await
+                && let Some(json) = result.as_string()
+            {
+                cache_usage.set(serde_json::from_str(&json).ok().flatten());
+            }
+        });
+    };
+
+    // Show changes made outside the app, e.g. with the CLI, unless that would
+    // throw away unsaved edits; saving those then reports a conflict
+    create_effect(move || {
+        if let Some(external) = app_state.external_config.get_clone() {
+            untrack(|| {
+                if edited() == loaded.get_clone() {
+                    apply(external);
+                }
+            });
+        }
+    });
+
+    // Changes already saved from this card that the backend couldn't write
+    // because the file changed the same settings
+    create_effect(move || {
+        if let Some((current, changes)) = app_state.config_conflict.get_clone() {
+            untrack(|| {
+                conflict.set(Some(SettingsConflict {
+                    current,
+                    patch: changes,
+                    held: true,
+                }))
+            });
+        }
+    });
+
     // Load config on mount
     on_mount(move || {
         wasm_bindgen_futures::spawn_local(async move {
@@ -192,99 +1113,191 @@ fn SettingsCard() -> View {
                 }
                 Err(_) => config_path.set("Error getting path".to_string()),
             }
+            if let Ok(result) = wasm_bindgen_futures::JsFuture::from(get_paths()).await
+                && let Some(json) = result.as_string()
+            {
+                app_paths.set(serde_json::from_str::<Option<Paths>>(&json).ok().flatten());
+            }
+            match wasm_bindgen_futures::JsFuture::from(get_config_schema()).await {
+                Ok(result) => {
+                    if let Some(json) = result.as_string()
+                        && let Ok(config_schema) = serde_json::from_str(&json)
+                    {
+                        fields.set(schema::fields(&config_schema));
+                    }
+                }
+                Err(e) => logging::error(
+                    "Error loading config schema",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+
+            refresh_cache_usage();
 
             // Load config
             match wasm_bindgen_futures::JsFuture::from(load_config()).await {
                 Ok(result) => {
-                    if let Some(json) = result.as_string() {
-                        if let Ok(config) = serde_json::from_str::<AppConfig>(&json) {
-                            username.set(config.username.clone());
-                            dark_mode.set(config.dark_mode);
-                            notifications.set(config.notifications_enabled);
-                            app_state.username.set(config.username);
-                        }
+                    if let Some(json) = result.as_string()
+                        && let Ok(versioned) = serde_json::from_str::<Versioned>(&json)
+                    {
+                        apply(versioned);
                     }
                 }
-                Err(e) => log(&format!("Error loading config: {:?}", e)),
+                Err(e) => logging::error(
+                    "Error loading config",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
             }
         });
     });
 
-    let on_save = move |_| {
-        let config = AppConfig {
-            username: username.get_clone(),
-            dark_mode: dark_mode.get(),
-            notifications_enabled: notifications.get(),
-            auto_save_interval: 300,
-            theme: "system".to_string(),
-        };
+    let show_status = move |status: &str| {
+        save_status.set(status.to_string());
+        // Clear status after 2 seconds
+        gloo_timers::callback::Timeout::new(2000, move || {
+            save_status.set(String::new());
+        })
+        .forget();
+    };
 
-        // Update global state
-        app_state.username.set(config.username.clone());
+    // Sends the changed settings as a merge patch, so settings changed
+    // elsewhere that this card didn't touch are kept
+    let send = move |patch: serde_json::Value, expected: String, done: &'static str| {
+        let patch_json = patch.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(patch_config(&patch_json, &expected)).await {
+                Ok(result) => {
+                    let outcome = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<SaveOutcome>(&json).ok());
+                    match outcome {
+                        Some(SaveOutcome::Saved {
+                            revision: saved,
+                            config,
+                        }) => {
+                            apply(Versioned {
+                                config,
+                                revision: saved,
+                            });
+                            conflict.set(None);
+                            refresh_cache_usage();
+                            show_status(done);
+                        }
+                        Some(SaveOutcome::Conflict {
+                            revision: current,
+                            config: theirs,
+                        }) => {
+                            let current = Versioned {
+                                config: theirs,
+                                revision: current,
+                            };
+                            conflict.set(Some(SettingsConflict {
+                                current,
+                                patch,
+                                held: false,
+                            }));
+                        }
+                        Some(SaveOutcome::Failed { error }) => {
+                            logging::error("Error saving config", json!({ "error": error }));
+                            show_status("Save failed");
+                        }
+                        None => show_status("Save failed"),
+                    }
+                }
+                Err(e) => {
+                    logging::error(
+                        "Error saving config",
+                        json!({ "error": format!("{:?}", e) }),
+                    );
+                    show_status("Error saving");
+                }
+            }
+        });
+    };
 
-        let json = serde_json::to_string(&config).unwrap_or_default();
+    let on_save = move |_| match config::merge_patch(&loaded.get_clone(), &edited()) {
+        Some(patch) => send(patch, revision.get_clone(), "Saved!"),
+        None => show_status("No changes"),
+    };
 
+    let on_clear_cache = move |_| {
         wasm_bindgen_futures::spawn_local(async move {
-            match wasm_bindgen_futures::JsFuture::from(save_config(&json)).await {
+            match wasm_bindgen_futures::JsFuture::from(clear_cache()).await {
                 Ok(result) => {
-                    if result.as_bool() == Some(true) {
-                        save_status.set("Saved!".to_string());
-                    } else {
-                        save_status.set("Save failed".to_string());
+                    if let Some(json) = result.as_string() {
+                        cache_usage.set(serde_json::from_str(&json).ok().flatten());
                     }
+                    show_status("Cache cleared");
+                }
+                Err(e) => {
+                    logging::error(
+                        "Error clearing cache",
+                        json!({ "error": format!("{:?}", e) }),
+                    );
+                    show_status("Clearing cache failed");
                 }
-                Err(_) => save_status.set("Error saving".to_string()),
             }
-
-            // Clear status after 2 seconds
-            gloo_timers::callback::Timeout::new(2000, move || {
-                save_status.set(String::new());
-            })
-            .forget();
         });
     };
 
+    // Discard this card's edits and show the config as it is now. Changes
+    // the backend still holds are dropped by a save against the file's
+    // revision.
+    let on_reload = move |_| {
+        if let Some(SettingsConflict { current, held, .. }) = conflict.get_clone() {
+            if held {
+                send(json!({}), current.revision.clone(), "Reloaded");
+            }
+            apply(current);
+        }
+        conflict.set(None);
+    };
+
+    // Apply this card's changes on top of the changes made elsewhere
+    let on_overwrite = move |_| {
+        if let Some(SettingsConflict { current, patch, .. }) = conflict.get_clone() {
+            conflict.set(None);
+            send(patch, current.revision, "Saved!");
+        }
+    };
+
     view! {
         div(class="card bg-base-200 shadow-xl") {
             div(class="card-body") {
                 h2(class="card-title text-primary") { "Settings" }
 
-                // Username input
-                div(class="form-control") {
-                    label(class="label") {
-                        span(class="label-text") { "Username" }
-                    }
-                    input(
-                        r#type="text",
-                        class="input input-bordered",
-                        placeholder="Enter username",
-                        bind:value=username,
-                    )
-                }
-
-                // Dark mode toggle
-                div(class="form-control") {
-                    label(class="label cursor-pointer") {
-                        span(class="label-text") { "Dark Mode" }
-                        input(
-                            r#type="checkbox",
-                            class="toggle toggle-primary",
-                            bind:checked=dark_mode,
-                        )
+                // One input per setting, from the config's schema
+                schema::SchemaForm(fields=*fields, values=values)
+
+                // Cache usage and clearing
+                div(class="flex items-center gap-2 mt-2") {
+                    span(class="text-sm text-base-content/70") {
+                        (match cache_usage.get() {
+                            Some(usage) => format!(
+                                "Cache: {} used in {} entries",
+                                sysinfo::format_bytes(usage.bytes),
+                                usage.entries
+                            ),
+                            None => String::new(),
+                        })
                     }
+                    button(class="btn btn-sm", on:click=on_clear_cache) { "Clear cache" }
                 }
 
-                // Notifications checkbox
-                div(class="form-control") {
-                    label(class="label cursor-pointer") {
-                        span(class="label-text") { "Enable Notifications" }
-                        input(
-                            r#type="checkbox",
-                            class="checkbox checkbox-primary",
-                            bind:checked=notifications,
-                        )
+                // Shown when a save was rejected because the config changed elsewhere
+                (if conflict.with(|c| c.is_some()) {
+                    view! {
+                        div(role="alert", class="alert alert-warning mt-4") {
+                            span { "Settings were changed elsewhere since they were loaded." }
+                            div(class="flex gap-2") {
+                                button(class="btn btn-sm", on:click=on_reload) { "Reload" }
+                                button(class="btn btn-sm btn-warning", on:click=on_overwrite) { "Overwrite" }
+                            }
+                        }
                     }
-                }
+                } else {
+                    view! {}
+                })
 
                 // Save button
                 div(class="card-actions justify-start mt-4") {
@@ -296,12 +1309,25 @@ fn SettingsCard() -> View {
                     } else {
                         view! {}
                     })
+                    support::SupportBundleButton {}
                 }
 
-                // Config path
+                // Config path, and where all files are kept unless installed normally
                 div(class="text-xs text-base-content/50 mt-4") {
                     "Config: " (config_path.get_clone())
                 }
+                (match app_paths.get_clone() {
+                    Some(dirs) => {
+                        let root = dirs.root.clone().unwrap_or_default();
+                        view! {
+                            div(class="text-xs text-base-content/50 mt-1 flex items-center gap-2") {
+                                span(class="badge badge-outline badge-sm") { (dirs.mode_label()) }
+                                (root)
+                            }
+                        }
+                    }
+                    None => view! {},
+                })
             }
         }
     }
@@ -453,6 +1479,8 @@ fn QuickActionsCard() -> View {
 /// Uses daisyUI navbar with start/center/end sections.
 #[component]
 fn TopBar() -> View {
+    let app_state = use_context::<AppState>();
+
     view! {
         div(class="navbar bg-base-200 shadow-lg") {
             div(class="navbar-start") {
@@ -460,7 +1488,7 @@ fn TopBar() -> View {
             }
             div(class="navbar-center hidden lg:flex") {
                 ul(class="menu menu-horizontal px-1") {
-                    li { a { "Home" } }
+                    li { a(on:click=move |_| app_state.navigate(Route::Home)) { "Home" } }
                     li { a { "About" } }
                 }
             }
@@ -474,15 +1502,31 @@ fn TopBar() -> View {
 /// Left sidebar component with dynamic width.
 #[component(inline_props)]
 fn LeftSidebar(width: Signal<f64>) -> View {
+    let app_state = use_context::<AppState>();
     let style = move || format!("width: {}px", width.get());
+    let nav_link = move |route: Route| {
+        let class = move || {
+            if app_state.route.get() == route {
+                "menu-active"
+            } else {
+                ""
+            }
+        };
+        view! {
+            li { a(class=class, on:click=move |_| app_state.navigate(route)) { (route.title()) } }
+        }
+    };
 
     view! {
         aside(class="sidebar bg-base-200", style=style) {
             ul(class="menu p-4") {
                 li(class="menu-title") { "Navigation" }
-                li { a { "Dashboard" } }
+                (nav_link(Route::Home))
                 li { a { "Projects" } }
                 li { a { "Settings" } }
+                (nav_link(Route::Diagnostics))
+                (nav_link(Route::Performance))
+                (nav_link(Route::Logs))
             }
         }
     }
@@ -532,10 +1576,10 @@ fn ResizeHandle(
         drag_start_x.set(e.client_x() as f64);
         start_width.set(panel_width.get());
 
-        if let Some(target) = e.target() {
-            if let Ok(element) = target.dyn_into::<Element>() {
-                let _ = element.set_pointer_capture(e.pointer_id());
-            }
+        if let Some(target) = e.target()
+            && let Ok(element) = target.dyn_into::<Element>()
+        {
+            let _ = element.set_pointer_capture(e.pointer_id());
         }
     };
 
@@ -563,10 +1607,10 @@ fn ResizeHandle(
         is_dragging.set(false);
         is_any_dragging.set(false);
 
-        if let Some(target) = e.target() {
-            if let Ok(element) = target.dyn_into::<Element>() {
-                let _ = element.release_pointer_capture(e.pointer_id());
-            }
+        if let Some(target) = e.target()
+            && let Ok(element) = target.dyn_into::<Element>()
+        {
+            let _ = element.release_pointer_capture(e.pointer_id());
         }
     };
 
@@ -580,26 +1624,109 @@ fn ResizeHandle(
     }
 }
 
-/// Main content area wrapper.
+/// Main content area showing the page for the current route.
 #[component]
 fn MainContent() -> View {
+    let app_state = use_context::<AppState>();
+
     view! {
         main(class="main-content p-6") {
-            div(class="flex flex-col gap-6") {
-                // State and Config cards in a grid
-                div(class="grid grid-cols-1 lg:grid-cols-3 gap-4") {
-                    SettingsCard {}
-                    GlobalStateCard {}
-                    QuickActionsCard {}
+            (match app_state.route.get() {
+                Route::Home => view! { HomePage {} },
+                Route::Diagnostics => view! { DiagnosticsPage {} },
+                Route::Performance => view! { PerformancePage {} },
+                Route::Logs => view! { logview::LogsPage {} },
+            })
+        }
+    }
+}
+
+/// Card listing recently exported charts, with a button to reveal each file.
+///
+/// Loads the backend's list on mount; exports made afterwards are added by
+/// the chart export menu through [`AppState::remember_export`].
+#[component]
+fn RecentExportsCard() -> View {
+    let app_state = use_context::<AppState>();
+
+    on_mount(move || {
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(get_recent_exports()).await {
+                Ok(result) => {
+                    if let Some(entries) = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<Vec<RecentExport>>(&json).ok())
+                    {
+                        app_state.recent_exports.set(entries);
+                    }
+                }
+                Err(e) => logging::error(
+                    "Error loading recent exports",
+                    json!({ "error": format!("{:?}", e) }),
+                ),
+            }
+        });
+    });
+
+    view! {
+        div(class="card bg-base-200 shadow-xl") {
+            div(class="card-body") {
+                h2(class="card-title text-primary") { "Recent Exports" }
+                (if app_state.recent_exports.with(|e| e.is_empty()) {
+                    view! {
+                        p(class="text-sm text-base-content/70") {
+                            "Use the ⋯ menu on a chart to export it as PNG, SVG or CSV."
+                        }
+                    }
+                } else {
+                    view! {}
+                })
+                ul(class="flex flex-col gap-1") {
+                    Indexed(
+                        list=app_state.recent_exports,
+                        view=|entry| {
+                            let path = entry.path.clone();
+                            let (format, title) = (entry.format.as_str(), entry.path.clone());
+                            let (name, size) = (entry.file_name().to_string(), sysinfo::format_bytes(entry.bytes));
+                            view! {
+                                li(class="flex items-center gap-2 text-sm") {
+                                    span(class="badge badge-outline badge-sm uppercase") { (format) }
+                                    span(class="font-mono truncate flex-1", title=title) { (name) }
+                                    span(class="text-base-content/60") { (size) }
+                                    button(
+                                        class="btn btn-ghost btn-xs",
+                                        on:click=move |_| { let _ = show_in_folder(&path); },
+                                    ) { "Show in folder" }
+                                }
+                            }
+                        },
+                    )
                 }
-                Stuff {}
-                BackendDemo {}
-                ChartDemo {}
             }
         }
     }
 }
 
+/// Home page with the demo cards.
+#[component]
+fn HomePage() -> View {
+    view! {
+        div(class="flex flex-col gap-6") {
+            // State and Config cards in a grid
+            div(class="grid grid-cols-1 lg:grid-cols-3 gap-4") {
+                SettingsCard {}
+                GlobalStateCard {}
+                QuickActionsCard {}
+            }
+            Stuff {}
+            BackendDemo {}
+            StorageCard {}
+            ChartDemo {}
+            RecentExportsCard {}
+        }
+    }
+}
+
 /// Main area component that orchestrates the resizable sidebar layout.
 #[component]
 fn MainArea() -> View {
@@ -667,6 +1794,11 @@ fn App() -> View {
     // Create and provide global app state
     let app_state = AppState::new();
     provide_context(app_state);
+    start_metrics_monitor(app_state);
+    start_config_watch(app_state);
+    instance::start(app_state);
+    // Tag log entries with the page they were logged on
+    create_effect(move || logging::set_route(app_state.route.get().title()));
 
     view! {
         div(class="app-layout") {
@@ -679,6 +1811,7 @@ fn App() -> View {
 
 #[wasm_bindgen(start)]
 pub fn run() {
-    console_error_panic_hook::set_once();
+    crash::install();
+    logging::init();
     sycamore::render(App);
 }
@@ -0,0 +1,274 @@
+//! Logging facade that forwards renderer logs into the backend log file.
+//!
+//! Entries are echoed to the DevTools console and buffered; the buffer is
+//! sent to the main process in batches, which tags each entry with the
+//! window it came from and hands it to the backend log sink. Errors are sent
+//! right away, and whatever is still buffered is sent when the page unloads.
+//!
+//! ```ignore
+//! logging::error("Error loading config", json!({ "error": format!("{:?}", e) }));
+//! ```
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = sendLogs)]
+    fn send_logs(json: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+}
+
+/// Number of buffered entries that triggers a send.
+pub const MAX_BATCH: usize = 50;
+/// How long entries are buffered before being sent.
+const FLUSH_DELAY_MS: u32 = 1000;
+
+/// Severity of a log entry, matching the backend's levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// One log entry as sent to the backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub timestamp_ms: u64,
+    pub level: Level,
+    /// Part of the UI the entry is about, e.g. "settings".
+    pub target: String,
+    pub message: String,
+    /// Route shown when the entry was logged.
+    pub route: String,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+}
+
+impl LogEntry {
+    /// Formats the entry for the DevTools console.
+    pub fn console_line(&self) -> String {
+        let mut line = format!("[{}] {}", self.target, self.message);
+        // Sorted so the line doesn't depend on the order fields were added in
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields.sort_by_key(|(key, _)| *key);
+        for (key, value) in fields {
+            match value {
+                Value::String(s) => line += &format!(" {}={}", key, s),
+                other => line += &format!(" {}={}", key, other),
+            }
+        }
+        line
+    }
+}
+
+/// Entries waiting to be sent.
+#[derive(Debug, Default)]
+pub struct LogBuffer {
+    entries: Vec<LogEntry>,
+}
+
+impl LogBuffer {
+    /// Adds an entry and returns true if the buffer should be sent now.
+    pub fn push(&mut self, entry: LogEntry) -> bool {
+        let urgent = entry.level == Level::Error;
+        self.entries.push(entry);
+        urgent || self.entries.len() >= MAX_BATCH
+    }
+
+    /// Removes and returns all buffered entries.
+    pub fn take(&mut self) -> Vec<LogEntry> {
+        std::mem::take(&mut self.entries)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct LogState {
+    buffer: LogBuffer,
+    route: String,
+    flush_timer: Option<gloo_timers::callback::Timeout>,
+}
+
+thread_local! {
+    static STATE: RefCell<LogState> = RefCell::new(LogState::default());
+}
+
+/// Sends all buffered entries to the backend.
+///
+/// Does nothing if the state is in use, which can only happen when called
+/// from the panic hook after a panic inside this module.
+pub fn flush() {
+    let entries = STATE.with(|state| {
+        let Ok(mut state) = state.try_borrow_mut() else {
+            return Vec::new();
+        };
+        state.flush_timer = None;
+        state.buffer.take()
+    });
+    if entries.is_empty() {
+        return;
+    }
+    if let Ok(json) = serde_json::to_string(&entries) {
+        send_logs(&json);
+    }
+}
+
+/// Sends buffered entries when the page is hidden or unloaded.
+///
+/// Call once at startup.
+pub fn init() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let on_unload = Closure::<dyn FnMut()>::new(flush);
+    let _ = window.add_event_listener_with_callback("pagehide", on_unload.as_ref().unchecked_ref());
+    on_unload.forget();
+}
+
+/// Records the route shown, which is attached to subsequent entries.
+pub fn set_route(route: impl Into<String>) {
+    STATE.with(|state| state.borrow_mut().route = route.into());
+}
+
+/// Returns the route last passed to [`set_route`].
+pub fn current_route() -> String {
+    STATE.with(|state| {
+        state
+            .try_borrow()
+            .map(|state| state.route.clone())
+            .unwrap_or_default()
+    })
+}
+
+/// Logs an entry with structured `fields` (a JSON object, or `Value::Null` for none).
+pub fn log(level: Level, target: &str, message: impl Into<String>, fields: Value) {
+    let fields = match fields {
+        Value::Object(map) => map,
+        Value::Null => Map::new(),
+        other => Map::from_iter([("value".to_string(), other)]),
+    };
+    // Not borrowed directly: an entry logged while the state is borrowed,
+    // e.g. from a panic hook, goes out without a route rather than panic
+    let entry = LogEntry {
+        timestamp_ms: js_sys::Date::now() as u64,
+        level,
+        target: target.to_string(),
+        message: message.into(),
+        route: current_route(),
+        fields,
+    };
+
+    let line = entry.console_line();
+    match level {
+        Level::Trace | Level::Debug => console_debug(&line),
+        Level::Info => console_info(&line),
+        Level::Warn => console_warn(&line),
+        Level::Error => console_error(&line),
+    }
+
+    let send_now = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let send_now = state.buffer.push(entry);
+        if !send_now && state.flush_timer.is_none() {
+            state.flush_timer = Some(gloo_timers::callback::Timeout::new(FLUSH_DELAY_MS, flush));
+        }
+        send_now
+    });
+    if send_now {
+        flush();
+    }
+}
+
+/// Logs a debug entry for the "ui" target.
+pub fn debug(message: impl Into<String>, fields: Value) {
+    log(Level::Debug, "ui", message, fields);
+}
+
+/// Logs an info entry for the "ui" target.
+pub fn info(message: impl Into<String>, fields: Value) {
+    log(Level::Info, "ui", message, fields);
+}
+
+/// Logs a warning for the "ui" target.
+pub fn warn(message: impl Into<String>, fields: Value) {
+    log(Level::Warn, "ui", message, fields);
+}
+
+/// Logs an error for the "ui" target; errors are sent to the backend immediately.
+pub fn error(message: impl Into<String>, fields: Value) {
+    log(Level::Error, "ui", message, fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(level: Level) -> LogEntry {
+        LogEntry {
+            timestamp_ms: 1,
+            level,
+            target: "ui".to_string(),
+            message: "Error loading config".to_string(),
+            route: "Dashboard".to_string(),
+            fields: json!({ "error": "timeout", "attempt": 2 })
+                .as_object()
+                .cloned()
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_entry_serialization() {
+        let value = serde_json::to_value(entry(Level::Warn)).unwrap();
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(value["route"], "Dashboard");
+        assert_eq!(value["fields"]["attempt"], 2);
+
+        let mut bare = entry(Level::Info);
+        bare.fields.clear();
+        assert!(serde_json::to_value(bare).unwrap().get("fields").is_none());
+    }
+
+    #[test]
+    fn test_console_line() {
+        assert_eq!(
+            entry(Level::Info).console_line(),
+            "[ui] Error loading config attempt=2 error=timeout"
+        );
+    }
+
+    #[test]
+    fn test_buffer_sends_on_error_or_full_batch() {
+        let mut buffer = LogBuffer::default();
+        for _ in 0..MAX_BATCH - 1 {
+            assert!(!buffer.push(entry(Level::Info)));
+        }
+        assert!(buffer.push(entry(Level::Debug)));
+        assert_eq!(buffer.take().len(), MAX_BATCH);
+        assert!(buffer.is_empty());
+
+        assert!(buffer.push(entry(Level::Error)));
+    }
+}
@@ -0,0 +1,534 @@
+//! Log viewer page: browsing, filtering and live-tailing the backend log.
+//!
+//! Older records are fetched from the backend a page at a time, newest
+//! first. New records arrive through a backend subscription that lasts as
+//! long as the page is shown; they are matched against the same filter
+//! here and added to the top of the list while live tail is on.
+
+use crate::logging;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sycamore::prelude::*;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = readLogs)]
+    fn read_logs(query_json: &str) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = onLogRecord)]
+    fn on_log_record(callback: &Closure<dyn FnMut(String)>) -> js_sys::Function;
+}
+
+/// Records requested per page.
+pub const PAGE_SIZE: usize = 200;
+
+/// Most records kept on screen; live tail drops the oldest beyond this.
+pub const MAX_SHOWN: usize = 2000;
+
+/// Time ranges offered by the viewer, as (label, milliseconds back from now).
+pub const TIME_RANGES: [(&str, Option<u64>); 5] = [
+    ("15m", Some(15 * 60_000)),
+    ("1h", Some(3_600_000)),
+    ("24h", Some(86_400_000)),
+    ("7d", Some(7 * 86_400_000)),
+    ("All", None),
+];
+
+const LEVELS: [&str; 5] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR"];
+
+/// Returns the severity rank of a level name, or `None` for unknown names.
+fn level_rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| l.eq_ignore_ascii_case(level))
+}
+
+/// One line of the backend log.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LogRecord {
+    pub timestamp_ms: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+impl LogRecord {
+    /// DaisyUI badge classes for the record's level.
+    pub fn level_badge(&self) -> &'static str {
+        match self.level.as_str() {
+            "ERROR" => "badge badge-error badge-sm",
+            "WARN" => "badge badge-warning badge-sm",
+            "INFO" => "badge badge-info badge-sm",
+            _ => "badge badge-ghost badge-sm",
+        }
+    }
+}
+
+/// Which records to show, as understood by the backend. Empty fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LogFilter {
+    pub min_level: Option<String>,
+    pub module: Option<String>,
+    pub text: Option<String>,
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+}
+
+impl LogFilter {
+    /// Builds a filter from the viewer's inputs, treating blank inputs as unset.
+    pub fn from_inputs(min_level: &str, module: &str, text: &str, from_ms: Option<u64>) -> Self {
+        let non_empty = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        Self {
+            min_level: non_empty(min_level),
+            module: non_empty(module),
+            text: non_empty(text),
+            from_ms,
+            to_ms: None,
+        }
+    }
+
+    /// Returns whether `record` passes the filter; the same rules the backend applies.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(min) = self.min_level.as_deref().and_then(level_rank)
+            && level_rank(&record.level).is_some_and(|rank| rank < min)
+        {
+            return false;
+        }
+        if let Some(module) = self.module.as_deref().filter(|m| !m.is_empty())
+            && !(record.target == module
+                || record
+                    .target
+                    .strip_prefix(module)
+                    .is_some_and(|rest| rest.starts_with("::")))
+        {
+            return false;
+        }
+        if self.from_ms.is_some_and(|from| record.timestamp_ms < from)
+            || self.to_ms.is_some_and(|to| record.timestamp_ms > to)
+        {
+            return false;
+        }
+        match self.text.as_deref().filter(|t| !t.is_empty()) {
+            Some(text) => {
+                let text = text.to_lowercase();
+                record.message.to_lowercase().contains(&text)
+                    || record
+                        .fields
+                        .values()
+                        .any(|value| field_text(value).to_lowercase().contains(&text))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Formats a field value for display, without quotes around strings.
+pub fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Opaque position in the log files returned by the backend for paging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    file: u32,
+    file_id: u64,
+    #[serde(default)]
+    file_key: u64,
+    offset: u64,
+}
+
+/// A page request sent to the backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogQuery {
+    pub filter: LogFilter,
+    pub before: Option<Cursor>,
+    pub limit: usize,
+}
+
+/// Records newest first, and where to continue for older ones.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct LogPage {
+    pub records: Vec<LogRecord>,
+    pub next: Option<Cursor>,
+}
+
+/// Adds a live record to the top of the list, keeping at most [`MAX_SHOWN`].
+///
+/// Returns false if older records had to be dropped, after which paging
+/// from the old cursor would leave a gap.
+pub fn push_live(records: &mut Vec<LogRecord>, record: LogRecord) -> bool {
+    records.insert(0, record);
+    if records.len() > MAX_SHOWN {
+        records.truncate(MAX_SHOWN);
+        return false;
+    }
+    true
+}
+
+/// Formats a timestamp as local `YYYY-MM-DD HH:MM:SS.mmm`.
+fn format_time(timestamp_ms: u64) -> String {
+    let date = js_sys::Date::new(&JsValue::from_f64(timestamp_ms as f64));
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date(),
+        date.get_hours(),
+        date.get_minutes(),
+        date.get_seconds(),
+        date.get_milliseconds()
+    )
+}
+
+/// Page listing log records with filters, live tail and a detail panel.
+#[component]
+pub(crate) fn LogsPage() -> View {
+    let min_level = create_signal(String::new());
+    let module = create_signal(String::new());
+    let text = create_signal(String::new());
+    let range_ms = create_signal(TIME_RANGES[1].1);
+    let live = create_signal(true);
+
+    let filter = create_signal(LogFilter::default());
+    let records = create_signal(Vec::<LogRecord>::new());
+    let next = create_signal(None::<Cursor>);
+    let selected = create_signal(None::<LogRecord>);
+    let loading = create_signal(false);
+    let error = create_signal(None::<String>);
+    // Bumped when the filter changes so responses to older queries are ignored
+    let generation = create_signal(0u32);
+
+    let load = move |before: Option<Cursor>| {
+        let query = LogQuery {
+            filter: filter.get_clone_untracked(),
+            before,
+            limit: PAGE_SIZE,
+        };
+        let json = serde_json::to_string(&query).unwrap_or_default();
+        let request = generation.get_untracked();
+        loading.set(true);
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = wasm_bindgen_futures::JsFuture::from(read_logs(&json)).await;
+            if generation.get_untracked() != request {
+                return;
+            }
+            loading.set(false);
+            match result {
+                Ok(result) => {
+                    match result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<LogPage>(&json).ok())
+                    {
+                        Some(page) => {
+                            records.update(|r| r.extend(page.records));
+                            next.set(page.next);
+                            error.set(None);
+                        }
+                        None => error.set(Some("Unexpected response from the backend".to_string())),
+                    }
+                }
+                Err(e) => {
+                    error.set(Some("Could not read the log files".to_string()));
+                    logging::error("Error reading logs", json!({ "error": format!("{:?}", e) }));
+                }
+            }
+        });
+    };
+
+    // Start over from the newest record whenever the filter changes
+    create_effect(move || {
+        let from_ms = range_ms
+            .get()
+            .map(|ms| (js_sys::Date::now() as u64).saturating_sub(ms));
+        filter.set(LogFilter::from_inputs(
+            &min_level.get_clone(),
+            &module.get_clone(),
+            &text.get_clone(),
+            from_ms,
+        ));
+        generation.set(generation.get_untracked().wrapping_add(1));
+        records.set(Vec::new());
+        next.set(None);
+        selected.set(None);
+        load(None);
+    });
+
+    let callback = Closure::<dyn FnMut(String)>::new(move |json: String| {
+        if !live.get_untracked() {
+            return;
+        }
+        let Ok(record) = serde_json::from_str::<LogRecord>(&json) else {
+            return;
+        };
+        if filter.with_untracked(|f| f.matches(&record)) {
+            let mut complete = true;
+            records.update(|r| complete = push_live(r, record));
+            if !complete {
+                next.set(None);
+            }
+        }
+    });
+    let unsubscribe = on_log_record(&callback);
+    on_cleanup(move || {
+        let _ = unsubscribe.call0(&JsValue::NULL);
+        drop(callback);
+    });
+
+    // Inputs apply on Enter or blur rather than on every keystroke
+    let on_module_change = move |e: web_sys::Event| {
+        if let Some(input) = e
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        {
+            module.set(input.value());
+        }
+    };
+    let on_text_change = move |e: web_sys::Event| {
+        if let Some(input) = e
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+        {
+            text.set(input.value());
+        }
+    };
+
+    let status = move || {
+        let count = records.with(|r| r.len());
+        if loading.get() {
+            "Loading...".to_string()
+        } else {
+            format!("{} entries", count)
+        }
+    };
+
+    view! {
+        div(class="space-y-4") {
+            h1(class="text-2xl font-bold") { "Logs" }
+
+            div(class="card bg-base-200 shadow-xl") {
+                div(class="card-body gap-3") {
+                    div(class="flex flex-wrap items-center gap-2") {
+                        select(class="select select-bordered select-sm", bind:value=min_level) {
+                            option(value="") { "All levels" }
+                            option(value="debug") { "Debug and above" }
+                            option(value="info") { "Info and above" }
+                            option(value="warn") { "Warnings and errors" }
+                            option(value="error") { "Errors only" }
+                        }
+                        input(
+                            r#type="text",
+                            class="input input-bordered input-sm w-48",
+                            placeholder="Module, e.g. backend::config",
+                            on:change=on_module_change,
+                        )
+                        input(
+                            r#type="search",
+                            class="input input-bordered input-sm w-48",
+                            placeholder="Search text",
+                            on:change=on_text_change,
+                        )
+                        div(class="join") {
+                            Indexed(
+                                list=TIME_RANGES.to_vec(),
+                                view=move |(label, ms)| view! {
+                                    button(
+                                        class=move || if range_ms.get() == ms {
+                                            "btn btn-sm join-item btn-primary"
+                                        } else {
+                                            "btn btn-sm join-item"
+                                        },
+                                        on:click=move |_| range_ms.set(ms),
+                                    ) { (label) }
+                                },
+                            )
+                        }
+                        label(class="label cursor-pointer gap-2") {
+                            span(class="label-text") { "Live" }
+                            input(
+                                r#type="checkbox",
+                                class="toggle toggle-primary toggle-sm",
+                                bind:checked=live,
+                            )
+                        }
+                        span(class="text-sm text-base-content/70 ml-auto") { (status) }
+                    }
+
+                    (if let Some(message) = error.get_clone() {
+                        view! { div(class="alert alert-error") { (message) } }
+                    } else {
+                        view! {}
+                    })
+
+                    div(class="overflow-auto max-h-[60vh]") {
+                        table(class="table table-xs table-pin-rows") {
+                            thead {
+                                tr {
+                                    th { "Time" }
+                                    th { "Level" }
+                                    th { "Module" }
+                                    th { "Message" }
+                                }
+                            }
+                            tbody {
+                                Indexed(
+                                    list=records,
+                                    view=move |record| {
+                                        let time = format_time(record.timestamp_ms);
+                                        let badge = record.level_badge();
+                                        let level = record.level.clone();
+                                        let target = record.target.clone();
+                                        let message = record.message.clone();
+                                        let is_selected = {
+                                            let record = record.clone();
+                                            move || selected.with(|s| s.as_ref() == Some(&record))
+                                        };
+                                        view! {
+                                            tr(
+                                                class=move || if is_selected() {
+                                                    "hover cursor-pointer bg-base-300"
+                                                } else {
+                                                    "hover cursor-pointer"
+                                                },
+                                                on:click=move |_| selected.set(Some(record.clone())),
+                                            ) {
+                                                td(class="font-mono whitespace-nowrap") { (time) }
+                                                td { span(class=badge) { (level) } }
+                                                td(class="font-mono") { (target) }
+                                                td { (message) }
+                                            }
+                                        }
+                                    },
+                                )
+                            }
+                        }
+                    }
+
+                    div(class="flex justify-center") {
+                        (if next.with(|n| n.is_some()) {
+                            view! {
+                                button(
+                                    class="btn btn-sm",
+                                    disabled=loading.get(),
+                                    on:click=move |_| load(next.get_untracked()),
+                                ) { "Load older" }
+                            }
+                        } else {
+                            view! {}
+                        })
+                    }
+                }
+            }
+
+            (if let Some(record) = selected.get_clone() {
+                let title = format!("{} {} {}", format_time(record.timestamp_ms), record.level, record.target);
+                let message = record.message.clone();
+                let fields = record
+                    .fields
+                    .iter()
+                    .map(|(key, value)| (key.clone(), field_text(value)))
+                    .collect::<Vec<_>>();
+                let has_fields = !fields.is_empty();
+                view! {
+                    div(class="card bg-base-200 shadow-xl") {
+                        div(class="card-body") {
+                            div(class="flex items-center justify-between") {
+                                h2(class="card-title text-primary font-mono text-base") { (title) }
+                                button(class="btn btn-sm btn-ghost", on:click=move |_| selected.set(None)) { "Close" }
+                            }
+                            p(class="whitespace-pre-wrap") { (message) }
+                            (if has_fields {
+                                let fields = fields.clone();
+                                view! {
+                                    table(class="table table-sm") {
+                                        tbody {
+                                            Indexed(
+                                                list=fields,
+                                                view=|(key, value)| view! {
+                                                    tr {
+                                                        th(class="font-mono w-40") { (key) }
+                                                        td(class="font-mono whitespace-pre-wrap break-all") { (value) }
+                                                    }
+                                                },
+                                            )
+                                        }
+                                    }
+                                }
+                            } else {
+                                view! { p(class="text-sm text-base-content/70") { "No fields" } }
+                            })
+                        }
+                    }
+                }
+            } else {
+                view! {}
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp_ms: u64, level: &str, target: &str, message: &str) -> LogRecord {
+        LogRecord {
+            timestamp_ms,
+            level: level.to_string(),
+            target: target.to_string(),
+            message: message.to_string(),
+            fields: json!({ "path": "~/config.json" })
+                .as_object()
+                .cloned()
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_filter_from_inputs() {
+        let filter = LogFilter::from_inputs("warn", " ", " config ", Some(5));
+        assert_eq!(filter.min_level.as_deref(), Some("warn"));
+        assert_eq!(filter.module, None);
+        assert_eq!(filter.text.as_deref(), Some("config"));
+        assert_eq!(serde_json::to_value(&filter).unwrap()["from_ms"], json!(5));
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let warn = record(100, "WARN", "renderer::ui", "Save failed");
+        let info = record(100, "INFO", "renderer", "Loaded");
+
+        let filter = LogFilter::from_inputs("warn", "", "", None);
+        assert!(filter.matches(&warn));
+        assert!(!filter.matches(&info));
+
+        let filter = LogFilter::from_inputs("", "renderer", "CONFIG.JSON", Some(50));
+        assert!(filter.matches(&warn));
+        assert!(filter.matches(&info));
+        assert!(!LogFilter::from_inputs("", "render", "", None).matches(&warn));
+        assert!(!LogFilter::from_inputs("", "", "", Some(150)).matches(&warn));
+    }
+
+    #[test]
+    fn test_page_deserialization_and_push_live() {
+        let page: LogPage = serde_json::from_str(
+            r#"{"records": [{"timestamp_ms": 1, "level": "INFO", "target": "backend",
+                "message": "hi"}], "next": {"file": 1, "file_id": 5, "offset": 10}}"#,
+        )
+        .unwrap();
+        assert!(page.records[0].fields.is_empty());
+        assert!(page.next.is_some());
+
+        let mut records = vec![page.records[0].clone(); MAX_SHOWN - 1];
+        assert!(push_live(&mut records, record(2, "INFO", "backend", "new")));
+        assert!(!push_live(
+            &mut records,
+            record(3, "INFO", "backend", "newer")
+        ));
+        assert_eq!(records.len(), MAX_SHOWN);
+        assert_eq!(records[0].message, "newer");
+    }
+}
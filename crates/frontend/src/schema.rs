@@ -0,0 +1,427 @@
+//! Settings form built from the config's JSON Schema.
+//!
+//! The backend generates the schema from its `AppConfig`, so a new setting
+//! shows up in the Settings card without frontend changes. Strings become
+//! text inputs, booleans toggles, integers number inputs within the
+//! schema's bounds, and settings with a fixed set of values selects. Nested
+//! objects become sections; maps and other values the form can't edit are
+//! left out.
+
+use serde_json::{Map, Value};
+use sycamore::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// The input a setting is edited with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Control {
+    Text,
+    Toggle,
+    Number { min: Option<f64>, max: Option<f64> },
+    Select(Vec<String>),
+}
+
+impl Control {
+    /// Returns the control for a setting's schema, or `None` if the form
+    /// can't edit it.
+    fn for_schema(schema: &Value) -> Option<Self> {
+        if let Some(choices) = schema.get("enum").and_then(Value::as_array)
+            && let Some(choices) = choices
+                .iter()
+                .map(|choice| choice.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        {
+            return Some(Control::Select(choices));
+        }
+        let bound = |key| schema.get(key).and_then(Value::as_f64);
+        match schema_type(schema)? {
+            "string" => Some(Control::Text),
+            "boolean" => Some(Control::Toggle),
+            "integer" => Some(Control::Number {
+                min: bound("minimum"),
+                max: bound("maximum"),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Turns what was typed or chosen into the setting's value. Numbers are
+    /// rounded and kept within bounds; returns `None` for text that isn't one.
+    pub fn parse(&self, input: &str) -> Option<Value> {
+        match self {
+            Control::Text | Control::Select(_) => Some(Value::String(input.to_string())),
+            Control::Toggle => input.parse().ok().map(Value::Bool),
+            Control::Number { min, max } => {
+                let mut number = input.trim().parse::<f64>().ok()?.round();
+                if !number.is_finite() {
+                    return None;
+                }
+                if let Some(min) = min {
+                    number = number.max(*min);
+                }
+                if let Some(max) = max {
+                    number = number.min(*max);
+                }
+                Some(Value::from(number as i64))
+            }
+        }
+    }
+}
+
+/// A setting in the form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// Keys leading to the setting from the top of the config.
+    pub path: Vec<String>,
+    /// Titles of the objects the setting is in, e.g. `["Logging"]`.
+    pub section: Vec<String>,
+    pub title: String,
+    pub description: Option<String>,
+    pub control: Control,
+}
+
+impl Field {
+    /// Returns the setting's value in `config`.
+    pub fn get<'a>(&self, config: &'a Value) -> Option<&'a Value> {
+        self.path
+            .iter()
+            .try_fold(config, |value, key| value.get(key))
+    }
+
+    /// Sets the setting in `config`, adding the objects it is in if needed.
+    pub fn set(&self, config: &mut Value, value: Value) {
+        let Some((last, parents)) = self.path.split_last() else {
+            return;
+        };
+        let mut target = config;
+        for key in parents {
+            let Value::Object(object) = target else {
+                return;
+            };
+            target = object
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        if let Value::Object(object) = target {
+            object.insert(last.clone(), value);
+        }
+    }
+
+    /// Returns the setting's value in `config` as shown in its input.
+    pub fn display(&self, config: &Value) -> String {
+        match self.get(config) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(value) => value.to_string(),
+        }
+    }
+}
+
+/// Returns the fields for the settings in `schema`, in its order.
+pub fn fields(schema: &Value) -> Vec<Field> {
+    let mut fields = Vec::new();
+    collect(schema, &mut Vec::new(), &mut Vec::new(), &mut fields);
+    fields
+}
+
+fn collect(
+    schema: &Value,
+    path: &mut Vec<String>,
+    section: &mut Vec<String>,
+    fields: &mut Vec<Field>,
+) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (key, property) in properties {
+        path.push(key.clone());
+        let title = property
+            .get("title")
+            .and_then(Value::as_str)
+            .map_or_else(|| title_from_key(key), str::to_string);
+        if property.get("properties").is_some() {
+            section.push(title);
+            collect(property, path, section, fields);
+            section.pop();
+        } else if let Some(control) = Control::for_schema(property) {
+            fields.push(Field {
+                path: path.clone(),
+                section: section.clone(),
+                title,
+                description: property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                control,
+            });
+        }
+        path.pop();
+    }
+}
+
+/// Returns the type of a schema; for `["string", "null"]`, the type that
+/// isn't null.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(name) => Some(name),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null"),
+        _ => None,
+    }
+}
+
+/// Turns `cache_quota_mb` into "Cache quota mb", for settings without a title.
+fn title_from_key(key: &str) -> String {
+    let words = key.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Inputs for `fields`, editing the config held in `values`.
+#[component(inline_props)]
+pub fn SchemaForm(fields: ReadSignal<Vec<Field>>, values: Signal<Value>) -> View {
+    view! {
+        (fields.with(|fields| {
+            let mut views = Vec::new();
+            let mut section: &[String] = &[];
+            for field in fields {
+                // A heading wherever the settings move into another object
+                if field.section != section && !field.section.is_empty() {
+                    let heading = field.section.join(" › ");
+                    views.push(view! { div(class="divider text-sm") { (heading) } });
+                }
+                section = &field.section;
+                views.push(field_view(field.clone(), values));
+            }
+            views
+        }))
+    }
+}
+
+fn field_view(field: Field, values: Signal<Value>) -> View {
+    let title = field.title.clone();
+    let description = match field.description.clone() {
+        Some(description) => view! {
+            label(class="label") {
+                span(class="label-text-alt text-base-content/60") { (description) }
+            }
+        },
+        None => view! {},
+    };
+    let shown = {
+        let field = field.clone();
+        move || values.with(|config| field.display(config))
+    };
+    // Invalid input, like an empty number, puts the current value back
+    let on_input = {
+        let field = field.clone();
+        move |input: String| match field.control.parse(&input) {
+            Some(value) => values.update(|config| field.set(config, value)),
+            None => values.update(|_| {}),
+        }
+    };
+    let input_value = |e: web_sys::Event| {
+        e.target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+    };
+
+    match field.control.clone() {
+        Control::Text => view! {
+            div(class="form-control") {
+                label(class="label") {
+                    span(class="label-text") { (title) }
+                }
+                input(
+                    r#type="text",
+                    class="input input-bordered",
+                    prop:value=shown,
+                    on:input=move |e| {
+                        if let Some(input) = input_value(e) {
+                            on_input(input.value());
+                        }
+                    },
+                )
+                (description)
+            }
+        },
+        Control::Toggle => {
+            let checked = move || shown() == "true";
+            view! {
+                div(class="form-control") {
+                    label(class="label cursor-pointer") {
+                        span(class="label-text") { (title) }
+                        input(
+                            r#type="checkbox",
+                            class="toggle toggle-primary",
+                            prop:checked=checked,
+                            on:change=move |e| {
+                                if let Some(input) = input_value(e) {
+                                    on_input(input.checked().to_string());
+                                }
+                            },
+                        )
+                    }
+                    (description)
+                }
+            }
+        }
+        Control::Number { min, max } => {
+            let min = min.map(|min| min.to_string()).unwrap_or_default();
+            let max = max.map(|max| max.to_string()).unwrap_or_default();
+            view! {
+                div(class="form-control") {
+                    label(class="label") {
+                        span(class="label-text") { (title) }
+                    }
+                    // Applied on Enter or blur, so bounds don't get in the
+                    // way while typing
+                    input(
+                        r#type="number",
+                        class="input input-bordered",
+                        min=min,
+                        max=max,
+                        step="1",
+                        prop:value=shown,
+                        on:change=move |e| {
+                            if let Some(input) = input_value(e) {
+                                on_input(input.value());
+                            }
+                        },
+                    )
+                    (description)
+                }
+            }
+        }
+        Control::Select(choices) => {
+            // A value from a hand-edited file is offered even if it isn't
+            // one of the choices, so it isn't changed by accident
+            let options = move || {
+                let current = shown();
+                let mut options = choices.clone();
+                if !options.contains(&current) {
+                    options.push(current.clone());
+                }
+                options
+                    .into_iter()
+                    .map(|choice| {
+                        let selected = choice == current;
+                        let label = choice.clone();
+                        view! {
+                            option(value=choice, prop:selected=selected) { (label) }
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            };
+            view! {
+                div(class="form-control") {
+                    label(class="label") {
+                        span(class="label-text") { (title) }
+                    }
+                    select(
+                        class="select select-bordered",
+                        on:change=move |e: web_sys::Event| {
+                            if let Some(select) = e
+                                .target()
+                                .and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok())
+                            {
+                                on_input(select.value());
+                            }
+                        },
+                    ) {
+                        (options())
+                    }
+                    (description)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "username": { "type": "string", "title": "Username", "description": "Your name." },
+                "dark_mode": { "type": "boolean" },
+                "theme": { "type": "string", "enum": ["system", "light", "dark"] },
+                "logging": {
+                    "type": "object",
+                    "title": "Logging",
+                    "properties": {
+                        "max_files": { "type": "integer", "minimum": 0, "maximum": 100 },
+                        "modules": { "type": "object", "additionalProperties": { "type": "string" } }
+                    }
+                },
+                "nickname": { "type": ["string", "null"] },
+                "tags": { "type": "array" }
+            }
+        })
+    }
+
+    #[test]
+    fn test_fields_from_schema() {
+        let fields = fields(&schema());
+        let summary: Vec<(String, &str, &Control)> = fields
+            .iter()
+            .map(|f| (f.path.join("."), f.title.as_str(), &f.control))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("username".to_string(), "Username", &Control::Text),
+                ("dark_mode".to_string(), "Dark mode", &Control::Toggle),
+                (
+                    "theme".to_string(),
+                    "Theme",
+                    &Control::Select(vec![
+                        "system".to_string(),
+                        "light".to_string(),
+                        "dark".to_string()
+                    ])
+                ),
+                (
+                    "logging.max_files".to_string(),
+                    "Max files",
+                    &Control::Number {
+                        min: Some(0.0),
+                        max: Some(100.0)
+                    }
+                ),
+                ("nickname".to_string(), "Nickname", &Control::Text),
+            ]
+        );
+        assert_eq!(fields[0].description.as_deref(), Some("Your name."));
+        assert_eq!(fields[3].section, ["Logging"]);
+        assert!(fields[0].section.is_empty());
+    }
+
+    #[test]
+    fn test_parse_get_and_set() {
+        let fields = fields(&schema());
+        let max_files = &fields[3];
+        assert_eq!(max_files.control.parse("7.6"), Some(json!(8)));
+        assert_eq!(max_files.control.parse("500"), Some(json!(100)));
+        assert_eq!(max_files.control.parse("-1"), Some(json!(0)));
+        assert_eq!(max_files.control.parse(""), None);
+        assert_eq!(fields[1].control.parse("true"), Some(json!(true)));
+
+        let mut config = json!({ "username": "me" });
+        assert_eq!(fields[0].display(&config), "me");
+        assert_eq!(max_files.display(&config), "");
+        max_files.set(&mut config, json!(3));
+        assert_eq!(
+            config,
+            json!({ "username": "me", "logging": { "max_files": 3 } })
+        );
+        assert_eq!(max_files.display(&config), "3");
+    }
+}
@@ -3,25 +3,71 @@
 //! Use `provide_context` in the root component to make AppState available
 //! throughout the component tree. Access it in child components with `use_context`.
 
+use crate::config::Versioned;
+use crate::export::RecentExport;
+use crate::sysinfo::MetricsSample;
 use sycamore::prelude::*;
 
+/// Number of recent exports shown, matching the backend's list.
+pub const MAX_RECENT_EXPORTS: usize = 10;
+
+/// Pages that can be shown in the main content area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Route {
+    #[default]
+    Home,
+    Diagnostics,
+    Performance,
+    Logs,
+}
+
+impl Route {
+    /// Returns the title shown in navigation.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Route::Home => "Dashboard",
+            Route::Diagnostics => "Diagnostics",
+            Route::Performance => "Performance",
+            Route::Logs => "Logs",
+        }
+    }
+}
+
 /// Global application state accessible throughout the component tree.
 #[derive(Clone, Copy)]
 pub struct AppState {
+    pub route: Signal<Route>,
     pub counter: Signal<i32>,
     pub is_busy: Signal<bool>,
     pub username: Signal<String>,
     pub notification: Signal<Option<String>>,
+    /// Memory warning threshold in MiB from the config (0 disables it).
+    pub memory_warning_mb: Signal<u32>,
+    /// Most recent self-monitoring sample streamed from the backend.
+    pub latest_metrics: Signal<Option<MetricsSample>>,
+    /// Recently exported charts, newest first.
+    pub recent_exports: Signal<Vec<RecentExport>>,
+    /// Config last reloaded after it was changed outside the app.
+    pub external_config: Signal<Option<Versioned>>,
+    /// Set when a change outside the app touched settings the backend had
+    /// yet to write: the config on disk, and the unwritten changes.
+    pub config_conflict: Signal<Option<(Versioned, serde_json::Value)>>,
 }
 
 impl AppState {
     /// Creates a new AppState with default values.
     pub fn new() -> Self {
         Self {
+            route: create_signal(Route::default()),
             counter: create_signal(0),
             is_busy: create_signal(false),
             username: create_signal(String::new()),
             notification: create_signal(None),
+            memory_warning_mb: create_signal(0),
+            latest_metrics: create_signal(None),
+            recent_exports: create_signal(Vec::new()),
+            external_config: create_signal(None),
+            config_conflict: create_signal(None),
         }
     }
 
@@ -40,6 +86,20 @@ impl AppState {
         self.counter.set(value);
     }
 
+    /// Switches the main content area to another page.
+    pub fn navigate(&self, route: Route) {
+        self.route.set(route);
+    }
+
+    /// Adds a finished export to the front of the recent exports list.
+    pub fn remember_export(&self, entry: RecentExport) {
+        self.recent_exports.update(|list| {
+            list.retain(|e| e.path != entry.path);
+            list.insert(0, entry);
+            list.truncate(MAX_RECENT_EXPORTS);
+        });
+    }
+
     /// Shows a notification message.
     pub fn notify(&self, msg: impl Into<String>) {
         self.notification.set(Some(msg.into()));
@@ -0,0 +1,166 @@
+//! Creating support bundles from the Settings card.
+//!
+//! The button opens a dialog previewing the files the backend will put in
+//! the bundle; saving asks for a destination and has the backend write the
+//! archive there.
+
+use crate::logging;
+use crate::state::AppState;
+use crate::sysinfo::format_bytes;
+use serde::Deserialize;
+use serde_json::json;
+use sycamore::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = previewSupportBundle)]
+    fn preview_support_bundle() -> js_sys::Promise;
+
+    #[wasm_bindgen(js_namespace = ["window", "electronAPI"], js_name = createSupportBundle)]
+    fn create_support_bundle() -> js_sys::Promise;
+}
+
+/// A file the bundle will contain.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PreviewItem {
+    pub name: String,
+    pub description: String,
+    pub bytes: u64,
+}
+
+/// Summary of a written bundle.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BundleSummary {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Returns the combined size of the previewed files.
+pub fn total_bytes(items: &[PreviewItem]) -> u64 {
+    items.iter().map(|item| item.bytes).sum()
+}
+
+/// Extracts a readable message from a rejected IPC promise.
+fn error_message(e: &JsValue) -> String {
+    e.dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .unwrap_or_else(|| format!("{:?}", e))
+}
+
+/// Button opening the support bundle dialog.
+#[component]
+pub(crate) fn SupportBundleButton() -> View {
+    let app_state = use_context::<AppState>();
+    let open = create_signal(false);
+    let items = create_signal(Vec::<PreviewItem>::new());
+    let saving = create_signal(false);
+
+    let on_open = move |_| {
+        open.set(true);
+        items.set(Vec::new());
+        wasm_bindgen_futures::spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(preview_support_bundle()).await {
+                Ok(result) => items.set(
+                    result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str(&json).ok())
+                        .unwrap_or_default(),
+                ),
+                Err(e) => logging::error(
+                    "Error previewing support bundle",
+                    json!({ "error": error_message(&e) }),
+                ),
+            }
+        });
+    };
+
+    let on_save = move |_| {
+        saving.set(true);
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = wasm_bindgen_futures::JsFuture::from(create_support_bundle()).await;
+            saving.set(false);
+            match result {
+                Ok(result) => {
+                    // Null means the save dialog was cancelled; keep the preview open
+                    if let Some(summary) = result
+                        .as_string()
+                        .and_then(|json| serde_json::from_str::<BundleSummary>(&json).ok())
+                    {
+                        open.set(false);
+                        app_state.notify(format!(
+                            "Support bundle saved to {} ({})",
+                            summary.path,
+                            format_bytes(summary.bytes)
+                        ));
+                    }
+                }
+                Err(e) => {
+                    let message = error_message(&e);
+                    logging::error(
+                        "Error creating support bundle",
+                        json!({ "error": message.clone() }),
+                    );
+                    app_state.notify(format!("Support bundle failed: {}", message));
+                }
+            }
+        });
+    };
+
+    let total = move || format_bytes(items.with(|i| total_bytes(i)));
+
+    view! {
+        button(class="btn btn-outline", on:click=on_open) { "Create Support Bundle…" }
+        div(class=move || if open.get() { "modal modal-open" } else { "modal" }) {
+            div(class="modal-box max-w-2xl") {
+                h3(class="font-bold text-lg") { "Support bundle" }
+                p(class="text-sm text-base-content/70 py-2") {
+                    "These files will be saved in one zip archive. The username and home folder are redacted."
+                }
+                div(class="overflow-auto max-h-80") {
+                    table(class="table table-sm") {
+                        tbody {
+                            Indexed(
+                                list=items,
+                                view=|item| view! {
+                                    tr {
+                                        td(class="font-mono") { (item.name) }
+                                        td { (item.description) }
+                                        td(class="text-right whitespace-nowrap") { (format_bytes(item.bytes)) }
+                                    }
+                                },
+                            )
+                        }
+                    }
+                }
+                div(class="text-sm text-right pt-2") { "Total before compression: " (total) }
+                div(class="modal-action") {
+                    button(class="btn", on:click=move |_| open.set(false)) { "Cancel" }
+                    button(
+                        class="btn btn-primary",
+                        disabled=move || saving.get() || items.with(|i| i.is_empty()),
+                        on:click=on_save,
+                    ) { "Save…" }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_parsing_and_total() {
+        let items: Vec<PreviewItem> = serde_json::from_str(
+            r#"[{"name": "config.json", "description": "Settings", "bytes": 120},
+                {"name": "logs/app.log", "description": "Log file", "bytes": 2048}]"#,
+        )
+        .unwrap();
+        assert_eq!(items[1].name, "logs/app.log");
+        assert_eq!(total_bytes(&items), 2168);
+        assert_eq!(total_bytes(&[]), 0);
+    }
+}
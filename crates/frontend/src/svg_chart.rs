@@ -0,0 +1,929 @@
+//! Native SVG rendering of [`ChartSpec`]s.
+//!
+//! Scales, ticks and shapes are computed by [`layout`], a pure function that
+//! is tested natively. The [`SvgChart`] component turns the layout into SVG
+//! elements with a legend and hover tooltips, drawn in a fixed
+//! [`WIDTH`] x [`HEIGHT`] view box that scales with its container.
+//! [`to_svg_document`] renders the same layout as a standalone document for
+//! export.
+
+use crate::chart::{ChartKind, ChartSpec, ThemeColors, translucent, use_theme_colors};
+use sycamore::prelude::*;
+
+/// Width of the SVG view box.
+pub const WIDTH: f64 = 640.0;
+/// Height of the SVG view box.
+pub const HEIGHT: f64 = 280.0;
+
+const MARGIN_LEFT: f64 = 56.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 12.0;
+const MARGIN_BOTTOM: f64 = 28.0;
+
+/// Maximum number of labels on the x axis; extra categories are skipped.
+const MAX_X_TICKS: usize = 8;
+/// Approximate number of ticks on a numeric axis.
+const TARGET_TICKS: usize = 5;
+
+/// Linear mapping from data values to view box coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale {
+    pub domain: (f64, f64),
+    pub range: (f64, f64),
+}
+
+impl Scale {
+    pub fn new(domain: (f64, f64), range: (f64, f64)) -> Self {
+        Self { domain, range }
+    }
+
+    /// Maps a data value into the range. An empty domain maps to the middle.
+    pub fn map(&self, value: f64) -> f64 {
+        let (d0, d1) = self.domain;
+        let (r0, r1) = self.range;
+        if d1 == d0 {
+            return (r0 + r1) / 2.0;
+        }
+        r0 + (value - d0) / (d1 - d0) * (r1 - r0)
+    }
+}
+
+/// Rounds a raw tick spacing up to 1, 2 or 5 times a power of ten.
+fn nice_step(raw: f64) -> f64 {
+    if raw <= 0.0 || !raw.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let nice = match raw / magnitude {
+        f if f <= 1.0 => 1.0,
+        f if f <= 2.0 => 2.0,
+        f if f <= 5.0 => 5.0,
+        _ => 10.0,
+    };
+    nice * magnitude
+}
+
+/// Returns about `target` evenly spaced, round tick values covering `min..=max`.
+pub fn nice_ticks(min: f64, max: f64, target: usize) -> Vec<f64> {
+    if !min.is_finite() || !max.is_finite() {
+        return vec![0.0, 1.0];
+    }
+    let (min, max) = if min == max {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min.min(max), min.max(max))
+    };
+    let step = nice_step((max - min) / (target.max(2) - 1) as f64);
+    let first = (min / step).floor() as i64;
+    let last = (max / step).ceil() as i64;
+    // Round away floating point noise such as 0.30000000000000004 and -0
+    let precision = 10f64.powi((1 - step.log10().floor() as i32).max(0));
+
+    (first..=last)
+        .map(|k| (k as f64 * step * precision).round() / precision + 0.0)
+        .collect()
+}
+
+/// Formats a tick with as many decimals as the spacing between ticks needs.
+pub fn format_tick(value: f64, step: f64) -> String {
+    let decimals = if step >= 1.0 || step <= 0.0 {
+        0
+    } else {
+        (-step.log10()).ceil() as usize
+    };
+    format!("{:.*}", decimals, value)
+}
+
+/// Formats a data value for a tooltip, dropping trailing zeros.
+pub fn format_value(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// A label on an axis, at a position along that axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tick {
+    pub position: f64,
+    pub label: String,
+}
+
+/// A hoverable bar or data point.
+///
+/// Points have zero width and height, with `x` and `y` at their center.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub series: usize,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub color: String,
+    pub tooltip: String,
+}
+
+impl Mark {
+    fn is_bar(&self) -> bool {
+        self.width > 0.0
+    }
+}
+
+/// Shapes drawn for one dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub color: String,
+    /// SVG path of the line, for line and area charts.
+    pub line: Option<String>,
+    /// SVG path of the filled region below the line.
+    pub area: Option<String>,
+}
+
+/// Everything needed to draw a chart, in view box coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub x_ticks: Vec<Tick>,
+    pub y_ticks: Vec<Tick>,
+    pub series: Vec<Series>,
+    pub marks: Vec<Mark>,
+}
+
+/// Returns the numeric ticks and scale for the y axis.
+fn y_axis(spec: &ChartSpec) -> (Vec<Tick>, Scale) {
+    let values = spec.datasets.iter().flat_map(|d| d.data.iter().copied());
+    let (mut lo, mut hi) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if lo > hi {
+        (lo, hi) = (0.0, 1.0);
+    }
+    if spec.options.begin_at_zero || spec.kind == ChartKind::Bar {
+        lo = lo.min(0.0);
+        hi = hi.max(0.0);
+    }
+    if let Some(max) = spec.options.y_max {
+        hi = max;
+    }
+
+    let mut values = nice_ticks(lo, hi, TARGET_TICKS);
+    if let Some(max) = spec.options.y_max {
+        values.retain(|v| *v <= max + f64::EPSILON);
+    }
+    let step = values
+        .get(1)
+        .zip(values.first())
+        .map_or(1.0, |(b, a)| b - a);
+    let domain_max = spec.options.y_max.unwrap_or(*values.last().unwrap_or(&hi));
+    let scale = Scale::new(
+        (values.first().copied().unwrap_or(lo), domain_max),
+        (HEIGHT - MARGIN_BOTTOM, MARGIN_TOP),
+    );
+
+    let ticks = values
+        .iter()
+        .map(|&v| Tick {
+            position: scale.map(v),
+            label: format_tick(v, step),
+        })
+        .collect();
+    (ticks, scale)
+}
+
+/// Builds an SVG path through `points`.
+fn line_path(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, (x, y))| format!("{}{:.1} {:.1}", if i == 0 { "M" } else { "L" }, x, y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds a closed SVG path filling the region between `points` and `baseline`.
+fn area_path(points: &[(f64, f64)], baseline: f64) -> Option<String> {
+    let (first, last) = (points.first()?, points.last()?);
+    Some(format!(
+        "{} L{:.1} {:.1} L{:.1} {:.1} Z",
+        line_path(points),
+        last.0,
+        baseline,
+        first.0,
+        baseline
+    ))
+}
+
+/// Computes the axes, shapes and tooltips of `spec`.
+///
+/// Bar, line and area charts place values at evenly spaced categories;
+/// scatter charts use a numeric x axis built from [`Dataset::x`].
+///
+/// [`Dataset::x`]: crate::chart::Dataset::x
+pub fn layout(spec: &ChartSpec, theme: &ThemeColors) -> Layout {
+    let (left, right) = (MARGIN_LEFT, WIDTH - MARGIN_RIGHT);
+    let (y_ticks, y_scale) = y_axis(spec);
+    let (y_lo, y_hi) = y_scale.domain;
+    let baseline = y_scale.map(0.0_f64.clamp(y_lo.min(y_hi), y_lo.max(y_hi)));
+    let unit = spec
+        .options
+        .y_label
+        .as_ref()
+        .map(|u| format!(" {}", u))
+        .unwrap_or_default();
+
+    let categories = spec
+        .datasets
+        .iter()
+        .map(|d| d.data.len())
+        .max()
+        .unwrap_or(0)
+        .max(spec.labels.len());
+    let category = |i: usize| {
+        spec.labels
+            .get(i)
+            .cloned()
+            .unwrap_or_else(|| (i + 1).to_string())
+    };
+
+    let mut series = Vec::new();
+    let mut marks = Vec::new();
+
+    let x_ticks = match spec.kind {
+        ChartKind::Scatter => {
+            let xs = spec
+                .datasets
+                .iter()
+                .flat_map(|d| (0..d.data.len()).map(|i| d.x_at(i)));
+            let (lo, hi) = xs.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+            let (lo, hi) = if lo > hi { (0.0, 1.0) } else { (lo, hi) };
+            let values = nice_ticks(lo, hi, TARGET_TICKS);
+            let step = values
+                .get(1)
+                .zip(values.first())
+                .map_or(1.0, |(b, a)| b - a);
+            let x_scale = Scale::new((values[0], *values.last().unwrap_or(&hi)), (left, right));
+
+            for (s, dataset) in spec.datasets.iter().enumerate() {
+                let color = theme.series_color(spec, s);
+                for (i, &y) in dataset.data.iter().enumerate() {
+                    let x = dataset.x_at(i);
+                    marks.push(Mark {
+                        series: s,
+                        x: x_scale.map(x),
+                        y: y_scale.map(y),
+                        width: 0.0,
+                        height: 0.0,
+                        color: color.clone(),
+                        tooltip: format!(
+                            "{}: ({}, {}{})",
+                            dataset.label,
+                            format_value(x),
+                            format_value(y),
+                            unit
+                        ),
+                    });
+                }
+                series.push(Series {
+                    label: dataset.label.clone(),
+                    color,
+                    line: None,
+                    area: None,
+                });
+            }
+
+            values
+                .iter()
+                .map(|&v| Tick {
+                    position: x_scale.map(v),
+                    label: format_tick(v, step),
+                })
+                .collect()
+        }
+        ChartKind::Bar => {
+            let band = (right - left) / categories.max(1) as f64;
+            let bar_width = band * 0.8 / spec.datasets.len().max(1) as f64;
+
+            for (s, dataset) in spec.datasets.iter().enumerate() {
+                let color = theme.series_color(spec, s);
+                for (i, &y) in dataset.data.iter().enumerate() {
+                    let top = y_scale.map(y);
+                    marks.push(Mark {
+                        series: s,
+                        x: left + band * i as f64 + band * 0.1 + bar_width * s as f64,
+                        y: top.min(baseline),
+                        width: bar_width,
+                        height: (baseline - top).abs(),
+                        color: if dataset.color_per_point {
+                            theme.color(i)
+                        } else {
+                            color.clone()
+                        },
+                        tooltip: format!(
+                            "{} · {}: {}{}",
+                            category(i),
+                            dataset.label,
+                            format_value(y),
+                            unit
+                        ),
+                    });
+                }
+                series.push(Series {
+                    label: dataset.label.clone(),
+                    color,
+                    line: None,
+                    area: None,
+                });
+            }
+
+            category_ticks(categories, |i| left + band * (i as f64 + 0.5), category)
+        }
+        _ => {
+            let x_at = |i: usize| {
+                if categories <= 1 {
+                    (left + right) / 2.0
+                } else {
+                    left + (right - left) * i as f64 / (categories - 1) as f64
+                }
+            };
+
+            for (s, dataset) in spec.datasets.iter().enumerate() {
+                let color = theme.series_color(spec, s);
+                let points: Vec<(f64, f64)> = dataset
+                    .data
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &y)| (x_at(i), y_scale.map(y)))
+                    .collect();
+                for (i, &y) in dataset.data.iter().enumerate() {
+                    marks.push(Mark {
+                        series: s,
+                        x: points[i].0,
+                        y: points[i].1,
+                        width: 0.0,
+                        height: 0.0,
+                        color: color.clone(),
+                        tooltip: format!(
+                            "{} · {}: {}{}",
+                            category(i),
+                            dataset.label,
+                            format_value(y),
+                            unit
+                        ),
+                    });
+                }
+                series.push(Series {
+                    label: dataset.label.clone(),
+                    color,
+                    line: (!points.is_empty()).then(|| line_path(&points)),
+                    area: if dataset.is_filled(spec.kind) {
+                        area_path(&points, baseline)
+                    } else {
+                        None
+                    },
+                });
+            }
+
+            category_ticks(categories, x_at, category)
+        }
+    };
+
+    Layout {
+        x_ticks,
+        y_ticks,
+        series,
+        marks,
+    }
+}
+
+/// Labels at most [`MAX_X_TICKS`] categories, always including the last one.
+fn category_ticks(
+    categories: usize,
+    position: impl Fn(usize) -> f64,
+    label: impl Fn(usize) -> String,
+) -> Vec<Tick> {
+    let stride = categories.div_ceil(MAX_X_TICKS).max(1);
+    (0..categories)
+        .filter(|i| (categories - 1 - i).is_multiple_of(stride))
+        .map(|i| Tick {
+            position: position(i),
+            label: label(i),
+        })
+        .collect()
+}
+
+/// Formats a view box coordinate for an SVG attribute.
+fn coord(value: f64) -> String {
+    format!("{:.1}", value)
+}
+
+/// Height of the legend row added above the chart in standalone documents.
+const LEGEND_HEIGHT: f64 = 24.0;
+
+/// Escapes text for use in SVG content and attribute values.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders `spec` as a standalone SVG document for export.
+///
+/// The document has a solid background and, if the legend is enabled, draws
+/// it inside the SVG; hover tooltips are left out. Returns `None` for kinds
+/// the SVG backend does not support.
+pub fn to_svg_document(spec: &ChartSpec, theme: &ThemeColors) -> Option<String> {
+    if !spec.kind.supports_svg() {
+        return None;
+    }
+    let layout = layout(spec, theme);
+    let legend = spec.options.show_legend && !spec.datasets.is_empty();
+    let offset = if legend { LEGEND_HEIGHT } else { 0.0 };
+    let height = HEIGHT + offset;
+    let bottom = HEIGHT - MARGIN_BOTTOM;
+    let (left, right) = (coord(MARGIN_LEFT), coord(WIDTH - MARGIN_RIGHT));
+
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif">"#,
+        w = WIDTH,
+        h = height
+    );
+    out += &format!(
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        escape_xml(&theme.surface)
+    );
+
+    if legend {
+        const CHAR_WIDTH: f64 = 6.5;
+        let items: Vec<(String, String)> = spec
+            .datasets
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.label.clone(), theme.series_color(spec, i)))
+            .collect();
+        let widths: Vec<f64> = items
+            .iter()
+            .map(|(label, _)| 18.0 + label.chars().count() as f64 * CHAR_WIDTH)
+            .collect();
+        let gap = 16.0;
+        let total = widths.iter().sum::<f64>() + gap * (items.len() - 1) as f64;
+        let mut x = ((WIDTH - total) / 2.0).max(0.0);
+        for ((label, color), width) in items.iter().zip(&widths) {
+            out += &format!(
+                r#"<rect x="{}" y="6" width="12" height="12" rx="2" fill="{}"/><text x="{}" y="12" fill="{}" font-size="12" dominant-baseline="middle">{}</text>"#,
+                coord(x),
+                escape_xml(color),
+                coord(x + 16.0),
+                escape_xml(&theme.text),
+                escape_xml(label)
+            );
+            x += width + gap;
+        }
+    }
+
+    out += &format!(r#"<g transform="translate(0 {})">"#, coord(offset));
+    for tick in &layout.y_ticks {
+        let y = coord(tick.position);
+        out += &format!(
+            r#"<line x1="{left}" x2="{right}" y1="{y}" y2="{y}" stroke="{}"/><text x="{}" y="{y}" fill="{}" font-size="11" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+            escape_xml(&theme.grid),
+            coord(MARGIN_LEFT - 6.0),
+            escape_xml(&theme.muted),
+            escape_xml(&tick.label)
+        );
+    }
+    out += &format!(
+        r#"<line x1="{left}" x2="{right}" y1="{y}" y2="{y}" stroke="{}"/>"#,
+        escape_xml(&theme.muted),
+        y = coord(bottom)
+    );
+    for tick in &layout.x_ticks {
+        out += &format!(
+            r#"<text x="{}" y="{}" fill="{}" font-size="11" text-anchor="middle">{}</text>"#,
+            coord(tick.position),
+            coord(bottom + 16.0),
+            escape_xml(&theme.muted),
+            escape_xml(&tick.label)
+        );
+    }
+    if let Some(label) = &spec.options.y_label {
+        out += &format!(
+            r#"<text x="{}" y="12" transform="rotate(-90)" fill="{}" font-size="11" text-anchor="middle">{}</text>"#,
+            coord(-(MARGIN_TOP + bottom) / 2.0),
+            escape_xml(&theme.muted),
+            escape_xml(label)
+        );
+    }
+    for series in &layout.series {
+        if let Some(d) = &series.area {
+            out += &format!(
+                r#"<path d="{}" fill="{}" fill-opacity="0.3" stroke="none"/>"#,
+                d,
+                escape_xml(&series.color)
+            );
+        }
+        if let Some(d) = &series.line {
+            out += &format!(
+                r#"<path d="{}" fill="none" stroke="{}" stroke-width="2" stroke-linejoin="round"/>"#,
+                d,
+                escape_xml(&series.color)
+            );
+        }
+    }
+    for mark in &layout.marks {
+        if mark.is_bar() {
+            out += &format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                coord(mark.x),
+                coord(mark.y),
+                coord(mark.width),
+                coord(mark.height),
+                escape_xml(&mark.color)
+            );
+        } else if spec.kind == ChartKind::Scatter {
+            out += &format!(
+                r#"<circle cx="{}" cy="{}" r="4" fill="{}"/>"#,
+                coord(mark.x),
+                coord(mark.y),
+                escape_xml(&mark.color)
+            );
+        }
+    }
+    out += "</g></svg>";
+    Some(out)
+}
+
+/// Tooltip drawn above a hovered mark, kept inside the view box.
+fn tooltip_view(mark: Mark, theme: ThemeColors) -> View {
+    const CHAR_WIDTH: f64 = 6.5;
+    const BOX_HEIGHT: f64 = 22.0;
+
+    let width = mark.tooltip.chars().count() as f64 * CHAR_WIDTH + 16.0;
+    let center = mark.x + mark.width / 2.0;
+    let x = (center - width / 2.0).clamp(0.0, (WIDTH - width).max(0.0));
+    let above = mark.y - BOX_HEIGHT - 8.0;
+    let y = if above >= 0.0 { above } else { mark.y + 8.0 };
+    let highlight = (!mark.is_bar()).then(|| {
+        let (cx, cy) = (coord(mark.x), coord(mark.y));
+        let (fill, stroke) = (mark.color.clone(), theme.surface.clone());
+        view! {
+            circle(cx=cx, cy=cy, r="4", fill=fill, stroke=stroke, strokeWidth="2")
+        }
+    });
+    let (text_x, text_y) = (coord(x + width / 2.0), coord(y + BOX_HEIGHT / 2.0));
+    let (x, y, width, height) = (coord(x), coord(y), coord(width), coord(BOX_HEIGHT));
+
+    view! {
+        g(pointerEvents="none") {
+            (highlight)
+            rect(x=x, y=y, width=width, height=height, rx="4", fill=theme.surface, stroke=theme.grid)
+            text(x=text_x, y=text_y, fill=theme.text, fontSize="12", textAnchor="middle", dominantBaseline="central") {
+                (mark.tooltip)
+            }
+        }
+    }
+}
+
+/// Draws the axes, grid and series of a layout.
+fn chart_view(
+    layout: &Layout,
+    spec: &ChartSpec,
+    theme: &ThemeColors,
+    hovered: Signal<Option<usize>>,
+) -> View {
+    let bottom = HEIGHT - MARGIN_BOTTOM;
+    let (left, right) = (coord(MARGIN_LEFT), coord(WIDTH - MARGIN_RIGHT));
+
+    let y_ticks = layout
+        .y_ticks
+        .iter()
+        .map(|tick| {
+            let (left, right, y) = (left.clone(), right.clone(), coord(tick.position));
+            let (y1, y2) = (y.clone(), y.clone());
+            let (label_x, label) = (coord(MARGIN_LEFT - 6.0), tick.label.clone());
+            let (grid, muted) = (theme.grid.clone(), theme.muted.clone());
+            view! {
+                line(x1=left, x2=right, y1=y1, y2=y2, stroke=grid)
+                text(x=label_x, y=y, fill=muted, fontSize="11", textAnchor="end", dominantBaseline="middle") {
+                    (label)
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let x_ticks = layout
+        .x_ticks
+        .iter()
+        .map(|tick| {
+            let (x, y) = (coord(tick.position), coord(bottom + 16.0));
+            let (label, muted) = (tick.label.clone(), theme.muted.clone());
+            view! {
+                text(x=x, y=y, fill=muted, fontSize="11", textAnchor="middle") {
+                    (label)
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let y_label = spec.options.y_label.clone().map(|label| {
+        let x = coord(-(MARGIN_TOP + bottom) / 2.0);
+        let muted = theme.muted.clone();
+        view! {
+            text(x=x, y="12", transform="rotate(-90)", fill=muted, fontSize="11", textAnchor="middle") {
+                (label)
+            }
+        }
+    });
+
+    let shapes = layout
+        .series
+        .iter()
+        .map(|series| {
+            let area = series.area.clone().map(|d| {
+                let fill = translucent(&series.color, 30);
+                view! { path(d=d, fill=fill, stroke="none") }
+            });
+            let line = series.line.clone().map(|d| {
+                let stroke = series.color.clone();
+                view! { path(d=d, fill="none", stroke=stroke, strokeWidth="2", strokeLinejoin="round") }
+            });
+            view! { (area) (line) }
+        })
+        .collect::<Vec<_>>();
+
+    // Bars and scatter points are visible marks; line points only get hit targets
+    let marks = layout
+        .marks
+        .iter()
+        .enumerate()
+        .map(|(i, mark)| {
+            let enter = move |_| hovered.set(Some(i));
+            let leave = move |_| hovered.set(None);
+            let (x, y, fill) = (coord(mark.x), coord(mark.y), mark.color.clone());
+            if mark.is_bar() {
+                let (width, height) = (coord(mark.width), coord(mark.height));
+                view! {
+                    rect(x=x, y=y, width=width, height=height, fill=fill, on:mouseenter=enter, on:mouseleave=leave)
+                }
+            } else if spec.kind == ChartKind::Scatter {
+                view! {
+                    circle(cx=x, cy=y, r="4", fill=fill, on:mouseenter=enter, on:mouseleave=leave)
+                }
+            } else {
+                view! {
+                    circle(cx=x, cy=y, r="8", fill="transparent", on:mouseenter=enter, on:mouseleave=leave)
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (axis_y1, axis_y2, axis_stroke) = (coord(bottom), coord(bottom), theme.muted.clone());
+    view! {
+        (y_ticks)
+        line(x1=left, x2=right, y1=axis_y1, y2=axis_y2, stroke=axis_stroke)
+        (x_ticks)
+        (y_label)
+        (shapes)
+        (marks)
+    }
+}
+
+/// SVG chart that follows `spec`.
+///
+/// Pie, doughnut and radar charts are not supported and show a notice instead.
+#[component(inline_props)]
+pub fn SvgChart(spec: ReadSignal<ChartSpec>) -> View {
+    let theme = use_theme_colors();
+    let layout = create_memo(move || spec.with(|spec| layout(spec, &theme.get_clone())));
+    let hovered = create_signal(None::<usize>);
+
+    let legend = move || {
+        let theme = theme.get_clone();
+        spec.with(|spec| {
+            if !spec.options.show_legend {
+                return view! {};
+            }
+            let items = spec
+                .datasets
+                .iter()
+                .enumerate()
+                .map(|(i, dataset)| {
+                    let swatch = format!("background-color: {}", theme.series_color(spec, i));
+                    let label = dataset.label.clone();
+                    view! {
+                        span(class="flex items-center gap-1") {
+                            span(class="inline-block w-3 h-3 rounded-sm", style=swatch) {}
+                            (label)
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            let style = format!("color: {}", theme.text);
+            view! {
+                div(class="flex flex-wrap justify-center gap-4 text-xs", style=style) {
+                    (items)
+                }
+            }
+        })
+    };
+
+    let body = move || {
+        spec.with(|spec| {
+            if !spec.kind.supports_svg() {
+                let notice = format!("{:?} charts are not supported by the SVG backend", spec.kind);
+                return view! {
+                    div(class="flex h-full items-center justify-center text-base-content/70") {
+                        (notice)
+                    }
+                };
+            }
+            let theme = theme.get_clone();
+            let content = layout.with(|layout| chart_view(layout, spec, &theme, hovered));
+            let tooltip = move || {
+                hovered
+                    .get()
+                    .and_then(|i| layout.with(|l| l.marks.get(i).cloned()))
+                    .map(|mark| tooltip_view(mark, theme.clone()))
+            };
+            let view_box = format!("0 0 {} {}", WIDTH, HEIGHT);
+            view! {
+                svg(class="w-full flex-1 min-h-0", viewBox=view_box, xmlns="http://www.w3.org/2000/svg", on:mouseleave=move |_| hovered.set(None)) {
+                    (content)
+                    (tooltip)
+                }
+            }
+        })
+    };
+
+    view! {
+        div(class="flex flex-col h-full gap-2") {
+            (legend)
+            (body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::{ChartOptions, Dataset};
+
+    fn theme() -> ThemeColors {
+        ThemeColors {
+            text: "white".to_string(),
+            muted: "gray".to_string(),
+            grid: "black".to_string(),
+            surface: "navy".to_string(),
+            palette: vec!["red".to_string(), "blue".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_nice_ticks() {
+        assert_eq!(nice_ticks(0.0, 95.0, 5), vec![0.0, 50.0, 100.0]);
+        assert_eq!(nice_ticks(0.0, 10.0, 5), vec![0.0, 5.0, 10.0]);
+        assert_eq!(nice_ticks(3.0, 3.0, 5), vec![2.0, 2.5, 3.0, 3.5, 4.0]);
+        assert_eq!(nice_ticks(-0.3, 0.3, 4), vec![-0.4, -0.2, 0.0, 0.2, 0.4]);
+        assert_eq!(nice_ticks(f64::NAN, 1.0, 5), vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_format_tick_and_value() {
+        assert_eq!(format_tick(50.0, 50.0), "50");
+        assert_eq!(format_tick(0.2, 0.2), "0.2");
+        assert_eq!(format_tick(0.05, 0.05), "0.05");
+        assert_eq!(format_value(1.5), "1.5");
+        assert_eq!(format_value(2.0), "2");
+        assert_eq!(format_value(1.234), "1.23");
+    }
+
+    #[test]
+    fn test_scale() {
+        let scale = Scale::new((0.0, 100.0), (240.0, 12.0));
+        assert_eq!(scale.map(0.0), 240.0);
+        assert_eq!(scale.map(100.0), 12.0);
+        assert_eq!(scale.map(50.0), 126.0);
+        assert_eq!(Scale::new((5.0, 5.0), (0.0, 10.0)).map(5.0), 5.0);
+    }
+
+    #[test]
+    fn test_bar_layout() {
+        let spec = ChartSpec::new(ChartKind::Bar)
+            .with_labels(["a", "b"])
+            .with_dataset(Dataset::new("first", vec![50.0, 100.0]))
+            .with_dataset(Dataset::new("second", vec![25.0, 0.0]).with_color("green"));
+        let layout = layout(&spec, &theme());
+
+        let labels: Vec<&str> = layout.y_ticks.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, vec!["0", "50", "100"]);
+        let x_labels: Vec<&str> = layout.x_ticks.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(x_labels, vec!["a", "b"]);
+
+        assert_eq!(layout.marks.len(), 4);
+        let baseline = HEIGHT - MARGIN_BOTTOM;
+        let plot_height = baseline - MARGIN_TOP;
+        let full = &layout.marks[1];
+        assert_eq!(full.y, MARGIN_TOP);
+        assert_eq!(full.height, plot_height);
+        assert_eq!(layout.marks[0].height, plot_height / 2.0);
+        assert_eq!(layout.marks[0].tooltip, "a · first: 50");
+        assert_eq!(layout.marks[2].color, "green");
+        assert_eq!(layout.marks[3].height, 0.0);
+
+        // Bars of the same category sit side by side
+        assert_eq!(layout.marks[2].x, layout.marks[0].x + layout.marks[0].width);
+    }
+
+    #[test]
+    fn test_area_layout() {
+        let spec = ChartSpec::new(ChartKind::Area)
+            .with_labels(["-4s", "-2s", "-0s"])
+            .with_dataset(Dataset::new("RSS", vec![0.0, 10.0, 5.0]))
+            .with_options(ChartOptions {
+                y_label: Some("MiB".to_string()),
+                ..ChartOptions::default()
+            });
+        let layout = layout(&spec, &theme());
+        let series = &layout.series[0];
+        let bottom = HEIGHT - MARGIN_BOTTOM;
+
+        assert_eq!(series.color, "red");
+        assert_eq!(
+            series.line.as_deref(),
+            Some("M56.0 252.0 L340.0 12.0 L624.0 132.0")
+        );
+        assert_eq!(
+            series.area.as_deref(),
+            Some("M56.0 252.0 L340.0 12.0 L624.0 132.0 L624.0 252.0 L56.0 252.0 Z")
+        );
+        assert_eq!(bottom, 252.0);
+        assert_eq!(layout.marks[1].tooltip, "-2s · RSS: 10 MiB");
+    }
+
+    #[test]
+    fn test_line_layout_thins_x_labels() {
+        let labels: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let spec = ChartSpec::new(ChartKind::Line)
+            .with_labels(labels)
+            .with_dataset(Dataset::new("n", (0..20).map(f64::from).collect()));
+        let layout = layout(&spec, &theme());
+
+        assert!(layout.x_ticks.len() <= MAX_X_TICKS);
+        assert_eq!(layout.x_ticks.last().unwrap().label, "19");
+        assert_eq!(layout.series[0].area, None);
+        assert_eq!(layout.marks.len(), 20);
+    }
+
+    #[test]
+    fn test_scatter_layout() {
+        let spec = ChartSpec::new(ChartKind::Scatter)
+            .with_dataset(Dataset::points("xy", vec![(0.0, 0.0), (10.0, 10.0)]));
+        let layout = layout(&spec, &theme());
+
+        let x_labels: Vec<&str> = layout.x_ticks.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(x_labels, vec!["0", "5", "10"]);
+        assert_eq!(layout.marks[0].x, MARGIN_LEFT);
+        assert_eq!(layout.marks[1].x, WIDTH - MARGIN_RIGHT);
+        assert_eq!(layout.marks[1].tooltip, "xy: (10, 10)");
+        assert_eq!(layout.series[0].line, None);
+    }
+
+    #[test]
+    fn test_empty_spec() {
+        let layout = layout(&ChartSpec::new(ChartKind::Line), &theme());
+        assert!(layout.marks.is_empty());
+        assert!(layout.x_ticks.is_empty());
+        assert_eq!(layout.y_ticks.len(), 3);
+    }
+
+    #[test]
+    fn test_svg_document() {
+        let spec = ChartSpec::new(ChartKind::Bar)
+            .with_labels(["a & b", "c"])
+            .with_dataset(Dataset::new("<Sales>", vec![1.0, 2.0]));
+        let doc = to_svg_document(&spec, &theme()).unwrap();
+
+        assert!(doc.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(doc.ends_with("</svg>"));
+        assert!(doc.contains(r#"fill="navy""#));
+        assert!(doc.contains("a &amp; b"));
+        assert!(doc.contains("&lt;Sales&gt;"));
+        assert_eq!(doc.matches("<rect").count(), 1 + 1 + 2);
+        assert!(doc.contains(r#"height="304""#));
+
+        let hidden = spec.with_options(ChartOptions {
+            show_legend: false,
+            ..ChartOptions::default()
+        });
+        let doc = to_svg_document(&hidden, &theme()).unwrap();
+        assert!(!doc.contains("Sales"));
+        assert!(doc.contains(r#"height="280""#));
+
+        assert!(to_svg_document(&ChartSpec::new(ChartKind::Pie), &theme()).is_none());
+    }
+}
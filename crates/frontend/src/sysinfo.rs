@@ -0,0 +1,292 @@
+//! Frontend mirrors of the backend system information structs.
+//!
+//! These structs mirror the JSON returned by the backend's system info exports
+//! for deserialization when communicating via IPC.
+
+use serde::Deserialize;
+
+/// Number of samples the backend keeps in its metrics history.
+pub const METRICS_HISTORY_CAPACITY: usize = 300;
+
+/// Usage (in percent) at or above which a filesystem is considered nearly full.
+pub const NEARLY_FULL_PERCENT: f64 = 90.0;
+
+/// Space and inode usage of one mounted filesystem.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MountUsage {
+    pub mount_point: String,
+    pub fs_type: String,
+    pub source: String,
+    #[serde(default)]
+    pub read_only: bool,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+    pub total_inodes: u64,
+    pub used_inodes: u64,
+    pub free_inodes: u64,
+}
+
+impl MountUsage {
+    /// Returns the percentage of space in use, counting reserved blocks as unavailable.
+    pub fn used_percent(&self) -> f64 {
+        let usable = self.used_bytes + self.free_bytes;
+        if usable == 0 {
+            0.0
+        } else {
+            self.used_bytes as f64 * 100.0 / usable as f64
+        }
+    }
+
+    /// Returns true if the filesystem is at or above [`NEARLY_FULL_PERCENT`].
+    pub fn is_nearly_full(&self) -> bool {
+        self.used_percent() >= NEARLY_FULL_PERCENT
+    }
+}
+
+/// Mounted filesystems plus the mount point holding the config directory.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StorageReport {
+    #[serde(default)]
+    pub mounts: Vec<MountUsage>,
+    #[serde(default)]
+    pub config_mount: Option<String>,
+}
+
+impl StorageReport {
+    /// Returns the mount holding the config directory, if it was found.
+    pub fn config_mount(&self) -> Option<&MountUsage> {
+        let mount_point = self.config_mount.as_deref()?;
+        self.mounts.iter().find(|m| m.mount_point == mount_point)
+    }
+}
+
+/// Basic platform information.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct SystemInfo {
+    pub platform: String,
+    pub arch: String,
+    pub family: String,
+}
+
+/// Distribution identity parsed from `/etc/os-release`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct OsRelease {
+    pub name: Option<String>,
+    pub pretty_name: Option<String>,
+    pub version: Option<String>,
+    pub version_id: Option<String>,
+    pub id: Option<String>,
+}
+
+impl OsRelease {
+    /// Returns the most descriptive name available.
+    pub fn display_name(&self) -> String {
+        self.pretty_name
+            .clone()
+            .or_else(|| match (&self.name, &self.version) {
+                (Some(name), Some(version)) => Some(format!("{} {}", name, version)),
+                (Some(name), None) => Some(name.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// OS identity and runtime environment of the running application.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EnvironmentInfo {
+    pub os: OsRelease,
+    pub kernel: String,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    /// One of "wayland", "x11", "tty" or "unknown".
+    pub session_type: String,
+    pub desktop: Option<String>,
+    /// One of "native", "flatpak", "snap" or "appimage".
+    pub packaging: String,
+    pub container: Option<String>,
+}
+
+/// Resource usage of one process in the app's process tree.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProcessStats {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub rss_bytes: u64,
+    pub cpu_time_ms: u64,
+    pub threads: u32,
+    pub open_fds: Option<u32>,
+}
+
+/// Resource usage of the whole process tree, with per-process details.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct ProcessTreeStats {
+    #[serde(default)]
+    pub processes: Vec<ProcessStats>,
+    pub total_rss_bytes: u64,
+    pub total_cpu_time_ms: u64,
+    pub total_threads: u32,
+    pub total_open_fds: u32,
+}
+
+/// One self-monitoring sample streamed from the backend.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MetricsSample {
+    pub timestamp_ms: u64,
+    pub process: ProcessTreeStats,
+    pub cpu_percent: Option<f64>,
+    pub wasm_memory_bytes: Option<u64>,
+    #[serde(default)]
+    pub sensors: Vec<SensorReading>,
+}
+
+/// One hardware sensor reading in display units.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SensorReading {
+    pub source: String,
+    pub chip: String,
+    pub label: String,
+    /// One of "temperature", "fan" or "voltage".
+    pub kind: String,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+impl SensorReading {
+    /// Formats a value of this sensor's kind with its unit.
+    pub fn format_value(&self, value: f64) -> String {
+        match self.kind.as_str() {
+            "temperature" => format!("{:.1} °C", value),
+            "fan" => format!("{:.0} RPM", value),
+            "voltage" => format!("{:.3} V", value),
+            _ => format!("{}", value),
+        }
+    }
+
+    /// Returns true if the reading is at or above its critical threshold.
+    pub fn is_critical(&self) -> bool {
+        self.critical.is_some_and(|crit| self.value >= crit)
+    }
+
+    /// Returns true if the reading is at or above its maximum threshold.
+    pub fn is_over_max(&self) -> bool {
+        self.max.is_some_and(|max| self.value >= max)
+    }
+}
+
+/// One downsampled point of a recorded metrics series.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HistoryPoint {
+    pub timestamp_ms: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub count: u32,
+}
+
+/// Result of a metrics history range query.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HistoryRange {
+    pub series: String,
+    /// One of "raw", "1m" or "1h".
+    pub resolution: String,
+    pub points: Vec<HistoryPoint>,
+}
+
+/// Formats how long ago something happened, e.g. `-5m`, `-3h` or `-2d`.
+pub fn format_ago(ms: u64) -> String {
+    const MINUTE: u64 = 60_000;
+    const HOUR: u64 = 60 * MINUTE;
+    match ms {
+        ms if ms < HOUR => format!("-{}m", ms / MINUTE),
+        ms if ms < 48 * HOUR => format!("-{}h", ms / HOUR),
+        ms => format!("-{}d", ms / (24 * HOUR)),
+    }
+}
+
+/// Formats a byte count using binary units, e.g. `1.5 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_os_release_display_name() {
+        let release = OsRelease {
+            name: Some("Fedora Linux".to_string()),
+            version: Some("40".to_string()),
+            ..OsRelease::default()
+        };
+        assert_eq!(release.display_name(), "Fedora Linux 40");
+        assert_eq!(OsRelease::default().display_name(), "unknown");
+    }
+
+    #[test]
+    fn test_sensor_reading_format_and_thresholds() {
+        let reading = SensorReading {
+            source: "hwmon0".to_string(),
+            chip: "coretemp".to_string(),
+            label: "Core 0".to_string(),
+            kind: "temperature".to_string(),
+            value: 85.25,
+            max: Some(80.0),
+            critical: Some(100.0),
+        };
+
+        assert_eq!(reading.format_value(reading.value), "85.2 °C");
+        assert!(reading.is_over_max());
+        assert!(!reading.is_critical());
+    }
+
+    #[test]
+    fn test_storage_report_config_mount() {
+        let json = r#"{
+            "mounts": [
+                {"mount_point": "/", "fs_type": "ext4", "source": "/dev/sda1",
+                 "total_bytes": 100, "used_bytes": 95, "free_bytes": 5,
+                 "total_inodes": 10, "used_inodes": 1, "free_inodes": 9}
+            ],
+            "config_mount": "/"
+        }"#;
+        let report: StorageReport = serde_json::from_str(json).unwrap();
+
+        let mount = report.config_mount().unwrap();
+        assert_eq!(mount.used_percent(), 95.0);
+        assert!(mount.is_nearly_full());
+    }
+
+    #[test]
+    fn test_format_ago() {
+        assert_eq!(format_ago(0), "-0m");
+        assert_eq!(format_ago(90_000), "-1m");
+        assert_eq!(format_ago(3 * 3_600_000), "-3h");
+        assert_eq!(format_ago(72 * 3_600_000), "-3d");
+    }
+}
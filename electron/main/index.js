@@ -1,4 +1,5 @@
-const { app, BrowserWindow, ipcMain } = require('electron');
+const { app, BrowserWindow, dialog, ipcMain, session, shell } = require('electron');
+const fs = require('node:fs');
 const path = require('node:path');
 
 // Suppress GPU VSync errors on Linux
@@ -18,6 +19,53 @@ try {
   rustBackend = null;
 }
 
+// In portable mode, or with APP_HOME or --data-dir set, the backend keeps all
+// files under one root; keep Electron's own profile there too. This has to
+// happen before the app is ready.
+let appPaths = null;
+try {
+  appPaths = JSON.parse(rustBackend?.getPaths() ?? 'null');
+} catch (err) {
+  console.warn('Could not read app directories:', err.message);
+}
+if (appPaths?.root) {
+  app.setPath('userData', path.join(appPaths.data, 'electron'));
+}
+
+// Only one instance may run, since instances would share the config and data
+// files. A later launch hands its arguments to the running instance and quits;
+// the running instance comes to the front and passes them to the renderer.
+const onSecondInstance = (json) => {
+  const windows = BrowserWindow.getAllWindows();
+  if (windows.length === 0) {
+    if (app.isReady()) {
+      createWindow();
+    }
+    return;
+  }
+  const [win] = windows;
+  if (win.isMinimized()) {
+    win.restore();
+  }
+  win.focus();
+  for (const w of windows) {
+    w.webContents.send('second-instance', json);
+  }
+};
+
+let isPrimaryInstance = true;
+try {
+  isPrimaryInstance =
+    rustBackend?.acquireSingleInstance(JSON.stringify(process.argv.slice(1)), onSecondInstance) ??
+    true;
+} catch (err) {
+  console.warn('Single-instance check failed:', err.message);
+}
+if (!isPrimaryInstance) {
+  console.log('Another instance is running; forwarded arguments to it');
+  app.exit(0);
+}
+
 // IPC handlers for Rust backend
 ipcMain.handle('rust-hello', async () => {
   if (rustBackend) {
@@ -33,19 +81,248 @@ ipcMain.handle('rust-add', async (event, a, b) => {
   return null;
 });
 
+// System info handlers
+ipcMain.handle('get-system-info', async () => {
+  return rustBackend?.getSystemInfo() ?? null;
+});
+
+ipcMain.handle('get-environment-info', async () => {
+  return rustBackend?.getEnvironmentInfo() ?? null;
+});
+
+ipcMain.handle('get-sensors', async () => {
+  return rustBackend?.getSensors() ?? '[]';
+});
+
+ipcMain.handle('get-storage-report', async (event, includePseudo) => {
+  return rustBackend?.getStorageReport(includePseudo) ?? '{"mounts":[],"config_mount":null}';
+});
+
+// Self-monitoring: sample the process tree periodically and stream it to all windows
+const METRICS_INTERVAL_MS = 2000;
+
+ipcMain.handle('get-metrics-history', async () => {
+  return rustBackend?.getMetricsHistory() ?? '[]';
+});
+
+ipcMain.handle('query-metrics-history', async (_event, series, fromMs, toMs, maxPoints) => {
+  return rustBackend?.queryMetricsHistory(series, fromMs, toMs, maxPoints) ?? null;
+});
+
+ipcMain.handle('report-wasm-memory', async (event, bytes) => {
+  rustBackend?.reportWasmMemory(event.sender.id, bytes);
+});
+
+const startMetricsStream = () => {
+  if (!rustBackend) {
+    return;
+  }
+  setInterval(() => {
+    const json = rustBackend.collectMetrics();
+    for (const win of BrowserWindow.getAllWindows()) {
+      win.webContents.send('metrics-sample', json);
+    }
+  }, METRICS_INTERVAL_MS);
+};
+
 // Config handlers
 ipcMain.handle('load-config', async () => {
   return rustBackend?.loadConfig() ?? '{}';
 });
 
-ipcMain.handle('save-config', async (event, json) => {
-  return rustBackend?.saveConfig(json) ?? false;
+// Resolves to {status: 'saved', revision}, {status: 'conflict', revision, config}
+// or {status: 'failed', error} as JSON
+ipcMain.handle('save-config', async (event, json, expectedRevision) => {
+  return (
+    rustBackend?.saveConfig(json, expectedRevision ?? null) ??
+    '{"status":"failed","error":"Neon backend not loaded"}'
+  );
+});
+
+// Change only some settings: a JSON Merge Patch, or one value at a JSON
+// Pointer. Both resolve to the same outcome JSON as save-config.
+ipcMain.handle('patch-config', async (event, patchJson, expectedRevision) => {
+  return (
+    rustBackend?.patchConfig(patchJson, expectedRevision ?? null) ??
+    '{"status":"failed","error":"Neon backend not loaded"}'
+  );
+});
+
+ipcMain.handle('get-config-value', async (event, pointer) => {
+  return rustBackend?.getConfigValue(pointer) ?? null;
+});
+
+ipcMain.handle('set-config-value', async (event, pointer, valueJson, expectedRevision) => {
+  return (
+    rustBackend?.setConfigValue(pointer, valueJson, expectedRevision ?? null) ??
+    '{"status":"failed","error":"Neon backend not loaded"}'
+  );
 });
 
 ipcMain.handle('get-config-path', async () => {
   return rustBackend?.getConfigPath() ?? 'unknown';
 });
 
+ipcMain.handle('get-config-schema', async () => {
+  return rustBackend?.getConfigSchema() ?? 'null';
+});
+
+ipcMain.handle('get-paths', async () => {
+  return rustBackend?.getPaths() ?? 'null';
+});
+
+ipcMain.handle('get-cache-usage', async () => {
+  return rustBackend?.getCacheUsage() ?? 'null';
+});
+
+// Clears Chromium's HTTP cache along with the backend's
+ipcMain.handle('clear-cache', async () => {
+  await session.defaultSession.clearCache();
+  return rustBackend?.clearCache() ?? 'null';
+});
+
+// Pick up config changes made outside the app, e.g. by the CLI. Polling
+// (rather than fs.watch) keeps working when the file is replaced by a
+// rename or doesn't exist yet. Converting the config to another format
+// changes its path, so the new file is watched from then on.
+const CONFIG_POLL_INTERVAL_MS = 1000;
+
+const watchConfig = () => {
+  const configPath = rustBackend?.getConfigPath();
+  if (!configPath || configPath === 'unknown') {
+    return;
+  }
+  fs.watchFile(configPath, { interval: CONFIG_POLL_INTERVAL_MS }, (curr, prev) => {
+    if (curr.mtimeMs === prev.mtimeMs) {
+      return;
+    }
+    // null when nothing changed, e.g. after the app's own save, or when the
+    // file can't be read; the current settings stay. A conflict with
+    // settings the app has yet to write is sent on for the Settings card.
+    const json = rustBackend.reloadConfig();
+    if (json !== null) {
+      for (const win of BrowserWindow.getAllWindows()) {
+        win.webContents.send('config-changed', json);
+      }
+    }
+    if (rustBackend.getConfigPath() !== configPath) {
+      fs.unwatchFile(configPath);
+      watchConfig();
+    }
+  });
+};
+
+// Chart export: ask where to save, then let the backend write the file
+const EXPORT_FILTERS = {
+  png: { name: 'PNG image', extensions: ['png'] },
+  svg: { name: 'SVG image', extensions: ['svg'] },
+  csv: { name: 'CSV data', extensions: ['csv'] },
+};
+
+ipcMain.handle('export-chart', async (event, format, payload, defaultName) => {
+  if (!rustBackend || !EXPORT_FILTERS[format]) {
+    return null;
+  }
+  const { canceled, filePath } = await dialog.showSaveDialog(
+    BrowserWindow.fromWebContents(event.sender),
+    {
+      defaultPath: path.join(app.getPath('documents'), `${defaultName}.${format}`),
+      filters: [EXPORT_FILTERS[format]],
+    },
+  );
+  if (canceled || !filePath) {
+    return null;
+  }
+  return rustBackend.exportFile(filePath, format, payload);
+});
+
+ipcMain.handle('get-recent-exports', async () => {
+  return rustBackend?.getRecentExports() ?? '[]';
+});
+
+ipcMain.handle('show-in-folder', async (_event, filePath) => {
+  shell.showItemInFolder(filePath);
+});
+
+// Renderer logs are tagged with the sending window's id, like metrics
+ipcMain.on('renderer-logs', (event, json) => {
+  try {
+    rustBackend?.writeRendererLogs(json, event.sender.id);
+  } catch (e) {
+    console.error('Dropped renderer logs:', e.message);
+  }
+});
+
+// Support bundle: versions only the main process knows are passed to the backend
+const runtimeVersions = () => JSON.stringify({
+  app: app.getVersion(),
+  electron: process.versions.electron,
+  chrome: process.versions.chrome,
+  node: process.versions.node,
+});
+
+ipcMain.handle('preview-support-bundle', async () => {
+  return rustBackend?.previewSupportBundle(runtimeVersions()) ?? '[]';
+});
+
+ipcMain.handle('create-support-bundle', async (event) => {
+  if (!rustBackend) {
+    return null;
+  }
+  const date = new Date().toISOString().slice(0, 10);
+  const { canceled, filePath } = await dialog.showSaveDialog(
+    BrowserWindow.fromWebContents(event.sender),
+    {
+      defaultPath: path.join(app.getPath('documents'), `support-bundle-${date}.zip`),
+      filters: [{ name: 'Zip archive', extensions: ['zip'] }],
+    },
+  );
+  if (canceled || !filePath) {
+    return null;
+  }
+  return rustBackend.createSupportBundle(filePath, runtimeVersions());
+});
+
+// Panics in a renderer's WASM code are recorded as crashes by the backend
+ipcMain.handle('report-crash', async (event, json) => {
+  return rustBackend?.writeRendererCrash(json, event.sender.id) ?? null;
+});
+
+// Log viewer: pages are read on request; new records are pushed to
+// subscribed windows through a backend subscription per window
+ipcMain.handle('read-logs', async (_event, queryJson) => {
+  return rustBackend?.readLogs(queryJson) ?? '{"records":[],"next":null}';
+});
+
+const logSubscriptions = new Map();
+
+const unsubscribeLogs = (webContentsId) => {
+  const subscription = logSubscriptions.get(webContentsId);
+  if (subscription !== undefined) {
+    rustBackend?.unsubscribeLogs(subscription);
+    logSubscriptions.delete(webContentsId);
+  }
+};
+
+ipcMain.handle('subscribe-logs', async (event) => {
+  const sender = event.sender;
+  if (!rustBackend || logSubscriptions.has(sender.id)) {
+    return;
+  }
+  const webContentsId = sender.id;
+  const subscription = rustBackend.subscribeLogs((json) => {
+    if (!sender.isDestroyed()) {
+      sender.send('log-record', json);
+    }
+  });
+  logSubscriptions.set(webContentsId, subscription);
+  sender.once('destroyed', () => unsubscribeLogs(webContentsId));
+});
+
+ipcMain.handle('unsubscribe-logs', async (event) => {
+  unsubscribeLogs(event.sender.id);
+});
+
 const createWindow = () => {
   const win = new BrowserWindow({
     width: 900,
@@ -59,6 +336,10 @@ const createWindow = () => {
     backgroundColor: '#1a1a2e',
   });
 
+  // Drop this window's WASM memory from the metrics once it's gone
+  const webContentsId = win.webContents.id;
+  win.on('closed', () => rustBackend?.forgetWindow(webContentsId));
+
   // Load the Trunk-built WASM app
   win.loadFile(path.join(__dirname, '../renderer/index.html'));
 
@@ -70,6 +351,8 @@ const createWindow = () => {
 
 app.whenReady().then(() => {
   createWindow();
+  startMetricsStream();
+  watchConfig();
 
   app.on('activate', () => {
     if (BrowserWindow.getAllWindows().length === 0) {
@@ -78,6 +361,15 @@ app.whenReady().then(() => {
   });
 });
 
+app.on('will-quit', () => {
+  try {
+    rustBackend?.flushConfig();
+  } catch (e) {
+    console.error('Failed to save pending config changes:', e.message);
+  }
+  rustBackend?.releaseSingleInstance();
+});
+
 app.on('window-all-closed', () => {
   if (process.platform !== 'darwin') {
     app.quit();
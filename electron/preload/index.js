@@ -8,10 +8,82 @@ contextBridge.exposeInMainWorld('electronAPI', {
   // Call the Rust add function via IPC
   rustAdd: (a, b) => ipcRenderer.invoke('rust-add', a, b),
 
+  // System info functions
+  getSystemInfo: () => ipcRenderer.invoke('get-system-info'),
+  getEnvironmentInfo: () => ipcRenderer.invoke('get-environment-info'),
+  getSensors: () => ipcRenderer.invoke('get-sensors'),
+  getStorageReport: (includePseudo) => ipcRenderer.invoke('get-storage-report', includePseudo),
+
+  // Self-monitoring functions
+  getMetricsHistory: () => ipcRenderer.invoke('get-metrics-history'),
+  queryMetricsHistory: (series, fromMs, toMs, maxPoints) =>
+    ipcRenderer.invoke('query-metrics-history', series, fromMs, toMs, maxPoints),
+  reportWasmMemory: (bytes) => ipcRenderer.invoke('report-wasm-memory', bytes),
+  // Subscribe to metrics samples; returns a function that unsubscribes
+  onMetricsSample: (callback) => {
+    const listener = (_event, json) => callback(json);
+    ipcRenderer.on('metrics-sample', listener);
+    return () => ipcRenderer.removeListener('metrics-sample', listener);
+  },
+
+  // Subscribe to later launches of the app, which forward their arguments
+  // here; returns a function that unsubscribes
+  onSecondInstance: (callback) => {
+    const listener = (_event, json) => callback(json);
+    ipcRenderer.on('second-instance', listener);
+    return () => ipcRenderer.removeListener('second-instance', listener);
+  },
+
   // Config functions
   loadConfig: () => ipcRenderer.invoke('load-config'),
-  saveConfig: (json) => ipcRenderer.invoke('save-config', json),
+  // Saving with the revision from loadConfig fails with a conflict if the
+  // config was changed elsewhere in the meantime
+  saveConfig: (json, expectedRevision) =>
+    ipcRenderer.invoke('save-config', json, expectedRevision),
+  patchConfig: (patchJson, expectedRevision) =>
+    ipcRenderer.invoke('patch-config', patchJson, expectedRevision),
+  getConfigValue: (pointer) => ipcRenderer.invoke('get-config-value', pointer),
+  setConfigValue: (pointer, valueJson, expectedRevision) =>
+    ipcRenderer.invoke('set-config-value', pointer, valueJson, expectedRevision),
   getConfigPath: () => ipcRenderer.invoke('get-config-path'),
+  getConfigSchema: () => ipcRenderer.invoke('get-config-schema'),
+  getPaths: () => ipcRenderer.invoke('get-paths'),
+  getCacheUsage: () => ipcRenderer.invoke('get-cache-usage'),
+  clearCache: () => ipcRenderer.invoke('clear-cache'),
+  // Subscribe to config changes made outside the app; returns a function that unsubscribes
+  onConfigChanged: (callback) => {
+    const listener = (_event, json) => callback(json);
+    ipcRenderer.on('config-changed', listener);
+    return () => ipcRenderer.removeListener('config-changed', listener);
+  },
+
+  // Chart export functions; exportChart resolves to null if the user cancels
+  exportChart: (format, payload, defaultName) =>
+    ipcRenderer.invoke('export-chart', format, payload, defaultName),
+  getRecentExports: () => ipcRenderer.invoke('get-recent-exports'),
+  showInFolder: (filePath) => ipcRenderer.invoke('show-in-folder', filePath),
+
+  // Support bundle functions; createSupportBundle resolves to null if the user cancels
+  previewSupportBundle: () => ipcRenderer.invoke('preview-support-bundle'),
+  createSupportBundle: () => ipcRenderer.invoke('create-support-bundle'),
+
+  // Batched log entries for the backend log file; fire-and-forget
+  sendLogs: (json) => ipcRenderer.send('renderer-logs', json),
+  // Report a WASM panic; resolves to the crash record path, or null
+  reportCrash: (json) => ipcRenderer.invoke('report-crash', json),
+
+  // Log viewer functions
+  readLogs: (queryJson) => ipcRenderer.invoke('read-logs', queryJson),
+  // Subscribe to new log records; returns a function that unsubscribes
+  onLogRecord: (callback) => {
+    const listener = (_event, json) => callback(json);
+    ipcRenderer.on('log-record', listener);
+    ipcRenderer.invoke('subscribe-logs');
+    return () => {
+      ipcRenderer.removeListener('log-record', listener);
+      ipcRenderer.invoke('unsubscribe-logs');
+    };
+  },
 });
 
 console.log('Preload script loaded - electronAPI exposed');